@@ -0,0 +1,312 @@
+//! Execution of the external tools behind each pipeline stage
+
+use crate::error::{CircomkitError, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Output};
+use std::sync::{Arc, Mutex};
+
+/// Pipeline stage an external tool runs for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// circom compiling a circuit
+    Compile,
+    /// The witness calculator generating a witness
+    Witness,
+    /// `snarkjs wtns export json` reading a witness's values
+    ExportWitness,
+    /// snarkjs generating or exporting keys
+    Setup,
+    /// snarkjs generating a proof
+    Prove,
+    /// snarkjs verifying a proof
+    Verify,
+    /// Any other command, e.g. `snarkjs r1cs info` or `circom --version`
+    Other,
+}
+
+/// One run of an external tool
+#[derive(Debug)]
+pub struct Invocation<'a> {
+    /// Stage the tool runs for
+    pub stage: Stage,
+    /// The command, with its arguments and environment set
+    pub command: &'a mut Command,
+    /// Files the stage is expected to write
+    pub outputs: &'a [PathBuf],
+}
+
+/// Runs the external tools of the pipeline
+///
+/// [`CommandBackend`] spawns circom, snarkjs, and node; [`MockBackend`]
+/// stands in for them in tests. Install a backend with
+/// [`Circomkit::with_backend`](crate::Circomkit::with_backend).
+pub trait Backend: Send + Sync + std::fmt::Debug {
+    /// Run the invocation, failing with `CommandFailed` on a nonzero exit
+    fn run(&self, invocation: Invocation<'_>) -> Result<Output>;
+
+    /// Whether the installed circom and snarkjs versions should be checked
+    fn checks_versions(&self) -> bool {
+        true
+    }
+}
+
+/// Shares a backend, e.g. to inspect [`MockBackend::calls`] after
+/// installing it
+impl<B: Backend + ?Sized> Backend for Arc<B> {
+    fn run(&self, invocation: Invocation<'_>) -> Result<Output> {
+        (**self).run(invocation)
+    }
+
+    fn checks_versions(&self) -> bool {
+        (**self).checks_versions()
+    }
+}
+
+/// Backend spawning the real tools
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandBackend;
+
+impl Backend for CommandBackend {
+    fn run(&self, invocation: Invocation<'_>) -> Result<Output> {
+        debug!("Running: {:?}", invocation.command);
+        run_command(invocation.command)
+    }
+}
+
+/// Backend returning canned results instead of running any tool
+///
+/// Every stage succeeds with empty output unless configured otherwise.
+/// Files a stage is expected to write are created, empty or with contents
+/// from [`MockBackend::with_file`], so later stages find them.
+///
+/// # Example
+///
+/// ```
+/// use circomkit::{Circomkit, CircomkitConfig, MockBackend, Stage};
+///
+/// let backend = MockBackend::new()
+///     .with_file(Stage::ExportWitness, ".json", r#"["1", "6"]"#)
+///     .with_failure(Stage::Verify, "[ERROR] snarkJS: Invalid proof");
+/// let circomkit = Circomkit::new(CircomkitConfig::default())
+///     .unwrap()
+///     .with_backend(backend);
+/// ```
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    stdout: HashMap<Stage, String>,
+    failures: HashMap<Stage, String>,
+    /// Contents written to outputs whose file name ends with a suffix
+    files: HashMap<Stage, Vec<(String, Vec<u8>)>>,
+    calls: Mutex<Vec<Stage>>,
+}
+
+impl MockBackend {
+    /// Create a mock where every stage succeeds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print `stdout` when `stage` runs
+    pub fn with_stdout(mut self, stage: Stage, stdout: impl Into<String>) -> Self {
+        self.stdout.insert(stage, stdout.into());
+        self
+    }
+
+    /// Fail `stage` with exit status 1 and `stderr`
+    pub fn with_failure(mut self, stage: Stage, stderr: impl Into<String>) -> Self {
+        self.failures.insert(stage, stderr.into());
+        self
+    }
+
+    /// Write `contents` to outputs of `stage` whose file name ends with
+    /// `suffix`, e.g. `"public.json"`
+    pub fn with_file(
+        mut self,
+        stage: Stage,
+        suffix: impl Into<String>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.files
+            .entry(stage)
+            .or_default()
+            .push((suffix.into(), contents.into()));
+        self
+    }
+
+    /// Stages run so far, in order
+    pub fn calls(&self) -> Vec<Stage> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+}
+
+impl Backend for MockBackend {
+    fn run(&self, invocation: Invocation<'_>) -> Result<Output> {
+        let stage = invocation.stage;
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(stage);
+        }
+
+        if let Some(stderr) = self.failures.get(&stage) {
+            return Err(CircomkitError::CommandFailed {
+                command: invocation
+                    .command
+                    .get_program()
+                    .to_string_lossy()
+                    .to_string(),
+                exit_code: 1,
+                stderr: stderr.clone(),
+            });
+        }
+
+        let canned = self.files.get(&stage).map(Vec::as_slice).unwrap_or(&[]);
+        for path in invocation.outputs {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let contents = canned
+                .iter()
+                .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+                .map_or(&[][..], |(_, contents)| contents.as_slice());
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)?;
+        }
+
+        Ok(Output {
+            status: ExitStatus::default(),
+            stdout: self
+                .stdout
+                .get(&stage)
+                .cloned()
+                .unwrap_or_default()
+                .into_bytes(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn checks_versions(&self) -> bool {
+        false
+    }
+}
+
+/// Run `cmd` to completion
+///
+/// A missing program is [`CircomkitError::ToolNotFound`] and a non-zero exit
+/// is [`CircomkitError::CommandFailed`] carrying stderr, or stdout when
+/// stderr is empty since snarkjs logs its errors there.
+pub(crate) fn run_command(cmd: &mut Command) -> Result<Output> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let output = cmd.output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            CircomkitError::tool_not_found(&program)
+        } else {
+            CircomkitError::Io(e)
+        }
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = if stderr.trim().is_empty() {
+            String::from_utf8_lossy(&output.stdout)
+        } else {
+            stderr
+        };
+        return Err(CircomkitError::CommandFailed {
+            command: program,
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: message.to_string(),
+        });
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Circomkit, CircomkitConfig};
+    use crate::types::{CircuitConfig, CircuitSignals, Protocol, SignalValue};
+
+    #[tokio::test]
+    async fn test_mock_backend_pipeline() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("adder.circom"),
+            "pragma circom 2.0.0;\ntemplate Adder() { signal input a; signal output c; c <== a; }\n",
+        )
+        .unwrap();
+        let ptau = dir.path().join("test.ptau");
+        std::fs::write(&ptau, b"").unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(dir.path())
+            .with_build_dir(dir.path().join("build"))
+            // Groth16 may verify natively, without the backend
+            .with_protocol(Protocol::Plonk);
+
+        let backend = Arc::new(
+            MockBackend::new()
+                .with_file(Stage::Prove, "proof.json", r#"{"A":[],"protocol":"plonk"}"#)
+                .with_file(Stage::Prove, "public.json", r#"["7"]"#),
+        );
+        let circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(backend.clone());
+        let circuit = CircuitConfig::new("adder")
+            .with_file("adder.circom")
+            .with_template("Adder");
+
+        let artifacts = circomkit.compile(&circuit).await.unwrap();
+        assert!(artifacts.files_exist());
+        // The mock's empty r1cs can't be parsed, so skip input validation
+        std::fs::remove_file(&artifacts.sym).unwrap();
+        circomkit.setup(&circuit, &ptau).await.unwrap();
+
+        let inputs = CircuitSignals::from([("a".to_string(), SignalValue::single(7))]);
+        let (proof, public) = circomkit.prove(&circuit, &inputs).await.unwrap();
+        assert_eq!(public.0, vec!["7".to_string()]);
+        assert!(circomkit.verify(&circuit, &proof, &public).await.unwrap());
+        assert_eq!(
+            backend.calls(),
+            [
+                Stage::Compile,
+                Stage::Setup,
+                Stage::Setup,
+                Stage::Witness,
+                Stage::Prove,
+                Stage::Verify
+            ]
+        );
+
+        let circomkit = Circomkit::new(config).unwrap().with_backend(
+            MockBackend::new().with_failure(Stage::Verify, "[ERROR] snarkJS: Invalid proof"),
+        );
+        assert!(!circomkit.verify(&circuit, &proof, &public).await.unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command_errors() {
+        match run_command(&mut Command::new("/nonexistent/tool")) {
+            Err(CircomkitError::ToolNotFound { tool }) => assert_eq!(tool, "/nonexistent/tool"),
+            other => panic!("Expected ToolNotFound, got {:?}", other),
+        }
+
+        // stdout stands in for an empty stderr
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo oops; exit 3"]);
+        match run_command(&mut cmd) {
+            Err(CircomkitError::CommandFailed {
+                exit_code, stderr, ..
+            }) => {
+                assert_eq!(exit_code, 3);
+                assert_eq!(stderr.trim(), "oops");
+            }
+            other => panic!("Expected CommandFailed, got {:?}", other),
+        }
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo ok"]);
+        assert_eq!(run_command(&mut cmd).unwrap().stdout, b"ok\n");
+    }
+}
@@ -1,16 +1,37 @@
 //! Main Circomkit implementation
 
 use crate::core::CircomkitConfig;
+use crate::core::backend::{Backend, CommandBackend, Invocation, Stage};
 use crate::error::{CircomkitError, Result};
+#[cfg(feature = "native-verify")]
+use crate::types::Prime;
 use crate::types::{
-    CircuitArtifacts, CircuitConfig, CircuitInfo, CircuitSignals, Proof, PublicSignals,
-    VerificationKey, Witness,
+    ArtifactFiles, CircuitArtifacts, CircuitConfig, CircuitInfo, CircuitSignals,
+    CompatibilityWarning, FullCheckReport, InfoDelta, Proof, ProofFailureKind, ProofTestResult,
+    Protocol, PublicSignals, SignalValue, StageTimings, ToolReport, VerificationKey, Witness,
+    WitnessTestResult,
 };
-use log::{debug, info};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use crate::utils::{
+    Constraint, MAX_LISTED_CONSTRAINTS, SymbolTable, fetch_circomlib, normalize_signals,
+    parse_inputs, ptau_dir, read_ptau_header, read_r1cs_constraints, read_r1cs_header,
+    read_symbol_table, signals_from_json,
+};
+use log::{debug, info, warn};
+use num_bigint::{BigUint, RandBigInt};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
 use tokio::fs;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 /// Main Circomkit instance for circuit testing and development
 #[derive(Debug)]
@@ -19,6 +40,27 @@ pub struct Circomkit {
     config: CircomkitConfig,
     /// Loaded circuit configurations
     circuits: HashMap<String, CircuitConfig>,
+    /// Wasm paths found by `compile`, keyed by circuit name
+    wasm_paths: Mutex<HashMap<String, PathBuf>>,
+    /// Programs whose version has been checked by `run_tool`
+    checked_tools: Mutex<HashSet<String>>,
+    /// Installed versions of circom and snarkjs, keyed by program
+    tool_versions: Mutex<HashMap<String, Option<String>>>,
+    /// Per-circuit locks held while compiling, keyed by circuit name
+    compile_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Runs the external tools
+    backend: Arc<dyn Backend>,
+    /// Preprocesses inputs before witness generation
+    input_hook: Option<InputHook>,
+}
+
+/// Function transforming a circuit's inputs before each witness
+struct InputHook(Arc<dyn Fn(&mut CircuitSignals) + Send + Sync>);
+
+impl std::fmt::Debug for InputHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("InputHook")
+    }
 }
 
 impl Circomkit {
@@ -28,9 +70,60 @@ impl Circomkit {
         Ok(Self {
             config,
             circuits: HashMap::new(),
+            wasm_paths: Mutex::new(HashMap::new()),
+            checked_tools: Mutex::new(HashSet::new()),
+            tool_versions: Mutex::new(HashMap::new()),
+            compile_locks: Mutex::new(HashMap::new()),
+            backend: Arc::new(CommandBackend),
+            input_hook: None,
         })
     }
 
+    /// Run external tools through `backend` instead of spawning them
+    ///
+    /// A [`MockBackend`](crate::MockBackend) lets code using circomkit be
+    /// tested without circom or snarkjs installed. `doctor`, PTAU
+    /// generation, and circomlib fetching always use the real tools.
+    pub fn with_backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backend = Arc::new(backend);
+        self
+    }
+
+    /// Run `hook` on the inputs of every witness, e.g. to derive a Merkle
+    /// root or other computed inputs in one place
+    ///
+    /// Applies to `generate_witness`, `generate_witness_json`,
+    /// `generate_witnesses`, and so to `prove` and the testers; input files given to
+    /// `generate_witness_from_file` are passed through unchanged.
+    pub fn with_input_hook(
+        mut self,
+        hook: impl Fn(&mut CircuitSignals) + Send + Sync + 'static,
+    ) -> Self {
+        self.set_input_hook(hook);
+        self
+    }
+
+    /// Set the hook of [`Circomkit::with_input_hook`] in place
+    pub(crate) fn set_input_hook(
+        &mut self,
+        hook: impl Fn(&mut CircuitSignals) + Send + Sync + 'static,
+    ) {
+        self.input_hook = Some(InputHook(Arc::new(hook)));
+    }
+
+    /// A Circomkit using `config` with this instance's backend, input hook,
+    /// and loaded circuits
+    pub(crate) fn reconfigured(&self, config: CircomkitConfig) -> Result<Self> {
+        let mut circomkit = Self::new(config)?;
+        circomkit.circuits = self.circuits.clone();
+        circomkit.backend = Arc::clone(&self.backend);
+        circomkit.input_hook = self
+            .input_hook
+            .as_ref()
+            .map(|InputHook(hook)| InputHook(Arc::clone(hook)));
+        Ok(circomkit)
+    }
+
     /// Create a new Circomkit instance with default configuration
     pub fn with_defaults() -> Result<Self> {
         Self::new(CircomkitConfig::default())
@@ -58,6 +151,26 @@ impl Circomkit {
         Ok(())
     }
 
+    /// Write the circuit configurations back to the circuits.json file
+    pub async fn save_circuits(&self) -> Result<()> {
+        self.save_circuits_to(&self.config.circuits).await
+    }
+
+    /// Write the circuit configurations to `path` as pretty JSON
+    ///
+    /// Circuits are sorted by name so the output is stable.
+    pub async fn save_circuits_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let sorted: BTreeMap<_, _> = self.circuits.iter().collect();
+        fs::write(path, serde_json::to_string_pretty(&sorted)?).await?;
+        info!("Saved {} circuit configurations", self.circuits.len());
+        Ok(())
+    }
+
     /// Add a circuit configuration
     pub fn add_circuit(&mut self, config: CircuitConfig) {
         self.circuits.insert(config.name.clone(), config);
@@ -69,91 +182,408 @@ impl Circomkit {
     }
 
     /// Compile a circuit
+    ///
+    /// Circuits sharing a name share a main component and build directory,
+    /// so compiles of the same name are run one at a time.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(circuit = %circuit.name))
+    )]
     pub async fn compile(&self, circuit: &CircuitConfig) -> Result<CircuitArtifacts> {
         info!("Compiling circuit: {}", circuit.name);
+        circuit.validate()?;
+        let _guard = self.compile_lock(&circuit.name).await;
 
         // Ensure build directory exists
         let build_dir = self.config.build_path(&circuit.name);
         fs::create_dir_all(&build_dir).await?;
 
-        // Generate main component if needed
+        // Build circom command
+        let (circom, args) = self.compile_command(circuit).await?;
+        let outputs: Vec<PathBuf> = [
+            (
+                self.config.emit_r1cs,
+                build_dir.join(format!("{}.r1cs", circuit.name)),
+            ),
+            (
+                self.config.emit_wasm,
+                build_dir
+                    .join(format!("{}_js", circuit.name))
+                    .join(format!("{}.wasm", circuit.name)),
+            ),
+            (
+                self.config.emit_sym,
+                build_dir.join(format!("{}.sym", circuit.name)),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, path)| enabled.then_some(path))
+        .collect();
+        // Drop the stamp first, so a failed compile never looks current
+        let stamp_path = self.compile_stamp_path(circuit);
+        let _ = fs::remove_file(&stamp_path).await;
+        let output = self.run_tool(Stage::Compile, &circom, &args, &outputs)?;
+        fs::write(&stamp_path, self.compile_stamp(&circom, &args).await).await?;
+
+        info!("Circuit compiled successfully: {}", circuit.name);
+
+        // Warnings go to stderr, but some circom versions print them to stdout
+        let warnings: Vec<String> = [&output.stdout, &output.stderr]
+            .into_iter()
+            .flat_map(|out| compiler_warnings(&String::from_utf8_lossy(out)))
+            .collect();
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+
+        let wasm = find_wasm(&build_dir, &circuit.name);
+        if let Ok(mut paths) = self.wasm_paths.lock() {
+            paths.insert(circuit.name.clone(), wasm.clone());
+        }
+
+        Ok(CircuitArtifacts {
+            r1cs: build_dir.join(format!("{}.r1cs", circuit.name)),
+            wasm,
+            sym: build_dir.join(format!("{}.sym", circuit.name)),
+            pkey: None,
+            vkey: None,
+            warnings,
+        })
+    }
+
+    /// Wait for other compiles of the circuit named `name` to finish
+    ///
+    /// Same-name circuits with different templates or parameters would
+    /// otherwise overwrite each other's main component while circom reads it.
+    async fn compile_lock(&self, name: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = match self.compile_locks.lock() {
+            Ok(mut locks) => Arc::clone(locks.entry(name.to_string()).or_default()),
+            Err(_) => Arc::default(),
+        };
+        lock.lock_owned().await
+    }
+
+    /// Program and arguments `compile` runs for the circuit, without running it
+    ///
+    /// Generates the main component, since the command refers to it.
+    pub async fn compile_command(&self, circuit: &CircuitConfig) -> Result<(String, Vec<String>)> {
+        let build_dir = self.config.build_path(&circuit.name);
+        self.compile_command_in(circuit, &build_dir, false).await
+    }
+
+    /// Program and arguments compiling the circuit into `out_dir`
+    ///
+    /// With `r1cs_only`, only the r1cs is emitted, ignoring the `emit_*`
+    /// options and the `--json`, `--wat`, and `--inspect` toggles.
+    async fn compile_command_in(
+        &self,
+        circuit: &CircuitConfig,
+        out_dir: &Path,
+        r1cs_only: bool,
+    ) -> Result<(String, Vec<String>)> {
         let main_path = self.generate_main_component(circuit).await?;
+        self.compile_args(circuit, &main_path, out_dir, r1cs_only)
+            .await
+    }
 
-        // Build circom command
-        let circom = self.config.circom_command();
-        let mut cmd = Command::new(&circom);
-
-        cmd.arg(&main_path)
-            .arg("--r1cs")
-            .arg("--wasm")
-            .arg("--sym")
-            .arg("-o")
-            .arg(&build_dir)
-            .arg("-p")
-            .arg(self.config.prime.to_string())
-            .arg(format!("--O{}", self.config.optimization));
+    /// Program and arguments compiling the main component at `main_path`,
+    /// without generating it
+    async fn compile_args(
+        &self,
+        circuit: &CircuitConfig,
+        main_path: &Path,
+        out_dir: &Path,
+        r1cs_only: bool,
+    ) -> Result<(String, Vec<String>)> {
+        let mut args = vec![path_arg(main_path)];
+        let targets = [
+            (self.config.emit_r1cs || r1cs_only, "--r1cs"),
+            (self.config.emit_wasm && !r1cs_only, "--wasm"),
+            (self.config.emit_sym && !r1cs_only, "--sym"),
+        ];
+        for (enabled, flag) in targets {
+            if enabled {
+                args.push(flag.to_string());
+            }
+        }
+        args.extend([
+            "-o".to_string(),
+            path_arg(out_dir),
+            "-p".to_string(),
+            circuit.prime.unwrap_or(self.config.prime).to_string(),
+            format!(
+                "--O{}",
+                circuit.optimization.unwrap_or(self.config.optimization)
+            ),
+        ]);
 
         // Add include paths
-        for include in &self.config.include {
-            cmd.arg("-l").arg(include);
+        for include in self.include_paths(circuit).await? {
+            args.push("-l".to_string());
+            args.push(path_arg(&include));
+        }
+
+        let toggles = [
+            (self.config.emit_json, "--json"),
+            (self.config.emit_wat, "--wat"),
+            (self.config.inspect, "--inspect"),
+        ];
+        for (enabled, flag) in toggles {
+            if enabled && !r1cs_only {
+                args.push(flag.to_string());
+            }
         }
+        args.extend(self.config.circom_flags.iter().cloned());
 
-        debug!("Running: {:?}", cmd);
+        Ok((self.config.circom_command(), args))
+    }
 
-        let output = cmd.output().map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                CircomkitError::tool_not_found(&circom)
-            } else {
-                CircomkitError::Io(e)
+    /// Include paths passed to circom for the circuit
+    ///
+    /// Configured include paths come first, then the directory of the
+    /// circuit's source file so its relative includes resolve from the
+    /// generated main component, followed by circomlib from git or from a
+    /// local directory. Fails if the circuit includes `circomlib/...` but no
+    /// circomlib could be found.
+    async fn include_paths(&self, circuit: &CircuitConfig) -> Result<Vec<PathBuf>> {
+        let mut paths = self.config.include.clone();
+
+        if let Some(source_dir) = self.source_path(circuit).parent()
+            && !source_dir.as_os_str().is_empty()
+            && !paths.iter().any(|path| path == source_dir)
+        {
+            paths.push(source_dir.to_path_buf());
+        }
+
+        if let Some(dep) = &self.config.circomlib_git {
+            paths.push(fetch_circomlib(dep, &self.config.cache_dir()).await?);
+        }
+
+        match self.config.resolve_circomlib() {
+            Some(circomlib) => {
+                // <root>/circomlib/circuits -> <root>, for `include "circomlib/..."`
+                if let Some(root) = circomlib.parent().and_then(Path::parent) {
+                    paths.push(root.to_path_buf());
+                }
+                paths.push(circomlib);
             }
-        })?;
+            None if self.config.circomlib_git.is_none()
+                && self.includes_circomlib(circuit).await? =>
+            {
+                return Err(CircomkitError::InvalidConfig(format!(
+                    "circuit '{}' includes circomlib, but circomlib could not be found; \
+                     install it into node_modules or set it with `with_circomlib`",
+                    circuit.name
+                )));
+            }
+            None => {}
+        }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: circom,
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
-            });
+        Ok(paths)
+    }
+
+    /// Path to the circuit's source file
+    fn source_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        circuit
+            .absolute_file
+            .clone()
+            .unwrap_or_else(|| self.config.circuit_path(&circuit.file))
+    }
+
+    /// Whether the circuit's source file includes `circomlib/...`
+    async fn includes_circomlib(&self, circuit: &CircuitConfig) -> Result<bool> {
+        let path = self.source_path(circuit);
+        if !path.exists() {
+            return Ok(false);
         }
 
-        info!("Circuit compiled successfully: {}", circuit.name);
+        let source = fs::read_to_string(&path).await?;
+        Ok(source
+            .lines()
+            .filter_map(|line| parse_include(line.trim()))
+            .any(|include| include.starts_with("circomlib/")))
+    }
 
-        Ok(CircuitArtifacts {
-            r1cs: build_dir.join(format!("{}.r1cs", circuit.name)),
-            wasm: build_dir
-                .join(format!("{}_js", circuit.name))
-                .join(format!("{}.wasm", circuit.name)),
-            sym: build_dir.join(format!("{}.sym", circuit.name)),
-            pkey: None,
-            vkey: None,
+    /// Version of the installed circom compiler, e.g. `2.1.9`
+    pub fn circom_version(&self) -> Result<String> {
+        let output = self.run_tool(
+            Stage::Other,
+            &self.config.circom_command(),
+            ["--version"],
+            &[],
+        )?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        parse_version(&stdout).ok_or_else(|| {
+            CircomkitError::Other(format!("Unrecognized circom version output: {}", stdout))
+        })
+    }
+
+    /// Check which external tools are installed
+    ///
+    /// Runs circom, snarkjs, and node (used for witness generation) with
+    /// `--version`. Tools that can't be run are `None` in the report, so a
+    /// test harness can fail early with [`ToolReport::require_all`]. circom
+    /// and snarkjs versions outside the supported range are listed in
+    /// `warnings`.
+    pub fn doctor(&self) -> Result<ToolReport> {
+        let circom = tool_version(&self.config.circom_command());
+        let snarkjs = tool_version(&self.config.snarkjs_command());
+        let warnings = [("circom", &circom), ("snarkjs", &snarkjs)]
+            .into_iter()
+            .filter_map(|(tool, version)| CompatibilityWarning::check(tool, version.as_deref()?))
+            .collect();
+
+        Ok(ToolReport {
+            circom,
+            snarkjs,
+            node: tool_version("node"),
+            warnings,
         })
     }
 
     /// Generate a main component file for the circuit
     ///
-    /// The main component is generated in `build/main/` directory.
-    /// If the circuit has an absolute file path, it uses that directly.
-    /// Otherwise, it uses the relative path from the circuits directory.
+    /// The main component is generated in the build layout's main directory,
+    /// `build/.circomkit/main/` by default. If the circuit has an absolute
+    /// file path, it uses that directly. Otherwise, it uses the relative path
+    /// from the circuits directory.
     async fn generate_main_component(&self, circuit: &CircuitConfig) -> Result<PathBuf> {
-        // Put main components in build/main/ directory
-        let main_dir = self.config.dir_build.join("main");
+        self.validate_public_signals(circuit).await?;
+
+        let main_dir = self.main_dir();
         fs::create_dir_all(&main_dir).await?;
 
         let main_path = main_dir.join(format!("{}.circom", circuit.name));
+        // With a main directory next to the sources, a circuit named after
+        // its file would overwrite the file it includes
+        let source = self.source_path(circuit).canonicalize().ok();
+        if source.is_some() && main_path.canonicalize().ok() == source {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "circuit '{}': main component {} would overwrite its source file",
+                circuit.name,
+                main_path.display()
+            )));
+        }
 
-        // Generate the main component
-        let params = if circuit.params.is_empty() {
-            String::new()
-        } else {
-            circuit
-                .params
-                .iter()
-                .map(|p| p.to_string())
-                .collect::<Vec<_>>()
-                .join(", ")
+        // circom allows a single main component per program
+        if let Some(source) = source
+            && let Ok(content) = fs::read_to_string(&source).await
+            && declares_main(&content)
+        {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "circuit '{}': {} already declares `component main`; remove it so \
+                 circomkit can generate the main component",
+                circuit.name,
+                source.display()
+            )));
+        }
+
+        // Concurrent compiles of the same circuit write identical contents,
+        // so leave a current file alone and replace a stale one atomically,
+        // never exposing a partly written file to a running circom
+        let main_source = self.main_component_source(circuit);
+        if fs::read_to_string(&main_path).await.ok() != Some(main_source.clone()) {
+            let temp_path = main_dir.join(format!("{}.circom.{}.tmp", circuit.name, temp_suffix()));
+            fs::write(&temp_path, &main_source).await?;
+            if let Err(e) = fs::rename(&temp_path, &main_path).await {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(e.into());
+            }
+        }
+        debug!("Generated main component: {:?}", main_path);
+
+        Ok(main_path)
+    }
+
+    /// Check that every public signal is a declared input of the template
+    ///
+    /// circom only makes whole signals public, so array elements such as
+    /// `leaves[0]` are rejected. The template check is skipped when the
+    /// template's source can't be found.
+    async fn validate_public_signals(&self, circuit: &CircuitConfig) -> Result<()> {
+        if circuit.public.is_empty() {
+            return Ok(());
+        }
+
+        let elements: Vec<&str> = circuit
+            .public
+            .iter()
+            .map(String::as_str)
+            .filter(|name| name.contains('['))
+            .collect();
+        if !elements.is_empty() {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "public signals of circuit '{}' name array elements, but circom only makes \
+                 whole signals public; declare the public elements as a separate input: {}",
+                circuit.name,
+                elements.join(", ")
+            )));
+        }
+
+        let Some(inputs) = self.template_inputs(circuit).await? else {
+            debug!(
+                "Skipping public signal validation: template {} not found",
+                circuit.template
+            );
+            return Ok(());
         };
 
+        let undeclared: Vec<&str> = circuit
+            .public
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !inputs.iter().any(|input| input == name.trim()))
+            .collect();
+
+        if !undeclared.is_empty() {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "public signals of circuit '{}' are not inputs of template {}: {}",
+                circuit.name,
+                circuit.template,
+                undeclared.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Input signals declared by the circuit's template
+    ///
+    /// Looks in the circuit file first, then in its includes.
+    async fn template_inputs(&self, circuit: &CircuitConfig) -> Result<Option<Vec<String>>> {
+        let path = self.source_path(circuit);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let source = fs::read_to_string(&path).await?;
+        if let Some(inputs) = template_inputs(&source, &circuit.template) {
+            return Ok(Some(inputs));
+        }
+
+        let mut flattener = Flattener::new(self.include_paths(circuit).await?);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        if flattener.inline(&source, dir).is_err() {
+            return Ok(None);
+        }
+        Ok(template_inputs(&flattener.body, &circuit.template))
+    }
+
+    /// Directory generated main components are written to
+    fn main_dir(&self) -> PathBuf {
+        self.config.main_dir()
+    }
+
+    /// Source of the main component for the circuit
+    fn main_component_source(&self, circuit: &CircuitConfig) -> String {
+        let params = circuit
+            .param_values()
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
         let public_signals = if circuit.public.is_empty() {
             String::new()
         } else {
@@ -165,438 +595,4289 @@ impl Circomkit {
             // Use absolute path directly
             abs_path.to_string_lossy().to_string()
         } else {
-            // Use relative path from build/main to circuits directory
-            // build/main -> ../../circuits/file.circom
-            format!(
-                "../../{}/{}",
-                self.config.dir_circuits.display(),
-                circuit.file
-            )
+            let file = self.config.dir_circuits.join(&circuit.file);
+            relative_include(&self.main_dir(), &file)
         };
 
-        // circom 2.1.9
-        let content = format!(
-            r#"pragma circom 2.1.9;
-
-include "{}";
+        circuit
+            .main_template
+            .as_deref()
+            .unwrap_or(DEFAULT_MAIN_TEMPLATE)
+            .replace("{version}", &self.config.circom_version)
+            .replace("{include}", &include_path)
+            .replace("{main}", circuit.main_name())
+            .replace("{public}", &public_signals)
+            .replace("{template}", &circuit.template)
+            .replace("{params}", &params)
+    }
 
-component main{} = {}({});
-"#,
-            include_path, public_signals, circuit.template, params
-        );
+    /// Export the circuit as a single source file with all includes inlined
+    ///
+    /// Starts from the generated main component, so the result compiles on
+    /// its own. Includes are resolved like circom does: relative to the
+    /// including file, then against the compile include paths. Each file
+    /// is inlined once, which also breaks include cycles. Pragmas are hoisted
+    /// to the top and deduplicated, keeping the main component's circom
+    /// version.
+    pub async fn flatten_source(&self, circuit: &CircuitConfig) -> Result<String> {
+        let mut flattener = Flattener::new(self.include_paths(circuit).await?);
+        flattener.inline(&self.main_component_source(circuit), &self.main_dir())?;
 
-        fs::write(&main_path, content).await?;
-        debug!("Generated main component: {:?}", main_path);
+        let mut output = flattener.pragmas.join("\n");
+        output.push_str("\n\n");
+        output.push_str(flattener.body.trim_start());
+        Ok(output)
+    }
 
-        Ok(main_path)
+    /// Source files the circuit is compiled from
+    ///
+    /// The circuit's source file followed by everything it includes,
+    /// transitively, as canonical paths in the order circom reads them.
+    /// Includes resolve as in [`Circomkit::flatten_source`]; a missing include
+    /// fails with `CircuitNotFound`.
+    pub async fn dependencies(&self, circuit: &CircuitConfig) -> Result<Vec<PathBuf>> {
+        let mut flattener = Flattener::new(self.include_paths(circuit).await?);
+        flattener.inline(&self.main_component_source(circuit), &self.main_dir())?;
+        Ok(flattener.files)
     }
 
     /// Generate a witness for the given inputs
+    ///
+    /// Inputs are written with every value as a decimal string (see
+    /// [`SignalValue::normalize`]), and values at or above the field modulus
+    /// are reduced with a warning. Inputs with more than
+    /// `max_input_elements` values are rejected. The witness is written to a
+    /// file unique to this call, so concurrent calls for the same circuit
+    /// don't overwrite each other.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(circuit = %circuit.name))
+    )]
     pub async fn generate_witness(
         &self,
         circuit: &CircuitConfig,
         inputs: &CircuitSignals,
     ) -> Result<Witness> {
-        info!("Generating witness for: {}", circuit.name);
-
-        let build_dir = self.config.build_path(&circuit.name);
-        let wasm_dir = build_dir.join(format!("{}_js", circuit.name));
-        let witness_calc = wasm_dir.join("generate_witness.js");
-        let wasm_file = wasm_dir.join(format!("{}.wasm", circuit.name));
+        let inputs = self.hooked_inputs(inputs);
+        let input_json = serde_json::to_string_pretty(&self.witness_inputs(circuit, &inputs))?;
+        self.run_witness(circuit, &inputs, input_json).await
+    }
 
-        // Check if circuit is compiled
-        if !wasm_file.exists() {
-            return Err(CircomkitError::CircuitNotFound(wasm_file));
+    /// Generate a witness for inputs given as a JSON object
+    ///
+    /// The value is validated like [`CircuitSignals`] and written to the
+    /// input file unchanged, unless an input hook has to transform it.
+    pub async fn generate_witness_json(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &serde_json::Value,
+    ) -> Result<Witness> {
+        let signals = signals_from_json(inputs.clone())?;
+        if self.input_hook.is_some() {
+            return self.generate_witness(circuit, &signals).await;
         }
-
-        // Write inputs to temp file
-        let input_path = build_dir.join("input.json");
         let input_json = serde_json::to_string_pretty(inputs)?;
-        fs::write(&input_path, input_json).await?;
-
-        // Generate witness
-        let witness_path = build_dir.join("witness.wtns");
+        self.run_witness(circuit, &signals, input_json).await
+    }
 
-        let output = Command::new("node")
-            .arg(&witness_calc)
-            .arg(&wasm_file)
-            .arg(&input_path)
-            .arg(&witness_path)
-            .output()
-            .map_err(|e| CircomkitError::Io(e))?;
+    /// Generate a witness from an existing input file
+    ///
+    /// The file is passed to the witness calculator as-is, skipping the
+    /// parse and serialize round trip of [`Circomkit::generate_witness`] for
+    /// large inputs. Inputs are not validated against the circuit.
+    pub async fn generate_witness_from_file(
+        &self,
+        circuit: &CircuitConfig,
+        input_path: &Path,
+    ) -> Result<Witness> {
+        info!("Generating witness for: {}", circuit.name);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::witness_failed(stderr.to_string()));
+        self.check_compiled(circuit)?;
+        if !input_path.exists() {
+            return Err(CircomkitError::InvalidSignals(format!(
+                "Input file not found: {}",
+                input_path.display()
+            )));
         }
 
-        info!("Witness generated successfully");
+        let temp_dir = self.config.temp_path(&circuit.name);
+        fs::create_dir_all(&temp_dir).await?;
+        let witness_path = temp_dir.join(format!("witness_{}.wtns", temp_suffix()));
 
-        Ok(Witness {
-            path: witness_path,
-            num_signals: 0, // TODO: Parse from witness file
-        })
+        self.calculate_witness(circuit, input_path, witness_path)
     }
 
-    /// Set up the proving and verification keys
-    pub async fn setup(
+    /// Validate `inputs`, write `input_json`, and run the witness calculator
+    async fn run_witness(
         &self,
         circuit: &CircuitConfig,
-        ptau_path: &Path,
-    ) -> Result<CircuitArtifacts> {
-        info!("Setting up keys for: {}", circuit.name);
-
-        let build_dir = self.config.build_path(&circuit.name);
-        let r1cs_path = build_dir.join(format!("{}.r1cs", circuit.name));
-
-        if !r1cs_path.exists() {
-            return Err(CircomkitError::CircuitNotFound(r1cs_path));
-        }
+        inputs: &CircuitSignals,
+        input_json: String,
+    ) -> Result<Witness> {
+        info!("Generating witness for: {}", circuit.name);
 
-        if !ptau_path.exists() {
-            return Err(CircomkitError::PtauNotFound(ptau_path.to_path_buf()));
-        }
+        self.check_input_size(inputs)?;
+        self.check_compiled(circuit)?;
+        self.validate_inputs(circuit, inputs).await?;
 
-        let snarkjs = self.config.snarkjs_command();
-        let protocol = self.config.protocol.to_string();
+        // Write inputs to a per-call temp file so concurrent calls don't race
+        let temp_dir = self.config.temp_path(&circuit.name);
+        fs::create_dir_all(&temp_dir).await?;
+        let suffix = temp_suffix();
+        let input_path = temp_dir.join(format!("input_{}.json", suffix));
+        fs::write(&input_path, input_json).await?;
 
-        // Generate zkey
-        let zkey_path = build_dir.join(format!("{}_pkey.zkey", protocol));
-
-        let output = Command::new(&snarkjs)
-            .arg(&protocol)
-            .arg("setup")
-            .arg(&r1cs_path)
-            .arg(ptau_path)
-            .arg(&zkey_path)
-            .output()
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    CircomkitError::tool_not_found(&snarkjs)
-                } else {
-                    CircomkitError::Io(e)
-                }
-            })?;
+        let witness_path = temp_dir.join(format!("witness_{}.wtns", suffix));
+        let witness = self.calculate_witness(circuit, &input_path, witness_path);
+        let _ = fs::remove_file(&input_path).await;
+        witness
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: snarkjs.clone(),
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
-            });
+    /// `inputs` as transformed by the input hook, if one is set
+    fn hooked_inputs<'a>(&self, inputs: &'a CircuitSignals) -> Cow<'a, CircuitSignals> {
+        match &self.input_hook {
+            Some(InputHook(hook)) => {
+                let mut inputs = inputs.clone();
+                hook(&mut inputs);
+                Cow::Owned(inputs)
+            }
+            None => Cow::Borrowed(inputs),
         }
+    }
 
-        // Export verification key
-        let vkey_path = build_dir.join(format!("{}_vkey.json", protocol));
-
-        let output = Command::new(&snarkjs)
-            .arg("zkey")
-            .arg("export")
-            .arg("verificationkey")
-            .arg(&zkey_path)
-            .arg(&vkey_path)
-            .output()
-            .map_err(|e| CircomkitError::Io(e))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: snarkjs,
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
-            });
+    /// Fail with `InvalidSignals` if the inputs hold more than
+    /// `max_input_elements` values
+    fn check_input_size(&self, inputs: &CircuitSignals) -> Result<()> {
+        let elements: usize = inputs.values().map(SignalValue::num_elements).sum();
+        if elements > self.config.max_input_elements {
+            return Err(CircomkitError::InvalidSignals(format!(
+                "Inputs have {} values, more than max_input_elements ({})",
+                elements, self.config.max_input_elements
+            )));
         }
-
-        info!("Setup completed successfully");
-
-        Ok(CircuitArtifacts {
-            r1cs: r1cs_path,
-            wasm: build_dir
-                .join(format!("{}_js", circuit.name))
-                .join(format!("{}.wasm", circuit.name)),
-            sym: build_dir.join(format!("{}.sym", circuit.name)),
-            pkey: Some(zkey_path),
-            vkey: Some(vkey_path),
-        })
+        Ok(())
     }
 
-    /// Generate a proof
-    pub async fn prove(
-        &self,
-        circuit: &CircuitConfig,
-        inputs: &CircuitSignals,
-    ) -> Result<(Proof, PublicSignals)> {
-        info!("Generating proof for: {}", circuit.name);
+    /// Inputs as written for the witness calculator
+    ///
+    /// Values are normalized to decimal strings, and values at or above the
+    /// circuit's field modulus are reduced modulo it with a warning.
+    fn witness_inputs(&self, circuit: &CircuitConfig, inputs: &CircuitSignals) -> CircuitSignals {
+        let modulus = circuit.prime.unwrap_or(self.config.prime).modulus();
+        let mut signals = normalize_signals(inputs);
+        for (name, value) in signals.iter_mut() {
+            if reduce_signal(value, &modulus) {
+                warn!(
+                    "Input '{}' of {} exceeds the field modulus and was reduced",
+                    name, circuit.name
+                );
+            }
+        }
+        signals
+    }
 
-        // First generate the witness
-        let witness = self.generate_witness(circuit, inputs).await?;
+    /// Whether the circuit's r1cs, wasm, and sym files are up to date
+    ///
+    /// Those the configuration emits must exist and be newer than the source
+    /// file and every file it includes, and the generated main component must match the current
+    /// configuration, so a changed template or parameter list still triggers
+    /// a recompile. The stamp `compile` leaves must also match the current
+    /// compile command, so a changed prime, optimization level, include
+    /// path, or flag does too, as does a different circom or snarkjs
+    /// version.
+    pub async fn is_compiled(&self, circuit: &CircuitConfig) -> bool {
+        let main_path = self.main_dir().join(format!("{}.circom", circuit.name));
+        match fs::read_to_string(&main_path).await {
+            Ok(main) if main == self.main_component_source(circuit) => {}
+            _ => return false,
+        }
 
         let build_dir = self.config.build_path(&circuit.name);
-        let protocol = self.config.protocol.to_string();
-        let zkey_path = build_dir.join(format!("{}_pkey.zkey", protocol));
+        let Ok((circom, args)) = self
+            .compile_args(circuit, &main_path, &build_dir, false)
+            .await
+        else {
+            return false;
+        };
+        match fs::read_to_string(self.compile_stamp_path(circuit)).await {
+            Ok(stamp) if stamp == self.compile_stamp(&circom, &args).await => {}
+            _ => return false,
+        }
 
-        if !zkey_path.exists() {
-            return Err(CircomkitError::proof_failed(
-                "Proving key not found. Run setup first.",
-            ));
+        let Ok(dependencies) = self.dependencies(circuit).await else {
+            return false;
+        };
+        let mut source_modified = None;
+        for path in &dependencies {
+            let Some(time) = modified(path).await else {
+                return false;
+            };
+            source_modified = source_modified.max(Some(time));
         }
+        let Some(source_modified) = source_modified else {
+            return false;
+        };
 
-        let proof_path = build_dir.join(format!("{}_proof.json", protocol));
-        let public_path = build_dir.join("public.json");
+        let artifacts = [
+            (
+                self.config.emit_r1cs,
+                build_dir.join(format!("{}.r1cs", circuit.name)),
+            ),
+            (self.config.emit_wasm, self.wasm_path(circuit)),
+            (
+                self.config.emit_sym,
+                build_dir.join(format!("{}.sym", circuit.name)),
+            ),
+        ];
+        for (_, path) in artifacts.iter().filter(|(enabled, _)| *enabled) {
+            if modified(path)
+                .await
+                .is_none_or(|built| built < source_modified)
+            {
+                return false;
+            }
+        }
+        true
+    }
 
-        let snarkjs = self.config.snarkjs_command();
+    /// Hash of the compile command and the installed circom and snarkjs
+    /// versions, which `compile` writes to the stamp
+    async fn compile_stamp(&self, circom: &str, args: &[String]) -> String {
+        let versions = [
+            self.installed_version(circom).await,
+            self.installed_version(&self.config.snarkjs_command()).await,
+        ];
+        let versions = versions.map(Option::unwrap_or_default);
+        command_hash(circom, args.iter().chain(&versions))
+    }
 
-        let output = Command::new(&snarkjs)
-            .arg(&protocol)
-            .arg("prove")
-            .arg(&zkey_path)
-            .arg(&witness.path)
-            .arg(&proof_path)
-            .arg(&public_path)
-            .output()
-            .map_err(|e| CircomkitError::Io(e))?;
+    /// Version of `program`, looked up once and cached
+    ///
+    /// `None` when it can't be run, and when the backend doesn't check
+    /// versions, as a mock one doesn't.
+    async fn installed_version(&self, program: &str) -> Option<String> {
+        if let Some(version) = self
+            .tool_versions
+            .lock()
+            .ok()
+            .and_then(|versions| versions.get(program).cloned())
+        {
+            return version;
+        }
+        if !self.backend.checks_versions() {
+            return None;
+        }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::proof_failed(stderr.to_string()));
+        let owned = program.to_string();
+        let version = tokio::task::spawn_blocking(move || tool_version(&owned))
+            .await
+            .ok()
+            .flatten();
+        if let Ok(mut versions) = self.tool_versions.lock() {
+            versions.insert(program.to_string(), version.clone());
         }
+        version
+    }
 
-        // Read proof and public signals
-        let proof_content = fs::read_to_string(&proof_path).await?;
-        let proof_data: serde_json::Value = serde_json::from_str(&proof_content)?;
+    /// Path of the stamp `compile` writes with a hash of its command
+    fn compile_stamp_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        self.config
+            .build_path(&circuit.name)
+            .join(format!("{}.compile-stamp", circuit.name))
+    }
 
-        let public_content = fs::read_to_string(&public_path).await?;
-        let public_signals: Vec<String> = serde_json::from_str(&public_content)?;
+    /// Fail unless the circuit's wasm has been built
+    fn check_compiled(&self, circuit: &CircuitConfig) -> Result<()> {
+        self.require_artifact(&self.wasm_path(circuit), self.config.emit_wasm, "emit_wasm")
+    }
 
-        info!("Proof generated successfully");
+    /// Fail unless the build artifact at `path` is emitted and exists
+    ///
+    /// An artifact the configuration doesn't emit is `InvalidConfig` naming
+    /// the `option` to enable, even if a stale file from an earlier compile
+    /// is still there; a missing one is `CircuitNotFound`.
+    pub(crate) fn require_artifact(&self, path: &Path, emitted: bool, option: &str) -> Result<()> {
+        if !emitted {
+            Err(CircomkitError::InvalidConfig(format!(
+                "{} is not built because `{}` is disabled; enable it and recompile",
+                path.display(),
+                option
+            )))
+        } else if path.exists() {
+            Ok(())
+        } else {
+            Err(CircomkitError::CircuitNotFound(path.to_path_buf()))
+        }
+    }
 
-        Ok((
-            Proof {
-                protocol: self.config.protocol,
-                data: proof_data,
-            },
-            PublicSignals::new(public_signals),
-        ))
+    /// Path to the circuit's compiled wasm
+    ///
+    /// Prefers the path found by the last `compile` of the circuit, and
+    /// otherwise looks for it in the build directory.
+    fn wasm_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        let resolved = self
+            .wasm_paths
+            .lock()
+            .ok()
+            .and_then(|paths| paths.get(&circuit.name).cloned());
+        match resolved {
+            Some(path) if path.exists() => path,
+            _ => find_wasm(&self.config.build_path(&circuit.name), &circuit.name),
+        }
     }
 
-    /// Verify a proof
-    pub async fn verify(
+    /// Run the witness calculator on `input_path`, writing `witness_path`
+    fn calculate_witness(
         &self,
         circuit: &CircuitConfig,
-        proof: &Proof,
-        public_signals: &PublicSignals,
-    ) -> Result<bool> {
-        info!("Verifying proof for: {}", circuit.name);
+        input_path: &Path,
+        witness_path: PathBuf,
+    ) -> Result<Witness> {
+        let outputs = [witness_path.clone()];
+        self.backend
+            .run(Invocation {
+                stage: Stage::Witness,
+                command: &mut self.witness_command(circuit, input_path, &witness_path),
+                outputs: &outputs,
+            })
+            .map_err(|e| match e {
+                CircomkitError::CommandFailed { stderr, .. } => {
+                    CircomkitError::from_witness_stderr(stderr)
+                }
+                e => e,
+            })?;
 
-        let build_dir = self.config.build_path(&circuit.name);
-        let protocol = self.config.protocol.to_string();
-        let vkey_path = build_dir.join(format!("{}_vkey.json", protocol));
+        info!("Witness generated successfully");
 
-        if !vkey_path.exists() {
+        Ok(Witness {
+            path: witness_path,
+            num_signals: 0, // TODO: Parse from witness file
+        })
+    }
+
+    /// Check that `inputs` supplies exactly the circuit's declared input signals
+    ///
+    /// Skipped when the circuit's r1cs or sym file is unavailable.
+    async fn validate_inputs(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &CircuitSignals,
+    ) -> Result<()> {
+        match self.declared_inputs(circuit).await? {
+            Some(declared) => check_inputs(&declared, inputs),
+            None => Ok(()),
+        }
+    }
+
+    /// JSON Schema describing the circuit's input signals
+    ///
+    /// Derived from the compiled circuit's declared inputs, so the circuit
+    /// must be compiled first. Each input is a decimal or hex string or an
+    /// integer, nested in fixed-length arrays for array signals.
+    pub async fn input_schema(&self, circuit: &CircuitConfig) -> Result<serde_json::Value> {
+        let declared = self.input_shapes(circuit).await?;
+        Ok(input_schema(&circuit.name, &declared))
+    }
+
+    /// Random inputs for the compiled circuit, for fuzzing
+    ///
+    /// Each input is filled with uniformly random elements of the circuit's
+    /// field, shaped like its declared dimensions. Pair with
+    /// `WitnessTester::check_property` to test against a reference
+    /// implementation.
+    pub async fn random_inputs(
+        &self,
+        circuit: &CircuitConfig,
+        rng: &mut impl Rng,
+    ) -> Result<CircuitSignals> {
+        self.random_inputs_in_range(circuit, &HashMap::new(), rng)
+            .await
+    }
+
+    /// Random inputs with some signals drawn from `bounds`
+    ///
+    /// Each bound is a half-open range applied to every element of its
+    /// signal, e.g. `0..2^n` for an input constrained by `Num2Bits(n)`.
+    /// Signals without a bound span the whole field.
+    pub async fn random_inputs_in_range(
+        &self,
+        circuit: &CircuitConfig,
+        bounds: &HashMap<String, Range<BigUint>>,
+        rng: &mut impl Rng,
+    ) -> Result<CircuitSignals> {
+        let shapes = self.input_shapes(circuit).await?;
+        let field = BigUint::from(0u32)..circuit.prime.unwrap_or(self.config.prime).modulus();
+
+        let mut names: Vec<&String> = bounds.keys().collect();
+        names.sort();
+        for name in names {
+            let range = &bounds[name];
+            if !shapes.contains_key(name) {
+                return Err(CircomkitError::InvalidSignals(format!(
+                    "Bounded signal '{}' is not an input of {}",
+                    name, circuit.name
+                )));
+            }
+            if range.start >= range.end || range.end > field.end {
+                return Err(CircomkitError::InvalidSignals(format!(
+                    "Bounds {}..{} for '{}' are empty or exceed the field",
+                    range.start, range.end, name
+                )));
+            }
+        }
+
+        Ok(shapes
+            .iter()
+            .map(|(name, dims)| {
+                let range = bounds.get(name).unwrap_or(&field);
+                (name.clone(), random_signal(dims, range, rng))
+            })
+            .collect())
+    }
+
+    /// Array dimensions of each input signal of the compiled circuit
+    ///
+    /// Scalar inputs have no dimensions.
+    pub(crate) async fn input_shapes(
+        &self,
+        circuit: &CircuitConfig,
+    ) -> Result<BTreeMap<String, Vec<usize>>> {
+        let build_dir = self.config.build_path(&circuit.name);
+        self.require_artifact(
+            &build_dir.join(format!("{}.r1cs", circuit.name)),
+            self.config.emit_r1cs,
+            "emit_r1cs",
+        )?;
+        self.require_artifact(
+            &build_dir.join(format!("{}.sym", circuit.name)),
+            self.config.emit_sym,
+            "emit_sym",
+        )?;
+        self.declared_inputs(circuit).await?.ok_or_else(|| {
+            CircomkitError::CircuitNotFound(build_dir.join(format!("{}.sym", circuit.name)))
+        })
+    }
+
+    /// Input signals of the main component with their array dimensions
+    ///
+    /// Inputs are identified by their wire ids from the r1cs header and
+    /// named from the sym file. Returns `None` if either file is missing or
+    /// not emitted by the configuration, as a leftover could be stale.
+    async fn declared_inputs(
+        &self,
+        circuit: &CircuitConfig,
+    ) -> Result<Option<BTreeMap<String, Vec<usize>>>> {
+        let build_dir = self.config.build_path(&circuit.name);
+        let r1cs_path = build_dir.join(format!("{}.r1cs", circuit.name));
+        let sym_path = build_dir.join(format!("{}.sym", circuit.name));
+
+        let emitted = self.config.emit_r1cs && self.config.emit_sym;
+        if !emitted || !r1cs_path.exists() || !sym_path.exists() {
+            debug!("Skipping input validation: r1cs or sym file not found");
+            return Ok(None);
+        }
+
+        let header = read_r1cs_header(&r1cs_path).await?;
+        let symbols = read_symbol_table(&sym_path).await?;
+
+        let mut declared: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+        for symbol in symbols.inputs(&header) {
+            let (name, indices) = symbol.base_name_and_indices();
+
+            let dims = declared.entry(name.to_string()).or_default();
+            if dims.len() < indices.len() {
+                dims.resize(indices.len(), 0);
+            }
+            for (dim, index) in dims.iter_mut().zip(indices) {
+                *dim = (*dim).max(index + 1);
+            }
+        }
+
+        Ok(Some(declared))
+    }
+
+    /// Generate witnesses for many input sets
+    ///
+    /// Each input set is written to `input_{id}_{i}.json` and its witness to
+    /// `witness_{id}_{i}.wtns` in the circuit's temp directory, where `id`
+    /// is unique to the batch. Up to
+    /// `max_parallel_jobs` witness calculators run at once. Witnesses are
+    /// returned in the same order as `inputs`. Every input set is checked
+    /// against the declared inputs first, as in `generate_witness`, so an
+    /// invalid one fails the batch before any file is written.
+    pub async fn generate_witnesses(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &[CircuitSignals],
+    ) -> Result<Vec<Witness>> {
+        info!(
+            "Generating {} witnesses for: {}",
+            inputs.len(),
+            circuit.name
+        );
+
+        self.check_compiled(circuit)?;
+        let inputs: Vec<_> = inputs
+            .iter()
+            .map(|signals| self.hooked_inputs(signals))
+            .collect();
+        for signals in &inputs {
+            self.check_input_size(signals)?;
+        }
+        // Validate every input set before any witness calculator runs
+        if let Some(declared) = self.declared_inputs(circuit).await? {
+            for (i, signals) in inputs.iter().enumerate() {
+                check_inputs(&declared, signals).map_err(|e| match e {
+                    CircomkitError::InvalidSignals(msg) => {
+                        CircomkitError::InvalidSignals(format!("input {}: {}", i, msg))
+                    }
+                    e => e,
+                })?;
+            }
+        }
+
+        let semaphore = Arc::new(Semaphore::new(self.config.max_parallel_jobs));
+        let mut jobs = JoinSet::new();
+        let mut witness_paths = Vec::with_capacity(inputs.len());
+        let mut input_paths = Vec::with_capacity(inputs.len());
+        let temp_dir = self.config.temp_path(&circuit.name);
+        fs::create_dir_all(&temp_dir).await?;
+        let suffix = temp_suffix();
+
+        for (i, signals) in inputs.iter().enumerate() {
+            let input_path = temp_dir.join(format!("input_{}_{}.json", suffix, i));
+            let input_json = serde_json::to_string_pretty(&self.witness_inputs(circuit, signals))?;
+            fs::write(&input_path, input_json).await?;
+
+            let witness_path = temp_dir.join(format!("witness_{}_{}.wtns", suffix, i));
+            let mut cmd = self.witness_command(circuit, &input_path, &witness_path);
+            let outputs = [witness_path.clone()];
+            witness_paths.push(witness_path);
+            input_paths.push(input_path);
+
+            let semaphore = Arc::clone(&semaphore);
+            let backend = Arc::clone(&self.backend);
+            jobs.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let output = tokio::task::spawn_blocking(move || {
+                    backend.run(Invocation {
+                        stage: Stage::Witness,
+                        command: &mut cmd,
+                        outputs: &outputs,
+                    })
+                })
+                .await
+                .map_err(|e| CircomkitError::Other(e.to_string()))?;
+                Ok::<_, CircomkitError>((i, output))
+            });
+        }
+
+        let mut results = Vec::with_capacity(inputs.len());
+        while let Some(joined) = jobs.join_next().await {
+            results.push(joined);
+        }
+        for input_path in &input_paths {
+            let _ = fs::remove_file(input_path).await;
+        }
+
+        let mut failures = Vec::new();
+        for joined in results {
+            let (i, output) = joined.map_err(|e| CircomkitError::Other(e.to_string()))??;
+            match output {
+                Ok(_) => {}
+                Err(CircomkitError::CommandFailed { stderr, .. }) => failures.push((i, stderr)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some((i, stderr)) = failures.into_iter().min_by_key(|(i, _)| *i) {
+            return Err(CircomkitError::from_witness_stderr(format!(
+                "input {}: {}",
+                i, stderr
+            )));
+        }
+
+        info!("Witnesses generated successfully");
+
+        Ok(witness_paths
+            .into_iter()
+            .map(|path| Witness {
+                path,
+                num_signals: 0,
+            })
+            .collect())
+    }
+
+    /// Read every named signal of `circuit` from a witness file
+    ///
+    /// The witness is exported with `snarkjs wtns export json` and mapped to
+    /// signal names through the circuit's `.sym` file. Keys are relative to
+    /// the main component, e.g. `out` or `bits.out[3]`; optimized-out
+    /// signals are skipped. Returns an empty map if the installed snarkjs
+    /// can't export witnesses.
+    pub async fn witness_to_signals(
+        &self,
+        circuit: &CircuitConfig,
+        witness_path: &Path,
+    ) -> Result<CircuitSignals> {
+        let sym_path = self
+            .config
+            .build_path(&circuit.name)
+            .join(format!("{}.sym", circuit.name));
+        self.require_artifact(&sym_path, self.config.emit_sym, "emit_sym")?;
+
+        let output_path = witness_path.with_extension("json");
+        let (snarkjs, args) = self.snarkjs_args(vec![
+            "wtns".to_string(),
+            "export".to_string(),
+            "json".to_string(),
+            path_arg(witness_path),
+            path_arg(&output_path),
+        ]);
+
+        match self.run_tool(
+            Stage::ExportWitness,
+            &snarkjs,
+            &args,
+            std::slice::from_ref(&output_path),
+        ) {
+            Ok(_) => {}
+            // Older snarkjs versions lack the command; there are no signals to read
+            Err(CircomkitError::CommandFailed { stderr, .. })
+                if EXPORT_UNSUPPORTED_MARKERS
+                    .iter()
+                    .any(|m| stderr.contains(m)) =>
+            {
+                warn!("snarkjs does not support `wtns export json`; no signals read");
+                return Ok(HashMap::new());
+            }
+            Err(CircomkitError::CommandFailed { stderr, .. }) => {
+                return Err(CircomkitError::witness_failed(format!(
+                    "snarkjs wtns export json failed: {}",
+                    stderr.trim()
+                )));
+            }
+            Err(e) => return Err(e),
+        }
+
+        let content = fs::read_to_string(&output_path).await;
+        let _ = fs::remove_file(&output_path).await;
+        let values: Vec<String> = serde_json::from_str(&content?)?;
+
+        let prefix = format!("{}.", circuit.main_name());
+        let symbols = read_symbol_table(&sym_path).await?;
+        Ok(symbols
+            .iter()
+            .filter(|s| s.name.starts_with(&prefix))
+            .filter_map(|s| {
+                let value = values.get(s.wire? as usize)?;
+                Some((
+                    s.signal_name().to_string(),
+                    SignalValue::Single(value.clone()),
+                ))
+            })
+            .collect())
+    }
+
+    /// Build the witness calculator command for a circuit
+    fn witness_command(
+        &self,
+        circuit: &CircuitConfig,
+        input_path: &Path,
+        witness_path: &Path,
+    ) -> Command {
+        let wasm = self.wasm_path(circuit);
+        let wasm_dir = wasm.parent().unwrap_or(Path::new("."));
+
+        let mut cmd = self.config.node_process("node");
+        cmd.arg(wasm_dir.join("generate_witness.js"))
+            .arg(&wasm)
+            .arg(input_path)
+            .arg(witness_path)
+            .args(&self.config.witness_args);
+
+        cmd
+    }
+
+    /// Set up the proving and verification keys
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(circuit = %circuit.name))
+    )]
+    pub async fn setup(
+        &self,
+        circuit: &CircuitConfig,
+        ptau_path: &Path,
+    ) -> Result<CircuitArtifacts> {
+        info!("Setting up keys for: {}", circuit.name);
+
+        // Fails early for a protocol the circuit's prime doesn't support
+        let (snarkjs, args) = self.setup_command(circuit, ptau_path)?;
+
+        let build_dir = self.config.build_path(&circuit.name);
+        let r1cs_path = build_dir.join(format!("{}.r1cs", circuit.name));
+
+        self.require_artifact(&r1cs_path, self.config.emit_r1cs, "emit_r1cs")?;
+
+        if !ptau_path.exists() {
+            return Err(CircomkitError::PtauNotFound(ptau_path.to_path_buf()));
+        }
+
+        let prime = circuit.prime.unwrap_or(self.config.prime);
+        match (
+            read_ptau_header(ptau_path).await,
+            read_r1cs_header(&r1cs_path).await,
+        ) {
+            (Ok(ptau), Ok(r1cs)) => ptau.check_circuit(&r1cs, prime)?,
+            (Err(e), _) | (_, Err(e)) => warn!("Skipping PTAU size check: {}", e),
+        }
+
+        // Generate zkey
+        let zkey_path = self.pkey_path(circuit);
+        self.run_tool(
+            Stage::Setup,
+            &snarkjs,
+            &args,
+            std::slice::from_ref(&zkey_path),
+        )?;
+
+        let vkey_path = self.export_vkey(circuit, &zkey_path)?;
+        info!("Setup completed successfully");
+
+        Ok(CircuitArtifacts {
+            r1cs: r1cs_path,
+            wasm: self.wasm_path(circuit),
+            sym: build_dir.join(format!("{}.sym", circuit.name)),
+            pkey: Some(zkey_path),
+            vkey: Some(vkey_path),
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Set up with an existing proving key instead of running a ceremony
+    ///
+    /// `zkey_path`, e.g. from a production ceremony, is copied to the
+    /// circuit's proving key path and its verification key exported, so
+    /// `prove` and `verify` use it like keys from [`Circomkit::setup`]. Fails
+    /// with `InvalidConfig` if the key is for another protocol than the
+    /// configured one.
+    pub async fn setup_from_zkey(
+        &self,
+        circuit: &CircuitConfig,
+        zkey_path: &Path,
+    ) -> Result<CircuitArtifacts> {
+        info!("Setting up {} from {}", circuit.name, zkey_path.display());
+
+        if !zkey_path.is_file() {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "proving key {} does not exist",
+                zkey_path.display()
+            )));
+        }
+
+        let build_dir = self.config.build_path(&circuit.name);
+        fs::create_dir_all(&build_dir).await?;
+        let pkey_path = self.pkey_path(circuit);
+        let same_file = match (zkey_path.canonicalize(), pkey_path.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        };
+        if !same_file {
+            fs::copy(zkey_path, &pkey_path).await?;
+        }
+
+        let vkey_path = self.export_vkey(circuit, &pkey_path)?;
+        let vkey = self.export_verification_key(circuit).await?;
+        if vkey.protocol != self.config.protocol {
+            let _ = fs::remove_file(&vkey_path).await;
+            if !same_file {
+                let _ = fs::remove_file(&pkey_path).await;
+            }
+            return Err(CircomkitError::InvalidConfig(format!(
+                "proving key {} is for {}, but the configuration uses {}",
+                zkey_path.display(),
+                vkey.protocol,
+                self.config.protocol
+            )));
+        }
+        info!("Setup completed successfully");
+
+        Ok(CircuitArtifacts {
+            r1cs: build_dir.join(format!("{}.r1cs", circuit.name)),
+            wasm: self.wasm_path(circuit),
+            sym: build_dir.join(format!("{}.sym", circuit.name)),
+            pkey: Some(pkey_path),
+            vkey: Some(vkey_path),
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Export the verification key of `zkey_path` to the circuit's key path
+    fn export_vkey(&self, circuit: &CircuitConfig, zkey_path: &Path) -> Result<PathBuf> {
+        let vkey_path = self.vkey_path(circuit);
+        let (snarkjs, args) = self.snarkjs_args(vec![
+            "zkey".to_string(),
+            "export".to_string(),
+            "verificationkey".to_string(),
+            path_arg(zkey_path),
+            path_arg(&vkey_path),
+        ]);
+        self.run_tool(
+            Stage::Setup,
+            &snarkjs,
+            &args,
+            std::slice::from_ref(&vkey_path),
+        )?;
+        Ok(vkey_path)
+    }
+
+    /// Program and arguments `setup` runs to generate the proving key
+    ///
+    /// Fails with `InvalidConfig` if the protocol doesn't support the
+    /// circuit's prime.
+    pub fn setup_command(
+        &self,
+        circuit: &CircuitConfig,
+        ptau_path: &Path,
+    ) -> Result<(String, Vec<String>)> {
+        self.config
+            .protocol
+            .check_prime(circuit.prime.unwrap_or(self.config.prime))?;
+
+        let r1cs_path = self
+            .config
+            .build_path(&circuit.name)
+            .join(format!("{}.r1cs", circuit.name));
+
+        let args = vec![
+            self.config.protocol.to_string(),
+            "setup".to_string(),
+            path_arg(&r1cs_path),
+            path_arg(ptau_path),
+            path_arg(&self.pkey_path(circuit)),
+        ];
+        Ok(self.snarkjs_args(args))
+    }
+
+    /// Program and arguments `prove` runs for an existing witness
+    pub fn prove_command(
+        &self,
+        circuit: &CircuitConfig,
+        witness_path: &Path,
+    ) -> (String, Vec<String>) {
+        let (proof_path, public_path) = self.proof_paths(circuit);
+
+        let args = vec![
+            self.config.protocol.to_string(),
+            "prove".to_string(),
+            path_arg(&self.pkey_path(circuit)),
+            path_arg(witness_path),
+            path_arg(&proof_path),
+            path_arg(&public_path),
+        ];
+        self.snarkjs_args(args)
+    }
+
+    /// Program and arguments `verify` runs for proof and public signal files
+    pub fn verify_command(
+        &self,
+        circuit: &CircuitConfig,
+        proof_path: &Path,
+        public_path: &Path,
+    ) -> (String, Vec<String>) {
+        let args = vec![
+            self.config.protocol.to_string(),
+            "verify".to_string(),
+            path_arg(&self.vkey_path(circuit)),
+            path_arg(public_path),
+            path_arg(proof_path),
+        ];
+        self.snarkjs_args(args)
+    }
+
+    /// Program and arguments `export_verifier` runs
+    pub fn export_verifier_command(&self, circuit: &CircuitConfig) -> (String, Vec<String>) {
+        let args = vec![
+            "zkey".to_string(),
+            "export".to_string(),
+            "solidityverifier".to_string(),
+            path_arg(&self.pkey_path(circuit)),
+            path_arg(&self.verifier_path(circuit)),
+        ];
+        self.snarkjs_args(args)
+    }
+
+    /// Run an external tool for `stage` with `node_options` set, through
+    /// the backend
+    ///
+    /// `outputs` are the files the tool is expected to write. The first time
+    /// circom or snarkjs is run, its version is checked and a warning logged
+    /// if it's outside the supported range.
+    pub(crate) fn run_tool<I, S>(
+        &self,
+        stage: Stage,
+        program: &str,
+        args: I,
+        outputs: &[PathBuf],
+    ) -> Result<Output>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        if self.backend.checks_versions() {
+            self.check_version_once(program);
+        }
+
+        let mut cmd = self.config.node_process(program);
+        cmd.args(args);
+        self.backend.run(Invocation {
+            stage,
+            command: &mut cmd,
+            outputs,
+        })
+    }
+
+    /// Warn once per program if circom or snarkjs is an unsupported version
+    fn check_version_once(&self, program: &str) {
+        let tool = if program == self.config.circom_command() {
+            "circom"
+        } else if program == self.config.snarkjs_command() {
+            "snarkjs"
+        } else {
+            return;
+        };
+        let first_use = self
+            .checked_tools
+            .lock()
+            .is_ok_and(|mut checked| checked.insert(program.to_string()));
+        if !first_use {
+            return;
+        }
+
+        if let Some(warning) =
+            tool_version(program).and_then(|version| CompatibilityWarning::check(tool, &version))
+        {
+            warn!("{}", warning);
+        }
+    }
+
+    /// snarkjs program and `args` followed by the configured `snarkjs_flags`
+    fn snarkjs_args(&self, args: Vec<String>) -> (String, Vec<String>) {
+        self.config.snarkjs_args(args)
+    }
+
+    /// Path to the circuit's proving key
+    fn pkey_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        self.config
+            .build_path(&circuit.name)
+            .join(format!("{}_pkey.zkey", self.config.protocol))
+    }
+
+    /// Path to the circuit's verification key
+    fn vkey_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        self.config
+            .build_path(&circuit.name)
+            .join(format!("{}_vkey.json", self.config.protocol))
+    }
+
+    /// Paths `prove` writes the proof and public signals to
+    fn proof_paths(&self, circuit: &CircuitConfig) -> (PathBuf, PathBuf) {
+        let build_dir = self.config.build_path(&circuit.name);
+        (
+            build_dir.join(format!("{}_proof.json", self.config.protocol)),
+            build_dir.join("public.json"),
+        )
+    }
+
+    /// Path `export_verifier` writes the Solidity verifier to
+    fn verifier_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        self.config
+            .build_path(&circuit.name)
+            .join(format!("{}_verifier.sol", self.config.protocol))
+    }
+
+    /// Generate a proof
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(circuit = %circuit.name))
+    )]
+    pub async fn prove(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &CircuitSignals,
+    ) -> Result<(Proof, PublicSignals)> {
+        info!("Generating proof for: {}", circuit.name);
+
+        // First generate the witness
+        let witness = self.generate_witness(circuit, inputs).await?;
+        let result = self.prove_witness(circuit, &witness).await;
+        let _ = fs::remove_file(&witness.path).await;
+        result
+    }
+
+    /// Generate a proof for inputs given as a JSON object
+    pub async fn prove_json(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &serde_json::Value,
+    ) -> Result<(Proof, PublicSignals)> {
+        info!("Generating proof for: {}", circuit.name);
+
+        let witness = self.generate_witness_json(circuit, inputs).await?;
+        let result = self.prove_witness(circuit, &witness).await;
+        let _ = fs::remove_file(&witness.path).await;
+        result
+    }
+
+    /// Generate a proof from an existing witness
+    async fn prove_witness(
+        &self,
+        circuit: &CircuitConfig,
+        witness: &Witness,
+    ) -> Result<(Proof, PublicSignals)> {
+        if !self.pkey_path(circuit).exists() {
+            return Err(CircomkitError::proof_failed(
+                "Proving key not found. Run setup first.",
+            ));
+        }
+
+        let (proof_path, public_path) = self.proof_paths(circuit);
+        let (snarkjs, args) = self.prove_command(circuit, &witness.path);
+
+        self.run_tool(
+            Stage::Prove,
+            &snarkjs,
+            &args,
+            &[proof_path.clone(), public_path.clone()],
+        )
+        .map_err(|e| match e {
+            CircomkitError::CommandFailed { stderr, .. } => CircomkitError::proof_failed(stderr),
+            e => e,
+        })?;
+
+        // Read proof and public signals
+        let proof_content = fs::read_to_string(&proof_path).await?;
+        let proof_data: serde_json::Value = serde_json::from_str(&proof_content)?;
+
+        let public_content = fs::read_to_string(&public_path).await?;
+        let public_signals: Vec<String> = serde_json::from_str(&public_content)?;
+
+        info!("Proof generated successfully");
+
+        Ok((
+            Proof::from_snarkjs_json(self.config.protocol, proof_data)?,
+            PublicSignals::new(public_signals),
+        ))
+    }
+
+    /// Verify a proof
+    ///
+    /// With the `native-verify` feature, Groth16 proofs over BN128 are
+    /// verified in-process; everything else goes through snarkjs.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(circuit = %circuit.name))
+    )]
+    pub async fn verify(
+        &self,
+        circuit: &CircuitConfig,
+        proof: &Proof,
+        public_signals: &PublicSignals,
+    ) -> Result<bool> {
+        info!("Verifying proof for: {}", circuit.name);
+
+        if !self.vkey_path(circuit).exists() {
             return Err(CircomkitError::verification_failed(
                 "Verification key not found. Run setup first.",
             ));
         }
 
-        // Write proof and public signals to temp files
-        let proof_path = build_dir.join("temp_proof.json");
-        let public_path = build_dir.join("temp_public.json");
+        // Verify Groth16 over BN128 in-process, skipping node startup
+        #[cfg(feature = "native-verify")]
+        if self.config.protocol == Protocol::Groth16
+            && proof.protocol == Protocol::Groth16
+            && circuit.prime.unwrap_or(self.config.prime) == Prime::Bn128
+        {
+            let vkey = self.export_verification_key(circuit).await?;
+            let is_valid =
+                crate::utils::verify_groth16(&vkey.data, &proof.data, &public_signals.0)?;
+            info!("Proof verification result: {}", is_valid);
+            return Ok(is_valid);
+        }
+
+        // Write proof and public signals to temp files
+        let temp_dir = self.config.temp_path(&circuit.name);
+        fs::create_dir_all(&temp_dir).await?;
+        let suffix = temp_suffix();
+        let proof_path = temp_dir.join(format!("temp_proof_{}.json", suffix));
+        let public_path = temp_dir.join(format!("temp_public_{}.json", suffix));
+
+        fs::write(&proof_path, serde_json::to_string(proof)?).await?;
+        fs::write(&public_path, serde_json::to_string(&public_signals.0)?).await?;
+
+        let (snarkjs, args) = self.verify_command(circuit, &proof_path, &public_path);
+
+        let output = self.run_tool(Stage::Verify, &snarkjs, &args, &[]);
+
+        // Clean up temp files
+        let _ = fs::remove_file(&proof_path).await;
+        let _ = fs::remove_file(&public_path).await;
+
+        let is_valid = verification_result(output)?;
+        info!("Proof verification result: {}", is_valid);
+
+        Ok(is_valid)
+    }
+
+    /// Verify many proofs against the circuit's verification key
+    ///
+    /// Returns each item's validity, in order. With the `native-verify`
+    /// feature, Groth16 proofs over BN128 are verified in-process.
+    /// Otherwise a single node process loads snarkjs and verifies every
+    /// item, rather than starting snarkjs once per proof as a loop over
+    /// [`Circomkit::verify`] does. When the snarkjs package can't be found
+    /// from the configured snarkjs command, the proofs are verified one by
+    /// one instead.
+    pub async fn verify_batch(
+        &self,
+        circuit: &CircuitConfig,
+        items: &[(Proof, PublicSignals)],
+    ) -> Result<Vec<bool>> {
+        info!("Verifying {} proofs for: {}", items.len(), circuit.name);
+
+        let vkey_path = self.vkey_path(circuit);
+        if !vkey_path.exists() {
+            return Err(CircomkitError::verification_failed(
+                "Verification key not found. Run setup first.",
+            ));
+        }
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        #[cfg(feature = "native-verify")]
+        if self.config.protocol == Protocol::Groth16
+            && items
+                .iter()
+                .all(|(proof, _)| proof.protocol == Protocol::Groth16)
+            && circuit.prime.unwrap_or(self.config.prime) == Prime::Bn128
+        {
+            let vkey = self.export_verification_key(circuit).await?;
+            let verifier = crate::utils::Groth16Verifier::from_verification_key(&vkey)?;
+            return items
+                .iter()
+                .map(|(proof, public)| verifier.verify(&proof.data, &public.0))
+                .collect();
+        }
+
+        let temp_dir = self.config.temp_path(&circuit.name);
+        fs::create_dir_all(&temp_dir).await?;
+        let suffix = temp_suffix();
+        let batch_path = temp_dir.join(format!("temp_batch_{}.json", suffix));
+        let results_path = temp_dir.join(format!("temp_batch_results_{}.json", suffix));
+
+        let batch = serde_json::json!({
+            "protocol": self.config.protocol,
+            "vkey": path_arg(&vkey_path),
+            "items": items
+                .iter()
+                .map(|(proof, public)| serde_json::json!({
+                    "proof": proof,
+                    "publicSignals": public.0,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        fs::write(&batch_path, serde_json::to_string(&batch)?).await?;
+
+        let module = snarkjs_module(&self.config.snarkjs_command());
+        let args = [
+            "-e".to_string(),
+            VERIFY_BATCH_SCRIPT.to_string(),
+            path_arg(&batch_path),
+            path_arg(&results_path),
+            module.as_deref().map(path_arg).unwrap_or_default(),
+        ];
+        let output = self.run_tool(
+            Stage::Verify,
+            "node",
+            &args,
+            std::slice::from_ref(&results_path),
+        );
+        let results = match output {
+            Ok(_) => fs::read_to_string(&results_path)
+                .await
+                .map_err(CircomkitError::from)
+                .and_then(|content| Ok(serde_json::from_str::<Vec<bool>>(&content)?)),
+            Err(e) => Err(e),
+        };
+
+        let _ = fs::remove_file(&batch_path).await;
+        let _ = fs::remove_file(&results_path).await;
+
+        match results {
+            Ok(results) if results.len() == items.len() => {
+                info!(
+                    "Verified {} proofs, {} valid",
+                    results.len(),
+                    results.iter().filter(|&&valid| valid).count()
+                );
+                Ok(results)
+            }
+            Ok(results) => Err(CircomkitError::verification_failed(format!(
+                "Batch verification returned {} results for {} proofs",
+                results.len(),
+                items.len()
+            ))),
+            Err(CircomkitError::CommandFailed { stderr, .. })
+                if stderr.contains(SNARKJS_MODULE_MISSING) =>
+            {
+                warn!("snarkjs package not found; verifying proofs one by one");
+                let mut results = Vec::with_capacity(items.len());
+                for (proof, public) in items {
+                    results.push(self.verify(circuit, proof, public).await?);
+                }
+                Ok(results)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Verify proof files produced elsewhere with snarkjs
+    ///
+    /// Needs no circuit configuration or build directory: `vkey_path`,
+    /// `public_path`, and `proof_path` are passed to `snarkjs <protocol>
+    /// verify` as they are.
+    pub fn verify_files(
+        &self,
+        protocol: Protocol,
+        vkey_path: &Path,
+        public_path: &Path,
+        proof_path: &Path,
+    ) -> Result<bool> {
+        for path in [vkey_path, public_path, proof_path] {
+            if !path.exists() {
+                return Err(CircomkitError::verification_failed(format!(
+                    "{} not found",
+                    path.display()
+                )));
+            }
+        }
+
+        let (snarkjs, args) = self.snarkjs_args(vec![
+            protocol.to_string(),
+            "verify".to_string(),
+            path_arg(vkey_path),
+            path_arg(public_path),
+            path_arg(proof_path),
+        ]);
+        let is_valid = verification_result(self.run_tool(Stage::Verify, &snarkjs, &args, &[]))?;
+        info!("Proof verification result: {}", is_valid);
+
+        Ok(is_valid)
+    }
+
+    /// Compile, check outputs, set up, prove, and verify in one call
+    ///
+    /// Once the circuit compiles every stage runs, with failures recorded in
+    /// the report rather than returned. Only the signals in
+    /// `expected_outputs` are compared.
+    pub async fn full_check(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &CircuitSignals,
+        expected_outputs: &CircuitSignals,
+        ptau_path: &Path,
+    ) -> Result<FullCheckReport> {
+        let start = Instant::now();
+        let artifacts = match self.compile(circuit).await {
+            Ok(artifacts) => artifacts,
+            Err(e) => {
+                return Ok(FullCheckReport {
+                    artifacts: None,
+                    compile_error: Some(e.to_string()),
+                    info: None,
+                    witness: None,
+                    proof: None,
+                });
+            }
+        };
+        let compile = Some(start.elapsed());
+        let info = self.info(circuit).await.ok();
+
+        let start = Instant::now();
+        let outputs = match self.generate_witness(circuit, inputs).await {
+            Ok(witness) => {
+                let outputs = self.witness_to_signals(circuit, &witness.path).await;
+                let _ = fs::remove_file(&witness.path).await;
+                outputs
+            }
+            Err(e) => Err(e),
+        };
+        let durations = StageTimings {
+            compile,
+            witness: Some(start.elapsed()),
+            ..Default::default()
+        };
+        let witness = match outputs {
+            Ok(outputs) => WitnessTestResult::compare(outputs, expected_outputs.clone(), durations),
+            Err(e) => WitnessTestResult::failed(&e, durations),
+        };
+
+        Ok(FullCheckReport {
+            artifacts: Some(artifacts),
+            compile_error: None,
+            info,
+            witness: Some(witness),
+            proof: Some(self.check_proof(circuit, inputs, ptau_path).await),
+        })
+    }
+
+    /// Set up, prove, and verify for `full_check`, recording any failure
+    async fn check_proof(
+        &self,
+        circuit: &CircuitConfig,
+        inputs: &CircuitSignals,
+        ptau_path: &Path,
+    ) -> ProofTestResult {
+        let failed = |stage, e: CircomkitError, durations| ProofTestResult {
+            valid: false,
+            proof: None,
+            public_signals: None,
+            error: Some(e.to_string()),
+            error_kind: Some(ProofFailureKind::of(&e, stage)),
+            durations,
+        };
+        let mut durations = StageTimings::default();
+
+        let start = Instant::now();
+        let setup = self.setup(circuit, ptau_path).await;
+        durations.setup = Some(start.elapsed());
+        if let Err(e) = setup {
+            return failed(ProofFailureKind::SetupFailed, e, durations);
+        }
+
+        let start = Instant::now();
+        let proved = self.prove(circuit, inputs).await;
+        durations.prove = Some(start.elapsed());
+        let (proof, public_signals) = match proved {
+            Ok(proved) => proved,
+            Err(e) => return failed(ProofFailureKind::ProvingFailed, e, durations),
+        };
+
+        let start = Instant::now();
+        let verified = self.verify(circuit, &proof, &public_signals).await;
+        durations.verify = Some(start.elapsed());
+        match verified {
+            Ok(valid) => ProofTestResult {
+                valid,
+                proof: Some(proof),
+                public_signals: Some(public_signals),
+                error: (!valid).then(|| "Proof verification returned false".to_string()),
+                error_kind: (!valid).then_some(ProofFailureKind::VerificationReturnedFalse),
+                durations,
+            },
+            Err(e) => failed(ProofFailureKind::VerificationFailed, e, durations),
+        }
+    }
+
+    /// Export a Solidity verifier contract
+    pub async fn export_verifier(&self, circuit: &CircuitConfig) -> Result<PathBuf> {
+        info!("Exporting Solidity verifier for: {}", circuit.name);
+
+        if !self.pkey_path(circuit).exists() {
+            return Err(CircomkitError::proof_failed(
+                "Proving key not found. Run setup first.",
+            ));
+        }
+
+        let verifier_path = self.verifier_path(circuit);
+        let (snarkjs, args) = self.export_verifier_command(circuit);
+
+        self.run_tool(
+            Stage::Other,
+            &snarkjs,
+            &args,
+            std::slice::from_ref(&verifier_path),
+        )?;
+
+        info!("Verifier exported: {:?}", verifier_path);
+
+        Ok(verifier_path)
+    }
+
+    /// Read the verification key produced by `setup`
+    ///
+    /// Fails with `VerificationFailed` if setup hasn't run.
+    pub async fn export_verification_key(
+        &self,
+        circuit: &CircuitConfig,
+    ) -> Result<VerificationKey> {
+        let vkey_path = self.vkey_path(circuit);
+
+        if !vkey_path.exists() {
+            return Err(CircomkitError::verification_failed(format!(
+                "Verification key {} not found. Run setup first.",
+                vkey_path.display()
+            )));
+        }
+
+        let content = fs::read_to_string(&vkey_path).await?;
+        let vkey: VerificationKey = serde_json::from_str(&content)?;
+        Ok(vkey)
+    }
+
+    /// Size of a compiled circuit relative to a `baseline` compilation
+    pub async fn compare_info(
+        &self,
+        circuit: &CircuitConfig,
+        baseline: &CircuitInfo,
+    ) -> Result<InfoDelta> {
+        let info = self.info(circuit).await?;
+        Ok(InfoDelta::between(baseline, &info))
+    }
+
+    /// List the R1CS constraints of a compiled circuit
+    ///
+    /// Meant for small circuits: fails if there are more than
+    /// [`MAX_LISTED_CONSTRAINTS`]. Wires are named from the circuit's `.sym`
+    /// file when it exists, preferring the first signal on each wire.
+    pub async fn constraints(&self, circuit: &CircuitConfig) -> Result<Vec<Constraint>> {
+        let build_dir = self.config.build_path(&circuit.name);
+        let r1cs_path = build_dir.join(format!("{}.r1cs", circuit.name));
+        self.require_artifact(&r1cs_path, self.config.emit_r1cs, "emit_r1cs")?;
+        let mut constraints = read_r1cs_constraints(&r1cs_path, MAX_LISTED_CONSTRAINTS).await?;
+
+        let sym_path = build_dir.join(format!("{}.sym", circuit.name));
+        if !self.config.emit_sym || !sym_path.exists() {
+            return Ok(constraints);
+        }
+
+        let symbols = read_symbol_table(&sym_path).await?;
+        name_wires(&mut constraints, &symbols);
+        Ok(constraints)
+    }
+
+    /// Get information about a compiled circuit
+    pub async fn info(&self, circuit: &CircuitConfig) -> Result<CircuitInfo> {
+        let build_dir = self.config.build_path(&circuit.name);
+        let r1cs_path = build_dir.join(format!("{}.r1cs", circuit.name));
+
+        self.require_artifact(&r1cs_path, self.config.emit_r1cs, "emit_r1cs")?;
+
+        let (snarkjs, args) = self.snarkjs_args(vec![
+            "r1cs".to_string(),
+            "info".to_string(),
+            path_arg(&r1cs_path),
+            "--json".to_string(),
+        ]);
+
+        let output = self.run_tool(Stage::Other, &snarkjs, &args, &[])?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // Parse the output (snarkjs outputs human-readable format)
+        // This is a simplified parser
+        let mut info = CircuitInfo {
+            constraints: 0,
+            private_inputs: 0,
+            public_inputs: 0,
+            public_outputs: 0,
+            labels: 0,
+        };
+
+        for line in stdout.lines() {
+            if line.contains("Constraints:") {
+                if let Some(n) = line.split(':').nth(1) {
+                    info.constraints = n.trim().parse().unwrap_or(0);
+                }
+            } else if line.contains("Private Inputs:") {
+                if let Some(n) = line.split(':').nth(1) {
+                    info.private_inputs = n.trim().parse().unwrap_or(0);
+                }
+            } else if line.contains("Public Inputs:") {
+                if let Some(n) = line.split(':').nth(1) {
+                    info.public_inputs = n.trim().parse().unwrap_or(0);
+                }
+            } else if line.contains("Outputs:") {
+                if let Some(n) = line.split(':').nth(1) {
+                    info.public_outputs = n.trim().parse().unwrap_or(0);
+                }
+            } else if line.contains("Labels:")
+                && let Some(n) = line.split(':').nth(1)
+            {
+                info.labels = n.trim().parse().unwrap_or(0);
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Estimate a circuit's size by compiling only its r1cs
+    ///
+    /// A fast path for sizing, distinct from the full `compile`: circom
+    /// skips the wasm and symbol table, which dominate compile time for
+    /// large circuits, and the counts are read from the r1cs header without
+    /// snarkjs. The r1cs is written to an `estimate` directory inside the
+    /// circuit's build directory, leaving compiled artifacts untouched.
+    pub async fn estimate_info(&self, circuit: &CircuitConfig) -> Result<CircuitInfo> {
+        info!("Estimating circuit size: {}", circuit.name);
+        circuit.validate()?;
+        let _guard = self.compile_lock(&circuit.name).await;
+
+        let out_dir = self.config.build_path(&circuit.name).join("estimate");
+        fs::create_dir_all(&out_dir).await?;
+
+        let (circom, args) = self.compile_command_in(circuit, &out_dir, true).await?;
+        let r1cs_path = out_dir.join(format!("{}.r1cs", circuit.name));
+        self.run_tool(
+            Stage::Compile,
+            &circom,
+            &args,
+            std::slice::from_ref(&r1cs_path),
+        )?;
+
+        Ok(read_r1cs_header(&r1cs_path).await?.circuit_info())
+    }
+
+    /// Clean build artifacts for a circuit
+    pub async fn clean(&self, circuit: &CircuitConfig) -> Result<()> {
+        let build_dir = self.config.build_path(&circuit.name);
+        if build_dir.exists() {
+            fs::remove_dir_all(&build_dir).await?;
+            info!("Cleaned build directory: {:?}", build_dir);
+        }
+        Ok(())
+    }
+
+    /// Report which build artifacts of a circuit exist, without compiling
+    pub async fn list_artifacts(&self, circuit: &CircuitConfig) -> Result<ArtifactFiles> {
+        let build_dir = self.config.build_path(&circuit.name);
+        let existing = |path: PathBuf| path.exists().then_some(path);
+
+        Ok(ArtifactFiles {
+            r1cs: existing(build_dir.join(format!("{}.r1cs", circuit.name))),
+            wasm: existing(self.wasm_path(circuit)),
+            sym: existing(build_dir.join(format!("{}.sym", circuit.name))),
+            pkey: existing(self.pkey_path(circuit)),
+            vkey: existing(self.vkey_path(circuit)),
+        })
+    }
+
+    /// Path of the artifact manifest written by `save_artifacts`
+    pub fn artifacts_manifest_path(&self, circuit: &CircuitConfig) -> PathBuf {
+        self.config
+            .build_path(&circuit.name)
+            .join(format!("{}.artifacts.json", circuit.name))
+    }
+
+    /// Write a manifest of the circuit's artifacts to its build directory
+    ///
+    /// Lets another process, e.g. a test run after a separate build step,
+    /// pick the artifacts up with `load_artifacts` instead of recompiling.
+    pub async fn save_artifacts(
+        &self,
+        circuit: &CircuitConfig,
+        artifacts: &CircuitArtifacts,
+    ) -> Result<PathBuf> {
+        let path = self.artifacts_manifest_path(circuit);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(artifacts)?).await?;
+        Ok(path)
+    }
+
+    /// Read the artifact manifest written by `save_artifacts`
+    ///
+    /// Returns `None` if there is no manifest or any file it refers to is
+    /// missing, and fails if the manifest can't be parsed.
+    pub async fn load_artifacts(
+        &self,
+        circuit: &CircuitConfig,
+    ) -> Result<Option<CircuitArtifacts>> {
+        let path = self.artifacts_manifest_path(circuit);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        let artifacts: CircuitArtifacts = serde_json::from_str(&content)?;
+        if !artifacts.files_exist() {
+            debug!("Ignoring manifest {:?} with missing files", path);
+            return Ok(None);
+        }
+
+        if let Ok(mut paths) = self.wasm_paths.lock() {
+            paths.insert(circuit.name.clone(), artifacts.wasm.clone());
+        }
+        Ok(Some(artifacts))
+    }
+
+    /// Remove build artifacts of circuits that are no longer configured
+    ///
+    /// Build subdirectories and generated main components whose name isn't
+    /// among the loaded circuits are deleted, so call `load_circuits` first;
+    /// with no circuits loaded this fails rather than deleting every build.
+    /// The main component, temp, and PTAU directories are kept when they
+    /// are inside the build directory. Returns the deleted paths.
+    pub async fn prune(&self) -> Result<Vec<PathBuf>> {
+        if self.circuits.is_empty() {
+            return Err(CircomkitError::InvalidConfig(
+                "No circuits are loaded; call load_circuits before prune".to_string(),
+            ));
+        }
+
+        let mut removed = Vec::new();
+        if !self.config.dir_build.exists() {
+            return Ok(removed);
+        }
+
+        let main_dir = self.main_dir();
+        let mut kept = vec![
+            main_dir.clone(),
+            self.config.dir_ptau.clone(),
+            ptau_dir(&self.config.dir_ptau),
+        ];
+        kept.extend(
+            self.config
+                .dir_temp
+                .clone()
+                .filter(|dir| dir.as_os_str() != "auto"),
+        );
+        let mut entries = fs::read_dir(&self.config.dir_build).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            // Skip the directories holding generated main components, temp
+            // files, and PTAU files
+            if !entry.file_type().await?.is_dir() || kept.iter().any(|dir| dir.starts_with(&path)) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !self.circuits.contains_key(&name) {
+                fs::remove_dir_all(&path).await?;
+                removed.push(path);
+            }
+        }
+
+        if main_dir.exists() {
+            let mut entries = fs::read_dir(&main_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let orphan = path.extension().is_some_and(|ext| ext == "circom")
+                    && path
+                        .file_stem()
+                        .is_some_and(|stem| !self.circuits.contains_key(&*stem.to_string_lossy()));
+                if orphan {
+                    fs::remove_file(&path).await?;
+                    removed.push(path);
+                }
+            }
+        }
+
+        info!("Pruned {} stale build artifacts", removed.len());
+        removed.sort();
+        Ok(removed)
+    }
+
+    /// Clean all build artifacts
+    pub async fn clean_all(&self) -> Result<()> {
+        if self.config.dir_build.exists() {
+            fs::remove_dir_all(&self.config.dir_build).await?;
+            info!("Cleaned all build artifacts");
+        }
+        Ok(())
+    }
+
+    /// Read input signals from an input file
+    ///
+    /// The file may be JSON, TOML or YAML, chosen by its extension; see
+    /// [`CircomkitConfig::find_input`]. TOML and YAML need the `toml` and
+    /// `serde_yaml` features respectively.
+    pub async fn read_inputs(&self, circuit: &str, input_name: &str) -> Result<CircuitSignals> {
+        let path = self.config.find_input(circuit, input_name);
+        let content = fs::read_to_string(&path).await.map_err(|_| {
+            CircomkitError::InvalidSignals(format!("Input file not found: {:?}", path))
+        })?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("json");
+        parse_inputs(&content, extension)
+    }
+}
+
+/// Inlines circom includes into a single source
+struct Flattener {
+    /// Include paths searched after the including file's directory
+    search_paths: Vec<PathBuf>,
+    /// Canonical paths of files already inlined
+    visited: HashSet<PathBuf>,
+    /// The same paths, in the order they were inlined
+    files: Vec<PathBuf>,
+    /// Pragmas seen so far, deduplicated
+    pragmas: Vec<String>,
+    /// Flattened source without pragmas
+    body: String,
+}
+
+impl Flattener {
+    fn new(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths,
+            visited: HashSet::new(),
+            files: Vec::new(),
+            pragmas: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    /// Append `source`, recursively inlining its includes
+    fn inline(&mut self, source: &str, dir: &Path) -> Result<()> {
+        for line in source.lines() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with("pragma ") {
+                let is_version = trimmed.starts_with("pragma circom ");
+                let seen = self
+                    .pragmas
+                    .iter()
+                    .any(|p| p == trimmed || (is_version && p.starts_with("pragma circom ")));
+                if !seen {
+                    self.pragmas.push(trimmed.to_string());
+                }
+                continue;
+            }
+
+            if let Some(include) = parse_include(trimmed) {
+                let path = self.resolve(include, dir)?;
+                let canonical = std::fs::canonicalize(&path)?;
+                if self.visited.insert(canonical.clone()) {
+                    self.files.push(canonical.clone());
+                    let content = std::fs::read_to_string(&canonical)?;
+                    let parent = canonical.parent().unwrap_or(Path::new("."));
+                    self.body
+                        .push_str(&format!("\n// Inlined from {}\n", include));
+                    self.inline(&content, parent)?;
+                }
+                continue;
+            }
+
+            self.body.push_str(line);
+            self.body.push('\n');
+        }
+        Ok(())
+    }
+
+    /// Resolve an include relative to `dir`, then the search paths
+    fn resolve(&self, include: &str, dir: &Path) -> Result<PathBuf> {
+        std::iter::once(dir)
+            .chain(self.search_paths.iter().map(PathBuf::as_path))
+            .map(|base| base.join(include))
+            .find(|path| path.exists())
+            .ok_or_else(|| CircomkitError::CircuitNotFound(dir.join(include)))
+    }
+}
+
+/// Name the wires of each constraint after the first signal on that wire
+fn name_wires(constraints: &mut [Constraint], symbols: &SymbolTable) {
+    let mut names = HashMap::new();
+    for symbol in symbols.iter() {
+        if let Some(wire) = symbol.wire {
+            names.entry(wire).or_insert_with(|| symbol.name.clone());
+        }
+    }
+
+    for constraint in constraints {
+        let wires: BTreeSet<u32> = [&constraint.a, &constraint.b, &constraint.c]
+            .into_iter()
+            .flat_map(|lc| lc.keys().copied())
+            .collect();
+        constraint.names = wires
+            .into_iter()
+            .filter_map(|wire| Some((wire, names.get(&wire)?.clone())))
+            .collect();
+    }
+}
+
+/// Extract a `major.minor.patch` version from `--version` output, e.g.
+/// `circom compiler 2.1.9`, `v20.11.0`, or `snarkjs@0.7.4`
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| {
+            let word = word.rsplit('@').next().unwrap_or(word);
+            word.strip_prefix('v').unwrap_or(word)
+        })
+        .find(|word| {
+            let mut parts = word.split('.');
+            parts.clone().count() == 3 && parts.all(|p| p.parse::<u32>().is_ok())
+        })
+        .map(str::to_string)
+}
+
+/// Version reported by `program --version`, or `None` if it can't be run
+///
+/// The exit status is ignored since some tools, like snarkjs, print their
+/// version with a usage message and a non-zero exit code.
+fn tool_version(program: &str) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| parse_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Warning lines from circom output, e.g.
+/// `warning[CA01]: In template "A": Local signal x does not appear in any constraint`
+fn compiler_warnings(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("warning"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reduce decimal values at or above `modulus` in place, returning whether
+/// any were
+fn reduce_signal(value: &mut SignalValue, modulus: &BigUint) -> bool {
+    match value {
+        SignalValue::Single(s) => match s.parse::<BigUint>() {
+            Ok(n) if n >= *modulus => {
+                *s = (n % modulus).to_string();
+                true
+            }
+            _ => false,
+        },
+        SignalValue::Number(_) => false,
+        SignalValue::Array(values) => {
+            let mut reduced = false;
+            for value in values {
+                reduced |= reduce_signal(value, modulus);
+            }
+            reduced
+        }
+    }
+}
+
+/// Locate the wasm circom emitted for a circuit
+///
+/// circom writes `<name>_js/<name>.wasm`, but the directory name differs
+/// between versions and flags, so any subdirectory of the build directory
+/// holding `<name>.wasm` is accepted. Returns the conventional path if no
+/// wasm exists yet.
+fn find_wasm(build_dir: &Path, name: &str) -> PathBuf {
+    let file = format!("{}.wasm", name);
+    let conventional = build_dir.join(format!("{}_js", name)).join(&file);
+    if conventional.exists() {
+        return conventional;
+    }
+
+    let Ok(entries) = std::fs::read_dir(build_dir) else {
+        return conventional;
+    };
+    let mut found: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path().join(&file))
+        .filter(|path| path.is_file())
+        .collect();
+    found.sort();
+    found.into_iter().next().unwrap_or(conventional)
+}
+
+/// Main component source used unless the circuit sets `main_template`
+const DEFAULT_MAIN_TEMPLATE: &str = r#"pragma circom {version};
+
+include "{include}";
+
+component {main}{public} = {template}({params});
+"#;
+
+/// Phrases in snarkjs output when `wtns export json` isn't available
+const EXPORT_UNSUPPORTED_MARKERS: &[&str] =
+    &["Invalid command", "Unknown command", "not supported"];
+
+/// Phrases snarkjs logs when `verify` accepts a proof
+const VERIFY_VALID_MARKERS: &[&str] = &["OK!"];
+
+/// Phrases snarkjs logs when `verify` rejects a proof
+const VERIFY_INVALID_MARKERS: &[&str] = &["Invalid proof", "INVALID"];
+
+/// Message `VERIFY_BATCH_SCRIPT` fails with when snarkjs can't be loaded
+const SNARKJS_MODULE_MISSING: &str = "circomkit: snarkjs module not found";
+
+/// node script run by `verify_batch` with the batch file, the results file,
+/// and the snarkjs package directory, empty to resolve `snarkjs` from the
+/// working directory
+///
+/// Loads the verification key once and writes one boolean per item, `false`
+/// for items snarkjs throws on. Exits explicitly since snarkjs leaves curve
+/// worker threads running.
+const VERIFY_BATCH_SCRIPT: &str = r#"
+const fs = require("fs");
+const [batchPath, resultsPath, modulePath] = process.argv.slice(1);
+let snarkjs;
+try {
+    snarkjs = require(modulePath || "snarkjs");
+} catch (e) {
+    console.error("circomkit: snarkjs module not found");
+    process.exit(3);
+}
+(async () => {
+    const batch = JSON.parse(fs.readFileSync(batchPath, "utf8"));
+    const vkey = JSON.parse(fs.readFileSync(batch.vkey, "utf8"));
+    const results = [];
+    for (const item of batch.items) {
+        try {
+            results.push(await snarkjs[batch.protocol].verify(vkey, item.publicSignals, item.proof));
+        } catch (e) {
+            results.push(false);
+        }
+    }
+    fs.writeFileSync(resultsPath, JSON.stringify(results));
+    process.exit(0);
+})().catch((e) => {
+    console.error(e && e.stack ? e.stack : e);
+    process.exit(1);
+});
+"#;
+
+/// Per-process counter distinguishing temp files of concurrent calls
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Suffix unique to this call, for temp file names
+///
+/// Includes the process id so separate processes sharing a build directory
+/// don't collide either.
+fn temp_suffix() -> String {
+    format!(
+        "{}_{}",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Whether circom source declares a `component main`
+fn declares_main(source: &str) -> bool {
+    strip_comments(source)
+        .split([';', '}'])
+        .filter_map(|statement| statement.trim_start().strip_prefix("component"))
+        .filter_map(|rest| rest.trim_start().strip_prefix("main"))
+        .any(|rest| rest.trim_start().starts_with(['=', '{']))
+}
+
+/// Include path of `file` from a main component in `main_dir`
+///
+/// Both paths are relative to the working directory, so the include climbs
+/// out of `main_dir` and back down to `file`. Falls back to an absolute path
+/// when either isn't a plain relative path.
+fn relative_include(main_dir: &Path, file: &Path) -> String {
+    let plain = |path: &Path| {
+        path.components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    };
+    if !plain(main_dir) || !plain(file) {
+        let absolute = std::path::absolute(file).unwrap_or_else(|_| file.to_path_buf());
+        return absolute.to_string_lossy().to_string();
+    }
+
+    let depth = main_dir
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count();
+    let mut include = "../".repeat(depth);
+    include.push_str(
+        &file
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/"),
+    );
+    include
+}
+
+/// A path as a command-line argument
+fn path_arg(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Extract the path from an `include "...";` directive
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("include")?.trim_start();
+    let rest = rest.strip_prefix('"')?;
+    let end = rest.find('"')?;
+    Some(&rest[..end])
+}
+
+/// Input signal names declared by `template` in `source`, without array
+/// dimensions. Returns `None` if the template isn't defined in `source`.
+fn template_inputs(source: &str, template: &str) -> Option<Vec<String>> {
+    let source = strip_comments(source);
+    let body = template_body(&source, template)?;
+
+    let mut inputs = Vec::new();
+    for statement in body.split(';') {
+        let Some(pos) = statement.find("signal") else {
+            continue;
+        };
+        let Some(rest) = statement[pos + "signal".len()..]
+            .trim_start()
+            .strip_prefix("input")
+        else {
+            continue;
+        };
+
+        // Skip tags, e.g. `signal input {binary} in`
+        let mut rest = rest.trim_start();
+        if let Some(tagged) = rest.strip_prefix('{') {
+            rest = tagged.split_once('}').map_or("", |(_, r)| r);
+        }
+
+        // Stop at an inline assignment
+        let declarations = rest.split(['<', '=']).next().unwrap_or("");
+        for name in declarations.split(',') {
+            let name: String = name
+                .trim()
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                .collect();
+            if !name.is_empty() {
+                inputs.push(name);
+            }
+        }
+    }
+
+    Some(inputs)
+}
+
+/// Body of the `template` definition in `source`, between its braces
+fn template_body<'a>(source: &'a str, template: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    while let Some(pos) = source[offset..].find("template") {
+        let start = offset + pos;
+        offset = start + "template".len();
+
+        if source[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            continue;
+        }
+
+        // `template [parallel|custom] Name(`
+        let mut rest = source[offset..].trim_start();
+        for modifier in ["parallel", "custom"] {
+            if let Some(r) = rest.strip_prefix(modifier) {
+                rest = r.trim_start();
+            }
+        }
+        let Some(after_name) = rest.strip_prefix(template) else {
+            continue;
+        };
+        if !after_name.trim_start().starts_with('(') {
+            continue;
+        }
+
+        let open = source.len() - after_name.len() + after_name.find('{')?;
+        let mut depth = 0;
+        for (i, c) in source[open..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(&source[open + 1..open + i]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+    None
+}
+
+/// Remove `//` and `/* */` comments from circom source
+fn strip_comments(source: &str) -> String {
+    let mut output = String::with_capacity(source.len());
+    let mut rest = source;
+    loop {
+        let line = rest.find("//");
+        let block = rest.find("/*");
+        match (line, block) {
+            (Some(l), b) if b.is_none_or(|b| l < b) => {
+                output.push_str(&rest[..l]);
+                rest = rest[l..].find('\n').map_or("", |end| &rest[l + end..]);
+            }
+            (_, Some(b)) => {
+                output.push_str(&rest[..b]);
+                rest = rest[b..].find("*/").map_or("", |end| &rest[b + end + 2..]);
+            }
+            _ => {
+                output.push_str(rest);
+                return output;
+            }
+        }
+    }
+}
+
+/// JSON Schema for an object with the `declared` input signals
+fn input_schema(title: &str, declared: &BTreeMap<String, Vec<usize>>) -> serde_json::Value {
+    let properties: serde_json::Map<String, serde_json::Value> = declared
+        .iter()
+        .map(|(name, dims)| (name.clone(), signal_schema(dims)))
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "type": "object",
+        "properties": properties,
+        "required": declared.keys().collect::<Vec<_>>(),
+        "additionalProperties": false,
+    })
+}
+
+/// Directory of the snarkjs package behind a snarkjs command, e.g.
+/// `node_modules/snarkjs` for the `node_modules/.bin/snarkjs` link
+fn snarkjs_module(command: &str) -> Option<PathBuf> {
+    let program = which::which(command).ok()?.canonicalize().ok()?;
+    program
+        .ancestors()
+        .find(|dir| {
+            dir.file_name() == Some(OsStr::new("snarkjs")) && dir.join("package.json").exists()
+        })
+        .map(Path::to_path_buf)
+}
+
+/// Last modification time of `path`, if it exists
+async fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).await.and_then(|m| m.modified()).ok()
+}
+
+/// Hex SHA-256 of a command's program and arguments, for compile stamps
+fn command_hash<'a>(program: &'a str, args: impl IntoIterator<Item = &'a String>) -> String {
+    let mut hasher = Sha256::new();
+    for part in std::iter::once(program).chain(args.into_iter().map(String::as_str)) {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Interpret the output of `snarkjs <protocol> verify`
+///
+/// The exit code decides: snarkjs exits with 0 for a valid proof and
+/// nonzero for an invalid one. Its log is checked as well, and an exit code
+/// the log contradicts is an error. A nonzero exit that doesn't report an
+/// invalid proof, e.g. for a missing file, is an error too.
+fn verification_result(output: Result<Output>) -> Result<bool> {
+    let reports = |text: &str, markers: &[&str]| markers.iter().any(|m| text.contains(m));
+
+    match output {
+        Ok(output) => {
+            let log = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            if reports(&log, VERIFY_INVALID_MARKERS) {
+                return Err(CircomkitError::verification_failed(format!(
+                    "snarkjs exited successfully but reported an invalid proof: {}",
+                    log.trim()
+                )));
+            }
+            Ok(true)
+        }
+        Err(CircomkitError::CommandFailed { stderr, .. }) => {
+            if reports(&stderr, VERIFY_VALID_MARKERS) {
+                Err(CircomkitError::verification_failed(format!(
+                    "snarkjs failed but reported a valid proof: {}",
+                    stderr.trim()
+                )))
+            } else if reports(&stderr, VERIFY_INVALID_MARKERS) {
+                Ok(false)
+            } else {
+                Err(CircomkitError::verification_failed(stderr))
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Random signal with array dimensions `dims`, each element drawn from `range`
+fn random_signal(dims: &[usize], range: &Range<BigUint>, rng: &mut impl Rng) -> SignalValue {
+    match dims.split_first() {
+        Some((&len, rest)) => {
+            SignalValue::Array((0..len).map(|_| random_signal(rest, range, rng)).collect())
+        }
+        None => SignalValue::single(rng.gen_biguint_range(&range.start, &range.end)),
+    }
+}
+
+/// JSON Schema for a signal with array dimensions `dims`
+fn signal_schema(dims: &[usize]) -> serde_json::Value {
+    match dims.split_first() {
+        Some((&len, rest)) => serde_json::json!({
+            "type": "array",
+            "items": signal_schema(rest),
+            "minItems": len,
+            "maxItems": len,
+        }),
+        None => serde_json::json!({
+            "type": ["string", "integer"],
+            "pattern": "^(-?[0-9]+|0x[0-9a-fA-F]+)$",
+        }),
+    }
+}
+
+/// Compare supplied inputs against the declared input signals
+///
+/// Array inputs may be nested or flat; only the total element count is
+/// checked, matching how the witness calculator reads them.
+fn check_inputs(declared: &BTreeMap<String, Vec<usize>>, inputs: &CircuitSignals) -> Result<()> {
+    let mut problems = Vec::new();
+
+    let missing: Vec<&str> = declared
+        .keys()
+        .filter(|name| !inputs.contains_key(*name))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        problems.push(format!("missing inputs: {}", missing.join(", ")));
+    }
+
+    let mut unexpected: Vec<&str> = inputs
+        .keys()
+        .filter(|name| !declared.contains_key(*name))
+        .map(String::as_str)
+        .collect();
+    unexpected.sort();
+    if !unexpected.is_empty() {
+        problems.push(format!("unexpected inputs: {}", unexpected.join(", ")));
+    }
+
+    for (name, dims) in declared {
+        if let Some(value) = inputs.get(name) {
+            let expected: usize = dims.iter().product();
+            let actual = value.num_elements();
+            if actual != expected {
+                problems.push(format!(
+                    "'{}' expects {} element(s), got {}",
+                    name, expected, actual
+                ));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(CircomkitError::InvalidSignals(problems.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::backend::{MockBackend, run_command};
+    use crate::types::{Prime, Protocol};
+    use crate::utils::r1cs_bytes;
+
+    #[test]
+    fn test_new_circomkit() {
+        let config = CircomkitConfig::default();
+        let circomkit = Circomkit::new(config);
+        assert!(circomkit.is_ok());
+    }
+
+    #[test]
+    fn test_add_circuit() {
+        let config = CircomkitConfig::default();
+        let mut circomkit = Circomkit::new(config).unwrap();
+
+        let circuit = CircuitConfig::new("test")
+            .with_template("TestCircuit")
+            .with_params(vec![10]);
+
+        circomkit.add_circuit(circuit);
+
+        assert!(circomkit.get_circuit("test").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_save_circuits_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CircomkitConfig {
+            circuits: dir.path().join("circuits.json"),
+            ..Default::default()
+        };
+        std::fs::write(
+            &config.circuits,
+            r#"{"adder": {"name": "adder", "file": "adder.circom", "template": "Adder"}}"#,
+        )
+        .unwrap();
+
+        let mut circomkit = Circomkit::new(config.clone()).unwrap();
+        circomkit.load_circuits().await.unwrap();
+        circomkit.add_circuit(
+            CircuitConfig::new("mux")
+                .with_template("Mux1")
+                .with_params(vec![2])
+                .with_prime(Prime::Bls12381),
+        );
+        circomkit.save_circuits().await.unwrap();
+
+        let mut reloaded = Circomkit::new(config).unwrap();
+        reloaded.load_circuits().await.unwrap();
+        assert_eq!(reloaded.circuits, circomkit.circuits);
+
+        let copy = dir.path().join("nested").join("copy.json");
+        circomkit.save_circuits_to(&copy).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&copy).unwrap(),
+            std::fs::read_to_string(dir.path().join("circuits.json")).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_witness_json_requires_object() {
+        let circomkit = Circomkit::with_defaults().unwrap();
+        let circuit = CircuitConfig::new("test");
+
+        assert!(matches!(
+            circomkit
+                .generate_witness_json(&circuit, &serde_json::json!([1, 2]))
+                .await,
+            Err(CircomkitError::InvalidSignals(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_witnesses_requires_compiled_circuit() {
+        let config = CircomkitConfig::default().with_build_dir("test_build_missing");
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("not_compiled");
+
+        let result = circomkit
+            .generate_witnesses(&circuit, &[CircuitSignals::new()])
+            .await;
+
+        assert!(matches!(result, Err(CircomkitError::CircuitNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_generate_witnesses_validates_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mul.circom"), "template Mul() {}").unwrap();
+        // 1 output and 2 private inputs, `c <== a * b`
+        let backend = Arc::new(
+            MockBackend::new()
+                .with_file(Stage::Compile, ".r1cs", r1cs_bytes(1, 0, 2, 1))
+                .with_file(
+                    Stage::Compile,
+                    ".sym",
+                    "1,1,0,main.c\n2,2,0,main.a\n3,3,0,main.b\n",
+                ),
+        );
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_circuits_dir(dir.path())
+                .with_build_dir(dir.path().join("build")),
+        )
+        .unwrap()
+        .with_backend(backend.clone());
+        let circuit = CircuitConfig::new("mul").with_template("Mul");
+        circomkit.compile(&circuit).await.unwrap();
+
+        let batch = [
+            crate::signals! { "a" => 2, "b" => 3 },
+            crate::signals! { "a" => 4 },
+        ];
+        match circomkit.generate_witnesses(&circuit, &batch).await {
+            Err(CircomkitError::InvalidSignals(msg)) => {
+                assert!(msg.starts_with("input 1: "));
+                assert!(msg.contains("missing inputs: b"));
+            }
+            other => panic!("Expected InvalidSignals, got {:?}", other),
+        }
+
+        // Nothing was written or run for the valid input either
+        assert_eq!(backend.calls(), [Stage::Compile]);
+        let written = std::fs::read_dir(circomkit.config().temp_path("mul"))
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("input_")
+            })
+            .count();
+        assert_eq!(written, 0);
+
+        let witnesses = circomkit
+            .generate_witnesses(&circuit, &batch[..1])
+            .await
+            .unwrap();
+        assert_eq!(witnesses.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_witnesses_assert_failed() {
+        /// Fails the witness of the batch's second input set
+        #[derive(Debug)]
+        struct FailSecond(MockBackend);
+
+        impl Backend for FailSecond {
+            fn run(&self, invocation: Invocation<'_>) -> Result<Output> {
+                let second = invocation
+                    .command
+                    .get_args()
+                    .any(|arg| arg.to_string_lossy().ends_with("_1.json"));
+                if invocation.stage == Stage::Witness && second {
+                    return Err(CircomkitError::CommandFailed {
+                        command: "node".to_string(),
+                        exit_code: 1,
+                        stderr: "Error: Assert Failed.\nError in template Mul_0 line: 7\n"
+                            .to_string(),
+                    });
+                }
+                self.0.run(invocation)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let js_dir = dir.path().join("mul").join("mul_js");
+        std::fs::create_dir_all(&js_dir).unwrap();
+        std::fs::write(js_dir.join("mul.wasm"), b"").unwrap();
+        let circomkit = Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path()))
+            .unwrap()
+            .with_backend(FailSecond(MockBackend::new()));
+        let circuit = CircuitConfig::new("mul");
+
+        let batch = [
+            crate::signals! { "a" => 1 },
+            crate::signals! { "a" => 2 },
+            crate::signals! { "a" => 3 },
+        ];
+        match circomkit.generate_witnesses(&circuit, &batch).await {
+            Err(CircomkitError::AssertFailed {
+                template,
+                line,
+                message,
+            }) => {
+                assert_eq!(template.as_deref(), Some("Mul"));
+                assert_eq!(line, Some(7));
+                assert!(message.starts_with("input 1: "));
+            }
+            other => panic!("Expected AssertFailed, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_witnesses_use_separate_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let js_dir = dir.path().join("stub").join("stub_js");
+        std::fs::create_dir_all(&js_dir).unwrap();
+        std::fs::write(js_dir.join("stub.wasm"), b"").unwrap();
+        // Copies the input file to the witness path
+        std::fs::write(
+            js_dir.join("generate_witness.js"),
+            r#"const fs = require("fs"); fs.copyFileSync(process.argv[3], process.argv[4]);"#,
+        )
+        .unwrap();
+
+        let config = CircomkitConfig::default().with_build_dir(dir.path());
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("stub");
+
+        let (first, second) = (crate::signals! { "a" => 0 }, crate::signals! { "a" => 1 });
+        let witnesses = tokio::join!(
+            circomkit.generate_witness(&circuit, &first),
+            circomkit.generate_witness(&circuit, &second),
+        );
+
+        let mut paths = HashSet::new();
+        for (i, witness) in [witnesses.0, witnesses.1].into_iter().enumerate() {
+            let witness = witness.unwrap();
+            let content = std::fs::read_to_string(&witness.path).unwrap();
+            let written: serde_json::Value = serde_json::from_str(&content).unwrap();
+            // Inputs are written as decimal strings
+            assert_eq!(written["a"], i.to_string());
+            assert!(paths.insert(witness.path));
+        }
+
+        // Input files are removed once the witness is written
+        let leftover_inputs = std::fs::read_dir(dir.path().join("stub"))
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("input_")
+            })
+            .count();
+        assert_eq!(leftover_inputs, 0);
+    }
+
+    #[tokio::test]
+    async fn test_input_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let js_dir = dir.path().join("stub").join("stub_js");
+        std::fs::create_dir_all(&js_dir).unwrap();
+        std::fs::write(js_dir.join("stub.wasm"), b"").unwrap();
+        // Copies the input file to the witness path
+        std::fs::write(
+            js_dir.join("generate_witness.js"),
+            r#"const fs = require("fs"); fs.copyFileSync(process.argv[3], process.argv[4]);"#,
+        )
+        .unwrap();
+
+        // Derive `sum` from `a` and `b`
+        let circomkit = Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path()))
+            .unwrap()
+            .with_input_hook(|inputs| {
+                let sum = SignalValue::add(&inputs["a"], &inputs["b"], Prime::Bn128).unwrap();
+                inputs.insert("sum".to_string(), sum);
+            });
+        let circuit = CircuitConfig::new("stub");
+        let written = |witness: Witness| -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(&witness.path).unwrap()).unwrap()
+        };
+
+        let inputs = crate::signals! { "a" => 2, "b" => 3 };
+        let witness = circomkit.generate_witness(&circuit, &inputs).await.unwrap();
+        assert_eq!(
+            written(witness),
+            serde_json::json!({"a": "2", "b": "3", "sum": "5"})
+        );
+        // The caller's inputs are left alone
+        assert!(!inputs.contains_key("sum"));
+
+        let witness = circomkit
+            .generate_witness_json(&circuit, &serde_json::json!({"a": 4, "b": "0x10"}))
+            .await
+            .unwrap();
+        assert_eq!(written(witness)["sum"], "20");
+
+        let batch = [inputs, crate::signals! { "a" => 5, "b" => 6 }];
+        let witnesses = circomkit
+            .generate_witnesses(&circuit, &batch)
+            .await
+            .unwrap();
+        let sums: Vec<_> = witnesses
+            .into_iter()
+            .map(|witness| written(witness)["sum"].clone())
+            .collect();
+        assert_eq!(sums, ["5", "11"]);
+    }
+
+    #[test]
+    fn test_witness_inputs_reduced() {
+        let circomkit = Circomkit::new(CircomkitConfig::default()).unwrap();
+        let over = Prime::Bn128.modulus() + 5u32;
+        let inputs = CircuitSignals::from([
+            ("a".to_string(), SignalValue::single(&over)),
+            ("b".to_string(), SignalValue::array([1, 16])),
+            ("c".to_string(), SignalValue::from("-1")),
+            ("d".to_string(), SignalValue::from(vec!["0x1f", "7"])),
+        ]);
+
+        let written = circomkit.witness_inputs(&CircuitConfig::new("test"), &inputs);
+        assert_eq!(written["a"], SignalValue::from("5"));
+        assert_eq!(written["b"], SignalValue::array(["1", "16"]));
+        assert_eq!(written["c"], SignalValue::from("-1"));
+        // Hex inputs are written in decimal
+        assert_eq!(written["d"], SignalValue::array(["31", "7"]));
+
+        // Reduced by the circuit's own prime
+        let circuit = CircuitConfig::new("test").with_prime(Prime::Goldilocks);
+        let written = circomkit.witness_inputs(&circuit, &inputs);
+        assert_eq!(written["b"], SignalValue::array(["1", "16"]));
+        assert_ne!(written["a"], SignalValue::from("5"));
+        let inputs = CircuitSignals::from([(
+            "a".to_string(),
+            SignalValue::array([Prime::Goldilocks.modulus() + 1u32]),
+        )]);
+        let written = circomkit.witness_inputs(&circuit, &inputs);
+        assert_eq!(written["a"], SignalValue::array(["1"]));
+    }
+
+    #[tokio::test]
+    async fn test_input_size_limit() {
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_max_input_elements(3)).unwrap();
+        let circuit = CircuitConfig::new("test");
+
+        let small = CircuitSignals::from([("a".to_string(), SignalValue::array([1, 2, 3]))]);
+        assert!(circomkit.check_input_size(&small).is_ok());
+
+        let large = CircuitSignals::from([
+            ("a".to_string(), SignalValue::array([1, 2, 3])),
+            ("b".to_string(), SignalValue::from(4)),
+        ]);
+        match circomkit.generate_witness(&circuit, &large).await {
+            Err(CircomkitError::InvalidSignals(message)) => {
+                assert!(message.contains("4 values"), "{}", message)
+            }
+            other => panic!("Expected InvalidSignals, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_witness_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let js_dir = dir.path().join("stub/stub_js");
+        std::fs::create_dir_all(&js_dir).unwrap();
+        std::fs::write(js_dir.join("stub.wasm"), b"").unwrap();
+        std::fs::write(
+            js_dir.join("generate_witness.js"),
+            r#"const fs = require("fs"); fs.copyFileSync(process.argv[3], process.argv[4]);"#,
+        )
+        .unwrap();
+
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path())).unwrap();
+        let circuit = CircuitConfig::new("stub");
+
+        // Big integers pass through byte for byte
+        let input = r#"{"a": 123456789012345678901234567890123456789}"#;
+        let input_path = dir.path().join("input.json");
+        std::fs::write(&input_path, input).unwrap();
+
+        let witness = circomkit
+            .generate_witness_from_file(&circuit, &input_path)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&witness.path).unwrap(), input);
+        assert!(input_path.exists());
+
+        assert!(matches!(
+            circomkit
+                .generate_witness_from_file(&circuit, &dir.path().join("missing.json"))
+                .await,
+            Err(CircomkitError::InvalidSignals(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_witness_uses_tempdir() {
+        let dir = tempfile::tempdir().unwrap();
+        let js_dir = dir.path().join("build/stub/stub_js");
+        std::fs::create_dir_all(&js_dir).unwrap();
+        std::fs::write(js_dir.join("stub.wasm"), b"").unwrap();
+        std::fs::write(
+            js_dir.join("generate_witness.js"),
+            r#"const fs = require("fs"); fs.copyFileSync(process.argv[3], process.argv[4]);"#,
+        )
+        .unwrap();
+
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path().join("build"))
+            .with_tempdir(dir.path().join("scratch"));
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("stub");
+
+        let witness = circomkit
+            .generate_witness(&circuit, &crate::signals! { "a" => 1 })
+            .await
+            .unwrap();
+        assert_eq!(
+            witness.path.parent(),
+            Some(&*dir.path().join("scratch/stub"))
+        );
+
+        // Nothing but the compiled circuit is left in the build directory
+        let build_entries: Vec<_> = std::fs::read_dir(dir.path().join("build/stub"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(build_entries, vec!["stub_js"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_witness_to_signals() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("stub");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(
+            build.join("stub.sym"),
+            "1,1,0,main.out\n2,2,0,main.a\n3,-1,0,main.tmp\n4,3,1,main.sub.x\n",
+        )
+        .unwrap();
+
+        // `snarkjs wtns export json <wtns> <json>` writing a fixed witness
+        let snarkjs = dir.path().join("snarkjs");
+        std::fs::write(
+            &snarkjs,
+            "#!/bin/sh\nprintf '[\"1\",\"33\",\"3\",\"11\"]' > \"$5\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&snarkjs, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path())
+            .with_snarkjs_path(&snarkjs);
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("stub");
+        let witness = build.join("witness.wtns");
+
+        let signals = circomkit
+            .witness_to_signals(&circuit, &witness)
+            .await
+            .unwrap();
+        let value = |name: &str| signals.get(name).map(SignalValue::as_string);
+        assert_eq!(signals.len(), 3);
+        assert_eq!(value("out").as_deref(), Some("33"));
+        assert_eq!(value("a").as_deref(), Some("3"));
+        assert_eq!(value("sub.x").as_deref(), Some("11"));
+        assert!(!build.join("witness.json").exists());
+
+        let missing = CircuitConfig::new("missing");
+        assert!(matches!(
+            circomkit.witness_to_signals(&missing, &witness).await,
+            Err(CircomkitError::CircuitNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_flatten_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        let lib = dir.path().join("lib");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::create_dir_all(&lib).unwrap();
+
+        std::fs::write(
+            circuits.join("top.circom"),
+            "pragma circom 2.0.0;\ninclude \"helper.circom\";\ninclude \"gadget.circom\";\ntemplate Top() { signal input in; }\n",
+        )
+        .unwrap();
+        // helper and gadget include each other
+        std::fs::write(
+            circuits.join("helper.circom"),
+            "pragma circom 2.0.0;\ninclude \"gadget.circom\";\ntemplate Helper() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            lib.join("gadget.circom"),
+            "pragma circom 2.0.0;\npragma custom_templates;\ninclude \"../circuits/helper.circom\";\ntemplate Gadget() {}\n",
+        )
+        .unwrap();
+
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path().join("build"))
+            .with_include(&lib);
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("top")
+            .with_absolute_file(circuits.join("top.circom"))
+            .with_template("Top");
+
+        let flat = circomkit.flatten_source(&circuit).await.unwrap();
+
+        assert!(flat.starts_with("pragma circom 2.1.9;\npragma custom_templates;\n"));
+        assert_eq!(flat.matches("pragma circom").count(), 1);
+        assert_eq!(flat.matches("template Helper()").count(), 1);
+        assert_eq!(flat.matches("template Gadget()").count(), 1);
+        assert!(flat.contains("template Top()"));
+        assert!(flat.contains("component main = Top();"));
+        assert!(!flat.lines().any(|l| l.starts_with("include")));
+
+        // Each file once, in the order circom reads them
+        let canonical = |path: PathBuf| std::fs::canonicalize(path).unwrap();
+        assert_eq!(
+            circomkit.dependencies(&circuit).await.unwrap(),
+            [
+                canonical(circuits.join("top.circom")),
+                canonical(circuits.join("helper.circom")),
+                canonical(lib.join("gadget.circom")),
+            ]
+        );
+
+        std::fs::remove_file(lib.join("gadget.circom")).unwrap();
+        assert!(matches!(
+            circomkit.dependencies(&circuit).await,
+            Err(CircomkitError::CircuitNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_include_paths_with_circomlib() {
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_include("lib")
+                .with_circomlib("deps/node_modules/circomlib/circuits"),
+        )
+        .unwrap();
+        let circuit = CircuitConfig::new("test");
+
+        assert_eq!(
+            circomkit.include_paths(&circuit).await.unwrap(),
+            vec![
+                PathBuf::from("lib"),
+                PathBuf::from("circuits"),
+                PathBuf::from("deps/node_modules"),
+                PathBuf::from("deps/node_modules/circomlib/circuits"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_main_template() {
+        let circomkit = Circomkit::new(CircomkitConfig::default()).unwrap();
+        let circuit = CircuitConfig::new("test")
+            .with_template("Test")
+            .with_params(vec![2, 3])
+            .with_public(vec!["a".to_string()]);
+        let default = circomkit.main_component_source(&circuit);
+
+        let circuit = circuit.with_main_template(
+            "pragma circom 2.1.0;\ninclude \"{include}\";\ninclude \"prelude.circom\";\n\
+             component main{public} = {template}({params});\n",
+        );
+        let source = circomkit.main_component_source(&circuit);
+        assert_eq!(
+            source,
+            "pragma circom 2.1.0;\ninclude \"../../../circuits/test.circom\";\n\
+             include \"prelude.circom\";\ncomponent main {public [a]} = Test(2, 3);\n"
+        );
+        assert!(default.contains("component main {public [a]} = Test(2, 3);"));
+    }
+
+    #[test]
+    fn test_main_component_pragma() {
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_circom_version("2.2.0")).unwrap();
+        let circuit = CircuitConfig::new("test").with_template("Test");
+
+        assert!(
+            circomkit
+                .main_component_source(&circuit)
+                .starts_with("pragma circom 2.2.0;\n")
+        );
+
+        let source = circomkit.main_component_source(&circuit.with_main_name("top"));
+        assert!(source.contains("component top = Test();"));
+
+        let circuit = CircuitConfig::new("test")
+            .with_template("Test")
+            .with_named_params(vec![("n".to_string(), 3), ("k".to_string(), 1)]);
+        let source = circomkit.main_component_source(&circuit);
+        assert!(source.contains("component main = Test(3, 1);"));
+    }
+
+    #[tokio::test]
+    async fn test_command_builders() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_build_dir(&build)
+                .with_optimization(2)
+                .with_include("lib"),
+        )
+        .unwrap();
+        let circuit = CircuitConfig::new("test").with_template("Test");
+        let arg = |p: &str| build.join(p).to_string_lossy().to_string();
+
+        // The program follows `CIRCOMKIT_CIRCOM`, and a circomlib found
+        // above the working directory adds a trailing include
+        let (program, args) = circomkit.compile_command(&circuit).await.unwrap();
+        assert_eq!(program, circomkit.config().circom_command());
+        assert_eq!(
+            args[..13],
+            [
+                arg(".circomkit/main/test.circom"),
+                "--r1cs".into(),
+                "--wasm".into(),
+                "--sym".into(),
+                "-o".into(),
+                arg("test"),
+                "-p".into(),
+                "bn128".into(),
+                "--O2".into(),
+                "-l".into(),
+                "lib".into(),
+                "-l".into(),
+                "circuits".into(),
+            ]
+        );
+        assert!(build.join(".circomkit/main/test.circom").exists());
+
+        let (program, args) = circomkit.prove_command(&circuit, Path::new("w.wtns"));
+        assert_eq!(program, circomkit.config().snarkjs_command());
+        assert_eq!(
+            args,
+            [
+                "groth16".into(),
+                "prove".into(),
+                arg("test/groth16_pkey.zkey"),
+                "w.wtns".into(),
+                arg("test/groth16_proof.json"),
+                arg("test/public.json"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compile_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::write(circuits.join("count.circom"), "template Count() {}").unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(dir.path().join("build"))
+            .with_emit_wasm(false);
+        let backend = Arc::new(MockBackend::new());
+        let circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(backend.clone());
+        let circuit = CircuitConfig::new("count").with_template("Count");
+
+        let (_, args) = circomkit.compile_command(&circuit).await.unwrap();
+        assert!(args.contains(&"--r1cs".to_string()));
+        assert!(args.contains(&"--sym".to_string()));
+        assert!(!args.contains(&"--wasm".to_string()));
+
+        // Only the emitted artifacts are built and checked
+        let artifacts = circomkit.compile(&circuit).await.unwrap();
+        assert!(artifacts.r1cs.exists() && artifacts.sym.exists());
+        assert!(!artifacts.wasm.exists());
+        assert!(circomkit.is_compiled(&circuit).await);
+
+        match circomkit
+            .generate_witness(&circuit, &CircuitSignals::new())
+            .await
+        {
+            Err(CircomkitError::InvalidConfig(msg)) => assert!(msg.contains("`emit_wasm`")),
+            other => panic!("Expected InvalidConfig, got {:?}", other),
+        }
+
+        // Turning an emission back on needs a recompile
+        let circomkit = Circomkit::new(config.clone().with_emit_wasm(true)).unwrap();
+        assert!(!circomkit.is_compiled(&circuit).await);
+
+        // Leftovers from an earlier compile aren't used once disabled
+        let circomkit = Circomkit::new(config.clone().with_emit_r1cs(false)).unwrap();
+        match circomkit.info(&circuit).await {
+            Err(CircomkitError::InvalidConfig(msg)) => assert!(msg.contains("`emit_r1cs`")),
+            other => panic!("Expected InvalidConfig, got {:?}", other),
+        }
+        let circomkit = Circomkit::new(config.with_emit_sym(false)).unwrap();
+        match circomkit.input_schema(&circuit).await {
+            Err(CircomkitError::InvalidConfig(msg)) => assert!(msg.contains("`emit_sym`")),
+            other => panic!("Expected InvalidConfig, got {:?}", other),
+        }
+        assert_eq!(backend.calls(), [Stage::Compile]);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_info() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("count.circom"), "template Count() {}").unwrap();
+
+        // 1 output, 2 public and 3 private inputs, 42 constraints
+        let r1cs = r1cs_bytes(1, 2, 3, 42);
+        let backend = Arc::new(MockBackend::new().with_file(Stage::Compile, ".r1cs", r1cs));
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_circuits_dir(dir.path())
+                .with_build_dir(dir.path().join("build"))
+                .with_inspect(true),
+        )
+        .unwrap()
+        .with_backend(backend.clone());
+        let circuit = CircuitConfig::new("count").with_template("Count");
+
+        let info = circomkit.estimate_info(&circuit).await.unwrap();
+        assert_eq!(
+            info,
+            CircuitInfo {
+                constraints: 42,
+                private_inputs: 3,
+                public_inputs: 2,
+                public_outputs: 1,
+                labels: 7,
+            }
+        );
+        assert_eq!(backend.calls(), [Stage::Compile]);
+
+        // Compiled artifacts are left alone
+        assert!(!circomkit.is_compiled(&circuit).await);
+        let artifacts = circomkit.list_artifacts(&circuit).await.unwrap();
+        assert!(artifacts.r1cs.is_none() && artifacts.wasm.is_none());
+
+        // Only the r1cs is requested, next to but apart from the build
+        let build = circomkit.config().build_path("count");
+        let (_, args) = circomkit
+            .compile_command_in(&circuit, &build.join("estimate"), true)
+            .await
+            .unwrap();
+        assert!(args.contains(&"--r1cs".to_string()));
+        for flag in ["--wasm", "--sym", "--inspect"] {
+            assert!(!args.contains(&flag.to_string()), "{} passed", flag);
+        }
+        assert!(args.contains(&path_arg(&build.join("estimate"))));
+    }
+
+    #[tokio::test]
+    async fn test_compile_command_includes_source_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        let sub = circuits.join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(
+            sub.join("foo.circom"),
+            "pragma circom 2.0.0;\ninclude \"./bar.circom\";\ntemplate Foo() { component bar = Bar(); }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            sub.join("bar.circom"),
+            "pragma circom 2.0.0;\ntemplate Bar() {}\n",
+        )
+        .unwrap();
+
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_circuits_dir(&circuits)
+                .with_build_dir(dir.path().join("build")),
+        )
+        .unwrap();
+        let circuit = CircuitConfig::new("foo")
+            .with_file("sub/foo.circom")
+            .with_template("Foo");
+
+        let (_, args) = circomkit.compile_command(&circuit).await.unwrap();
+        let includes: Vec<PathBuf> = args
+            .windows(2)
+            .filter(|w| w[0] == "-l")
+            .map(|w| PathBuf::from(&w[1]))
+            .collect();
+        assert_eq!(includes, vec![sub.clone()]);
+
+        // `./bar.circom` doesn't resolve from the generated main component,
+        // only from the added include path
+        assert!(!circomkit.main_dir().join("bar.circom").exists());
+        assert!(includes.iter().any(|dir| dir.join("./bar.circom").exists()));
+
+        // Configured include paths aren't repeated
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_circuits_dir(&circuits)
+                .with_build_dir(dir.path().join("build"))
+                .with_include(&sub),
+        )
+        .unwrap();
+        let (_, args) = circomkit.compile_command(&circuit).await.unwrap();
+        assert_eq!(args.iter().filter(|arg| *arg == "-l").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_compile_command_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_build_dir(dir.path())
+                .with_optimization(2),
+        )
+        .unwrap();
+        let circuit = CircuitConfig::new("test")
+            .with_optimization(0)
+            .with_prime(Prime::Bls12381);
+
+        let (_, args) = circomkit.compile_command(&circuit).await.unwrap();
+        assert!(args.windows(2).any(|w| w == ["-p", "bls12381"]));
+        assert!(args.contains(&"--O0".to_string()));
+        assert!(!args.contains(&"--O2".to_string()));
+
+        // An out-of-range override is rejected rather than passed to circom
+        let unoptimizable = CircuitConfig::new("test").with_optimization(7);
+        assert!(matches!(
+            circomkit.compile(&unoptimizable).await,
+            Err(CircomkitError::InvalidConfig(msg)) if msg.contains("optimization level")
+        ));
+
+        let json = serde_json::to_value(CircuitConfig::new("test")).unwrap();
+        assert!(json.get("optimization").is_none());
+        assert!(json.get("prime").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compile_command_output_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_build_dir(dir.path())
+                .with_emit_json(true)
+                .with_inspect(true)
+                .with_circom_flag("--verbose"),
+        )
+        .unwrap();
+
+        let (_, args) = circomkit
+            .compile_command(&CircuitConfig::new("test"))
+            .await
+            .unwrap();
+        assert!(args.ends_with(&["--json".into(), "--inspect".into(), "--verbose".into()]));
+        assert!(!args.contains(&"--wat".to_string()));
+    }
+
+    #[test]
+    fn test_compiler_warnings() {
+        let output = "template instances: 2\n\
+            warning[CA01]: In template \"A\": Local signal x does not appear in any constraint\n\
+            \u{20}  ┌─ \"a.circom\":4:5\n\
+            Everything went okay";
+
+        assert_eq!(
+            compiler_warnings(output),
+            ["warning[CA01]: In template \"A\": Local signal x does not appear in any constraint"]
+        );
+    }
+
+    #[test]
+    fn test_template_inputs() {
+        let source = r#"
+            template Other() { signal input wrong; }
+            /* template Adder() { signal input commented; } */
+            template parallel Adder(n) {
+                signal input a, b[n]; // signal input skipped;
+                signal input {binary} flag;
+                signal input c <== 1;
+                signal output out;
+                for (var i = 0; i < n; i++) { signal intermediate; }
+            }
+        "#;
 
-        fs::write(&proof_path, serde_json::to_string(&proof.data)?).await?;
-        fs::write(&public_path, serde_json::to_string(&public_signals.0)?).await?;
+        assert_eq!(
+            template_inputs(source, "Adder").unwrap(),
+            ["a", "b", "flag", "c"]
+        );
+        assert_eq!(template_inputs(source, "Other").unwrap(), ["wrong"]);
+        assert_eq!(template_inputs(source, "Add"), None);
+    }
 
-        let snarkjs = self.config.snarkjs_command();
+    #[tokio::test]
+    async fn test_validate_public_signals() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("adder.circom");
+        std::fs::write(
+            &file,
+            "template Adder() { signal input a; signal input b[2]; signal output c; }",
+        )
+        .unwrap();
 
-        let output = Command::new(&snarkjs)
-            .arg(&protocol)
-            .arg("verify")
-            .arg(&vkey_path)
-            .arg(&public_path)
-            .arg(&proof_path)
-            .output()
-            .map_err(|e| CircomkitError::Io(e))?;
+        let circomkit = Circomkit::new(CircomkitConfig::default()).unwrap();
+        let circuit = CircuitConfig::new("adder")
+            .with_absolute_file(&file)
+            .with_template("Adder");
 
-        // Clean up temp files
-        let _ = fs::remove_file(&proof_path).await;
-        let _ = fs::remove_file(&public_path).await;
+        let valid = circuit.clone().with_public(vec!["a".into(), "b".into()]);
+        assert!(circomkit.validate_public_signals(&valid).await.is_ok());
+
+        let invalid = circuit
+            .clone()
+            .with_public(vec!["a".into(), "bb".into(), "c".into()]);
+        match circomkit.validate_public_signals(&invalid).await {
+            Err(CircomkitError::InvalidConfig(msg)) => assert!(msg.ends_with(": bb, c")),
+            other => panic!("Expected InvalidConfig, got {:?}", other),
+        }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.contains("Invalid proof") || stderr.contains("INVALID") {
-                return Ok(false);
+        // Elements of a declared array can't be made public on their own
+        let elements = circuit.with_public(vec!["a".into(), "b[0]".into(), "b[5]".into()]);
+        match circomkit.validate_public_signals(&elements).await {
+            Err(CircomkitError::InvalidConfig(msg)) => {
+                assert!(msg.contains("whole signals"));
+                assert!(msg.ends_with(": b[0], b[5]"));
             }
-            return Err(CircomkitError::verification_failed(stderr.to_string()));
+            other => panic!("Expected InvalidConfig, got {:?}", other),
         }
+        let missing = CircuitConfig::new("missing").with_public(vec!["b[0]".into()]);
+        assert!(circomkit.validate_public_signals(&missing).await.is_err());
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let is_valid = stdout.contains("OK") || stdout.contains("valid");
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("circom compiler 2.1.9\n").as_deref(),
+            Some("2.1.9")
+        );
+        assert_eq!(parse_version("v20.11.0\n").as_deref(), Some("20.11.0"));
+        assert_eq!(
+            parse_version("snarkjs@0.7.4\nUsage:").as_deref(),
+            Some("0.7.4")
+        );
+        assert_eq!(parse_version("circom compiler"), None);
+        assert_eq!(tool_version("circomkit-no-such-tool"), None);
+    }
 
-        info!("Proof verification result: {}", is_valid);
+    #[tokio::test]
+    async fn test_export_verification_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path())).unwrap();
+        let circuit = CircuitConfig::new("test");
 
-        Ok(is_valid)
+        match circomkit.export_verification_key(&circuit).await {
+            Err(err @ CircomkitError::VerificationFailed { .. }) => {
+                assert!(err.to_string().contains("Run setup first"))
+            }
+            other => panic!("Expected VerificationFailed, got {:?}", other),
+        }
+
+        std::fs::create_dir_all(dir.path().join("test")).unwrap();
+        std::fs::write(
+            dir.path().join("test/groth16_vkey.json"),
+            r#"{"protocol": "groth16", "curve": "bn128", "nPublic": 2, "IC": []}"#,
+        )
+        .unwrap();
+
+        let vkey = circomkit.export_verification_key(&circuit).await.unwrap();
+        assert_eq!(vkey.protocol, Protocol::Groth16);
+        assert_eq!(vkey.data["curve"], "bn128");
+        assert_eq!(vkey.num_public().unwrap(), 2);
     }
 
-    /// Export a Solidity verifier contract
-    pub async fn export_verifier(&self, circuit: &CircuitConfig) -> Result<PathBuf> {
-        info!("Exporting Solidity verifier for: {}", circuit.name);
+    #[tokio::test]
+    async fn test_prune_and_list_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        for path in ["kept", "stale", ".circomkit/main"] {
+            std::fs::create_dir_all(build.join(path)).unwrap();
+        }
+        std::fs::write(build.join("kept/kept.r1cs"), b"").unwrap();
+        std::fs::write(build.join(".circomkit/main/kept.circom"), b"").unwrap();
+        std::fs::write(build.join(".circomkit/main/stale.circom"), b"").unwrap();
 
-        let build_dir = self.config.build_path(&circuit.name);
-        let protocol = self.config.protocol.to_string();
-        let zkey_path = build_dir.join(format!("{}_pkey.zkey", protocol));
+        let mut circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(&build)).unwrap();
+        let kept = CircuitConfig::new("kept");
+        circomkit.add_circuit(kept.clone());
 
-        if !zkey_path.exists() {
-            return Err(CircomkitError::proof_failed(
-                "Proving key not found. Run setup first.",
-            ));
+        let artifacts = circomkit.list_artifacts(&kept).await.unwrap();
+        assert_eq!(artifacts.r1cs, Some(build.join("kept/kept.r1cs")));
+        assert_eq!(artifacts.wasm, None);
+        assert_eq!(artifacts.vkey, None);
+
+        let removed = circomkit.prune().await.unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                build.join(".circomkit/main/stale.circom"),
+                build.join("stale")
+            ]
+        );
+        assert!(build.join("kept/kept.r1cs").exists());
+        assert!(build.join(".circomkit/main/kept.circom").exists());
+
+        // Temp and PTAU directories inside the build directory are kept
+        for path in ["scratch/kept", "ptau"] {
+            std::fs::create_dir_all(build.join(path)).unwrap();
         }
+        std::fs::write(build.join("ptau/powersOfTau28_hez_final_08.ptau"), b"").unwrap();
+        let mut circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_build_dir(&build)
+                .with_tempdir(build.join("scratch"))
+                .with_ptau_dir(build.join("ptau")),
+        )
+        .unwrap();
+        circomkit.add_circuit(kept);
+        assert!(circomkit.prune().await.unwrap().is_empty());
+        assert!(build.join("ptau/powersOfTau28_hez_final_08.ptau").exists());
+        assert!(build.join("scratch/kept").exists());
+
+        // Without loaded circuits nothing is deleted
+        let circomkit = Circomkit::new(CircomkitConfig::default().with_build_dir(&build)).unwrap();
+        assert!(matches!(
+            circomkit.prune().await,
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+        assert!(build.join("kept/kept.r1cs").exists());
+    }
 
-        let verifier_path = build_dir.join(format!("{}_verifier.sol", protocol));
+    #[tokio::test]
+    async fn test_is_compiled() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        let build = dir.path().join("build");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::write(circuits.join("cached.circom"), "template cached() {}").unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(&build);
+        let circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(MockBackend::new());
+        let circuit = CircuitConfig::new("cached");
+        assert!(!circomkit.is_compiled(&circuit).await);
 
-        let snarkjs = self.config.snarkjs_command();
+        circomkit.compile(&circuit).await.unwrap();
+        assert!(circomkit.is_compiled(&circuit).await);
 
-        let output = Command::new(&snarkjs)
-            .arg("zkey")
-            .arg("export")
-            .arg("solidityverifier")
-            .arg(&zkey_path)
-            .arg(&verifier_path)
-            .output()
-            .map_err(|e| CircomkitError::Io(e))?;
+        // A different main component needs a recompile
+        assert!(
+            !circomkit
+                .is_compiled(&circuit.clone().with_params(vec![2]))
+                .await
+        );
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: snarkjs,
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
-            });
+        // So do different compile options
+        assert!(
+            !circomkit
+                .is_compiled(&circuit.clone().with_prime(Prime::Bls12381))
+                .await
+        );
+        let optimized = Circomkit::new(config.clone().with_optimization(2)).unwrap();
+        assert!(!optimized.is_compiled(&circuit).await);
+        let inspected = Circomkit::new(config.clone().with_inspect(true)).unwrap();
+        assert!(!inspected.is_compiled(&circuit).await);
+
+        // And a different circom or snarkjs version
+        let recorded = |program: String, version: &str| {
+            circomkit
+                .tool_versions
+                .lock()
+                .unwrap()
+                .insert(program, Some(version.to_string()))
+        };
+        recorded(config.circom_command(), "2.1.9");
+        assert!(!circomkit.is_compiled(&circuit).await);
+        circomkit.compile(&circuit).await.unwrap();
+        assert!(circomkit.is_compiled(&circuit).await);
+        recorded(config.snarkjs_command(), "0.7.4");
+        assert!(!circomkit.is_compiled(&circuit).await);
+        recorded(config.circom_command(), "2.2.0");
+        circomkit.compile(&circuit).await.unwrap();
+        assert!(circomkit.is_compiled(&circuit).await);
+
+        // And artifacts without a stamp, e.g. from an interrupted compile
+        std::fs::remove_file(build.join("cached/cached.compile-stamp")).unwrap();
+        assert!(!circomkit.is_compiled(&circuit).await);
+        circomkit.compile(&circuit).await.unwrap();
+        assert!(circomkit.is_compiled(&circuit).await);
+
+        // So does a source edited after the build
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(circuits.join("cached.circom"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(!circomkit.is_compiled(&circuit).await);
+    }
+
+    #[tokio::test]
+    async fn test_is_compiled_tracks_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        let build = dir.path().join("build");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::write(
+            circuits.join("outer.circom"),
+            "include \"inner.circom\";\ntemplate outer() {}",
+        )
+        .unwrap();
+        std::fs::write(circuits.join("inner.circom"), "template inner() {}").unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(&build);
+        let circomkit = Circomkit::new(config)
+            .unwrap()
+            .with_backend(MockBackend::new());
+        let circuit = CircuitConfig::new("outer");
+
+        circomkit.compile(&circuit).await.unwrap();
+        assert!(circomkit.is_compiled(&circuit).await);
+
+        // Editing an included file invalidates the build
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(circuits.join("inner.circom"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(!circomkit.is_compiled(&circuit).await);
+
+        // As does a missing one
+        std::fs::remove_file(circuits.join("inner.circom")).unwrap();
+        assert!(!circomkit.is_compiled(&circuit).await);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        let circomkit = Circomkit::new(CircomkitConfig::default().with_build_dir(&build)).unwrap();
+        let circuit = CircuitConfig::new("saved");
+        assert_eq!(circomkit.load_artifacts(&circuit).await.unwrap(), None);
+
+        let artifacts = CircuitArtifacts {
+            r1cs: build.join("saved/saved.r1cs"),
+            wasm: build.join("saved/js/saved.wasm"),
+            sym: build.join("saved/saved.sym"),
+            pkey: None,
+            vkey: None,
+            warnings: vec!["unconstrained".to_string()],
+        };
+        let manifest = circomkit
+            .save_artifacts(&circuit, &artifacts)
+            .await
+            .unwrap();
+        assert_eq!(manifest, build.join("saved/saved.artifacts.json"));
+
+        // Missing files make the manifest stale
+        assert_eq!(circomkit.load_artifacts(&circuit).await.unwrap(), None);
+
+        std::fs::create_dir_all(build.join("saved/js")).unwrap();
+        for path in [&artifacts.r1cs, &artifacts.wasm, &artifacts.sym] {
+            std::fs::write(path, b"").unwrap();
         }
+        let loaded = circomkit.load_artifacts(&circuit).await.unwrap();
+        assert_eq!(loaded.as_ref(), Some(&artifacts));
+        // The recorded wasm is used even outside `<name>_js`
+        assert_eq!(circomkit.wasm_path(&circuit), artifacts.wasm);
 
-        info!("Verifier exported: {:?}", verifier_path);
+        std::fs::write(&manifest, "not json").unwrap();
+        assert!(circomkit.load_artifacts(&circuit).await.is_err());
+    }
 
-        Ok(verifier_path)
+    #[tokio::test]
+    async fn test_circuit_named_main() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        let mut circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(&build)).unwrap();
+        let circuit = CircuitConfig::new("main").with_template("Main");
+        circomkit.add_circuit(circuit.clone());
+
+        let (_, args) = circomkit.compile_command(&circuit).await.unwrap();
+        let main_component = build.join(".circomkit/main/main.circom");
+        assert_eq!(args[0], main_component.to_string_lossy());
+        assert_eq!(args[5], build.join("main").to_string_lossy());
+
+        // The circuit's own build directory is separate and survives pruning
+        std::fs::create_dir_all(build.join("main")).unwrap();
+        std::fs::write(build.join("main/main.r1cs"), b"").unwrap();
+        assert!(circomkit.prune().await.unwrap().is_empty());
+        assert!(main_component.exists());
+        assert!(build.join("main/main.r1cs").exists());
     }
 
-    /// Get information about a compiled circuit
-    pub async fn info(&self, circuit: &CircuitConfig) -> Result<CircuitInfo> {
-        let build_dir = self.config.build_path(&circuit.name);
-        let r1cs_path = build_dir.join(format!("{}.r1cs", circuit.name));
+    #[test]
+    fn test_relative_include() {
+        assert_eq!(
+            relative_include(
+                Path::new("build/.circomkit/main"),
+                Path::new("circuits/adder.circom")
+            ),
+            "../../../circuits/adder.circom"
+        );
+        assert_eq!(
+            relative_include(Path::new("./build/main"), Path::new("./c/x.circom")),
+            "../../c/x.circom"
+        );
+        assert!(
+            Path::new(&relative_include(
+                Path::new("/tmp/build/main"),
+                Path::new("circuits/adder.circom")
+            ))
+            .is_absolute()
+        );
+    }
+
+    #[test]
+    fn test_input_schema() {
+        let declared = BTreeMap::from([("a".to_string(), vec![]), ("m".to_string(), vec![2, 3])]);
+
+        let schema = input_schema("test", &declared);
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], serde_json::json!(["a", "m"]));
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(
+            schema["properties"]["a"]["type"],
+            serde_json::json!(["string", "integer"])
+        );
+
+        let m = &schema["properties"]["m"];
+        assert_eq!(m["type"], "array");
+        assert_eq!(m["minItems"], 2);
+        assert_eq!(m["maxItems"], 2);
+        assert_eq!(m["items"]["minItems"], 3);
+        assert_eq!(
+            m["items"]["items"]["type"],
+            schema["properties"]["a"]["type"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_input_schema_requires_compiled_circuit() {
+        let dir = tempfile::tempdir().unwrap();
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path())).unwrap();
+
+        assert!(matches!(
+            circomkit.input_schema(&CircuitConfig::new("test")).await,
+            Err(CircomkitError::CircuitNotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verification_result() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let exited = |code: i32, stdout: &str| Output {
+            status: std::process::ExitStatus::from_raw(code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        };
+        let failed = |log: &str| CircomkitError::CommandFailed {
+            command: "snarkjs".to_string(),
+            exit_code: 1,
+            stderr: log.to_string(),
+        };
+
+        // Captured from `snarkjs groth16 verify` 0.7
+        assert!(verification_result(Ok(exited(0, "[INFO]  snarkJS: OK!\n"))).unwrap());
+        assert!(!verification_result(Err(failed("[ERROR] snarkJS: Invalid proof\n"))).unwrap());
+
+        // The exit code decides when the log says neither
+        assert!(verification_result(Ok(exited(0, ""))).unwrap());
+
+        // Contradictions are errors
+        let err = verification_result(Ok(exited(0, "[ERROR] snarkJS: Invalid proof\n")))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exited successfully but reported an invalid proof"));
+        let err = verification_result(Err(failed("[INFO]  snarkJS: OK!\n")))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("failed but reported a valid proof"));
+
+        // Failures unrelated to the proof
+        let missing = "Error: ENOENT: no such file or directory, open 'vkey.json'";
+        assert!(matches!(
+            verification_result(Err(failed(missing))),
+            Err(CircomkitError::VerificationFailed { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (vkey, public, proof) = (
+            dir.path().join("vkey.json"),
+            dir.path().join("public.json"),
+            dir.path().join("proof.json"),
+        );
+        std::fs::write(&vkey, "{}").unwrap();
+        std::fs::write(&public, "[\"1\"]").unwrap();
+
+        // snarkjs accepting proofs that contain "good"
+        let snarkjs = dir.path().join("snarkjs");
+        std::fs::write(
+            &snarkjs,
+            "#!/bin/sh\n[ \"$1\" = \"plonk\" ] && [ \"$2\" = \"verify\" ] || exit 2\n\
+             if grep -q good \"$5\"; then echo '[INFO]  snarkJS: OK!'; \
+             else echo '[ERROR] snarkJS: Invalid proof' >&2; exit 1; fi\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&snarkjs, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_snarkjs_path(&snarkjs)).unwrap();
 
-        if !r1cs_path.exists() {
-            return Err(CircomkitError::CircuitNotFound(r1cs_path));
+        std::fs::write(&proof, "{\"good\": true}").unwrap();
+        assert!(
+            circomkit
+                .verify_files(Protocol::Plonk, &vkey, &public, &proof)
+                .unwrap()
+        );
+        std::fs::write(&proof, "{\"bad\": true}").unwrap();
+        assert!(
+            !circomkit
+                .verify_files(Protocol::Plonk, &vkey, &public, &proof)
+                .unwrap()
+        );
+
+        // Other snarkjs failures are errors
+        assert!(
+            circomkit
+                .verify_files(Protocol::Groth16, &vkey, &public, &proof)
+                .is_err()
+        );
+        let err = circomkit
+            .verify_files(
+                Protocol::Plonk,
+                &dir.path().join("missing.json"),
+                &public,
+                &proof,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("missing.json not found"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_verify_batch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        std::fs::create_dir_all(build.join("batch")).unwrap();
+        std::fs::write(
+            build.join("batch").join("plonk_vkey.json"),
+            r#"{"accept": "1"}"#,
+        )
+        .unwrap();
+
+        // A snarkjs package accepting good proofs of the key's public signal
+        let package = dir.path().join("node_modules").join("snarkjs");
+        std::fs::create_dir_all(&package).unwrap();
+        std::fs::write(
+            package.join("package.json"),
+            r#"{"name": "snarkjs", "main": "main.js"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            package.join("main.js"),
+            "exports.plonk = { verify: async (vkey, pub, proof) => \
+             proof.good === true && pub[0] === vkey.accept };",
+        )
+        .unwrap();
+        let cli = package.join("cli.js");
+        std::fs::write(&cli, "#!/bin/sh\nexit 2\n").unwrap();
+        std::fs::set_permissions(&cli, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = CircomkitConfig::default()
+            .with_build_dir(&build)
+            .with_protocol(Protocol::Plonk);
+        let circuit = CircuitConfig::new("batch");
+        let proof = |good: bool| {
+            Proof::from_snarkjs_json(Protocol::Plonk, serde_json::json!({ "good": good })).unwrap()
+        };
+        let public = |value: &str| PublicSignals::new(vec![value.to_string()]);
+        let items = [
+            (proof(true), public("1")),
+            (proof(false), public("1")),
+            (proof(true), public("2")),
+        ];
+
+        let circomkit = Circomkit::new(config.clone().with_snarkjs_path(&cli)).unwrap();
+        assert_eq!(
+            circomkit.verify_batch(&circuit, &items).await.unwrap(),
+            [true, false, false]
+        );
+        assert!(
+            circomkit
+                .verify_batch(&circuit, &[])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // Without the package, each proof is verified by snarkjs in turn
+        let snarkjs = dir.path().join("snarkjs");
+        std::fs::write(
+            &snarkjs,
+            "#!/bin/sh\nif grep -q true \"$5\"; then echo '[INFO]  snarkJS: OK!'; \
+             else echo '[ERROR] snarkJS: Invalid proof' >&2; exit 1; fi\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&snarkjs, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let circomkit = Circomkit::new(config.clone().with_snarkjs_path(&snarkjs)).unwrap();
+        assert_eq!(
+            circomkit.verify_batch(&circuit, &items).await.unwrap(),
+            [true, false, true]
+        );
+
+        // One process for the whole batch
+        let backend =
+            Arc::new(MockBackend::new().with_file(Stage::Verify, ".json", "[true, false, true]"));
+        let circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(backend.clone());
+        assert_eq!(
+            circomkit.verify_batch(&circuit, &items).await.unwrap(),
+            [true, false, true]
+        );
+        assert_eq!(backend.calls(), [Stage::Verify]);
+
+        let circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(MockBackend::new().with_file(Stage::Verify, ".json", "[true]"));
+        assert!(circomkit.verify_batch(&circuit, &items).await.is_err());
+
+        std::fs::remove_dir_all(build.join("batch")).unwrap();
+        assert!(matches!(
+            circomkit.verify_batch(&circuit, &items).await,
+            Err(CircomkitError::VerificationFailed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_random_inputs() {
+        use rand::SeedableRng;
+
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("fuzz");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(
+            build.join("fuzz.sym"),
+            "1,1,0,main.a\n2,2,0,main.bits[0]\n3,3,0,main.bits[1]\n4,4,0,main.bits[2]\n",
+        )
+        .unwrap();
+
+        // Four private inputs
+        std::fs::write(build.join("fuzz.r1cs"), r1cs_bytes(0, 0, 4, 0)).unwrap();
+
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path())).unwrap();
+        let circuit = CircuitConfig::new("fuzz");
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+
+        let inputs = circomkit.random_inputs(&circuit, &mut rng).await.unwrap();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs["a"].num_elements(), 1);
+        assert_eq!(inputs["bits"].num_elements(), 3);
+        let a: BigUint = inputs["a"].as_string().parse().unwrap();
+        assert!(a < Prime::Bn128.modulus());
+
+        // Seeded generators repeat their inputs
+        let mut again = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        assert_eq!(
+            circomkit.random_inputs(&circuit, &mut again).await.unwrap(),
+            inputs
+        );
+
+        let bounds =
+            HashMap::from([("bits".to_string(), BigUint::from(0u32)..BigUint::from(2u32))]);
+        for _ in 0..10 {
+            let inputs = circomkit
+                .random_inputs_in_range(&circuit, &bounds, &mut rng)
+                .await
+                .unwrap();
+            let SignalValue::Array(bits) = &inputs["bits"] else {
+                panic!("Expected an array");
+            };
+            assert!(
+                bits.iter()
+                    .all(|bit| ["0", "1"].contains(&bit.as_string().as_str()))
+            );
         }
 
-        let snarkjs = self.config.snarkjs_command();
+        let unknown = HashMap::from([("b".to_string(), BigUint::from(0u32)..BigUint::from(2u32))]);
+        assert!(
+            circomkit
+                .random_inputs_in_range(&circuit, &unknown, &mut rng)
+                .await
+                .is_err()
+        );
+        let empty = HashMap::from([("a".to_string(), BigUint::from(2u32)..BigUint::from(2u32))]);
+        assert!(
+            circomkit
+                .random_inputs_in_range(&circuit, &empty, &mut rng)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_check_inputs() {
+        let declared = BTreeMap::from([("a".to_string(), vec![]), ("in".to_string(), vec![2, 3])]);
+
+        let valid = crate::signals! {
+            "a" => 1,
+            "in" => vec![vec![1, 2, 3], vec![4, 5, 6]],
+        };
+        assert!(check_inputs(&declared, &valid).is_ok());
+
+        // Flattened arrays are accepted
+        let flat = crate::signals! {
+            "a" => 1,
+            "in" => vec![1, 2, 3, 4, 5, 6],
+        };
+        assert!(check_inputs(&declared, &flat).is_ok());
+
+        let invalid = crate::signals! {
+            "in" => vec![1, 2, 3],
+            "typo" => 1,
+        };
+        let err = check_inputs(&declared, &invalid).unwrap_err().to_string();
+        assert!(err.contains("missing inputs: a"));
+        assert!(err.contains("unexpected inputs: typo"));
+        assert!(err.contains("'in' expects 6 element(s), got 3"));
+    }
+
+    #[test]
+    fn test_name_wires() {
+        let symbols =
+            SymbolTable::parse("1,1,0,main.out\n2,2,0,main.in\n3,2,1,main.sub.in\n").unwrap();
+        let lc = |wires: &[u32]| wires.iter().map(|w| (*w, 1.into())).collect();
+        let mut constraints = vec![Constraint {
+            a: lc(&[2]),
+            b: lc(&[0, 2]),
+            c: lc(&[1, 5]),
+            names: BTreeMap::new(),
+        }];
+
+        name_wires(&mut constraints, &symbols);
+        assert_eq!(
+            constraints[0].names,
+            BTreeMap::from([(1, "main.out".to_string()), (2, "main.in".to_string())])
+        );
+        assert_eq!(
+            constraints[0].to_string(),
+            "(main.in) * (1 + main.in) - (main.out + w5) = 0"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_compile_finds_wasm() {
+        use std::os::unix::fs::PermissionsExt;
 
-        let output = Command::new(&snarkjs)
-            .arg("r1cs")
-            .arg("info")
-            .arg(&r1cs_path)
-            .arg("--json")
-            .output()
-            .map_err(|e| CircomkitError::Io(e))?;
+        // circom writing the wasm to `<name>/` instead of `<name>_js/`
+        let dir = tempfile::tempdir().unwrap();
+        let circom = dir.path().join("circom");
+        std::fs::write(
+            &circom,
+            "#!/bin/sh\nwhile [ $# -gt 0 ] && [ \"$1\" != \"-o\" ]; do shift; done\n\
+             [ \"$1\" = \"-o\" ] || exit 1\n\
+             mkdir -p \"$2/stub\" && touch \"$2/stub/stub.wasm\" \"$2/stub/generate_witness.js\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&circom, std::fs::Permissions::from_mode(0o755)).unwrap();
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: snarkjs,
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
+        let build = dir.path().join("build");
+        let config = CircomkitConfig::default()
+            .with_build_dir(&build)
+            .with_circom_path(&circom);
+        let circomkit = Circomkit::new(config.clone()).unwrap();
+        let circuit = CircuitConfig::new("stub");
+
+        let wasm = build.join("stub/stub/stub.wasm");
+        let artifacts = circomkit.compile(&circuit).await.unwrap();
+        assert_eq!(artifacts.wasm, wasm);
+        circomkit.check_compiled(&circuit).unwrap();
+
+        let cmd =
+            circomkit.witness_command(&circuit, Path::new("input.json"), Path::new("witness.wtns"));
+        let args: Vec<_> = cmd.get_args().map(PathBuf::from).collect();
+        assert_eq!(args[0], build.join("stub/stub/generate_witness.js"));
+        assert_eq!(args[1], wasm);
+
+        // Found again without the compile that resolved it
+        let circomkit = Circomkit::new(config).unwrap();
+        let listed = circomkit.list_artifacts(&circuit).await.unwrap();
+        assert_eq!(listed.wasm, Some(wasm));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_compiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::write(
+            circuits.join("gadgets.circom"),
+            "template Gadget(n) { signal input in; }",
+        )
+        .unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(dir.path().join("build"));
+        let circomkit = Arc::new(
+            Circomkit::new(config)
+                .unwrap()
+                .with_backend(MockBackend::new()),
+        );
+
+        // Distinct circuits sharing a build root, each compiled twice at once
+        let mut tasks = JoinSet::new();
+        for n in 0..16 {
+            let circomkit = circomkit.clone();
+            tasks.spawn(async move {
+                let circuit = CircuitConfig::new(format!("gadget_{}", n % 8))
+                    .with_file("gadgets.circom")
+                    .with_template("Gadget")
+                    .with_params(vec![n % 8]);
+                circomkit.compile(&circuit).await.map(|_| circuit)
             });
         }
+        while let Some(result) = tasks.join_next().await {
+            let circuit = result.unwrap().unwrap();
+            let main = circomkit
+                .main_dir()
+                .join(format!("{}.circom", circuit.name));
+            assert_eq!(
+                std::fs::read_to_string(main).unwrap(),
+                circomkit.main_component_source(&circuit)
+            );
+        }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        // No temp files are left behind
+        let files = std::fs::read_dir(circomkit.main_dir()).unwrap().count();
+        assert_eq!(files, 8);
+    }
 
-        // Parse the output (snarkjs outputs human-readable format)
-        // This is a simplified parser
-        let mut info = CircuitInfo {
-            constraints: 0,
-            private_inputs: 0,
-            public_inputs: 0,
-            public_outputs: 0,
-            labels: 0,
-        };
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_compiles_same_name() {
+        /// Records the main component circom would compile
+        #[derive(Debug, Default)]
+        struct RecordMain(Mutex<Vec<String>>);
 
-        for line in stdout.lines() {
-            if line.contains("Constraints:") {
-                if let Some(n) = line.split(':').nth(1) {
-                    info.constraints = n.trim().parse().unwrap_or(0);
-                }
-            } else if line.contains("Private Inputs:") {
-                if let Some(n) = line.split(':').nth(1) {
-                    info.private_inputs = n.trim().parse().unwrap_or(0);
-                }
-            } else if line.contains("Public Inputs:") {
-                if let Some(n) = line.split(':').nth(1) {
-                    info.public_inputs = n.trim().parse().unwrap_or(0);
-                }
-            } else if line.contains("Outputs:") {
-                if let Some(n) = line.split(':').nth(1) {
-                    info.public_outputs = n.trim().parse().unwrap_or(0);
-                }
-            } else if line.contains("Labels:") {
-                if let Some(n) = line.split(':').nth(1) {
-                    info.labels = n.trim().parse().unwrap_or(0);
-                }
+        impl Backend for RecordMain {
+            fn run(&self, invocation: Invocation<'_>) -> Result<Output> {
+                let main = invocation
+                    .command
+                    .get_args()
+                    .find(|arg| arg.to_string_lossy().ends_with(".circom"))
+                    .map(|arg| std::fs::read_to_string(arg).unwrap())
+                    .unwrap();
+                // Give a conflicting compile time to replace the file
+                std::thread::sleep(std::time::Duration::from_millis(5));
+                self.0.lock().unwrap().push(main);
+                MockBackend::new().run(invocation)
             }
         }
 
-        Ok(info)
-    }
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::write(
+            circuits.join("gadgets.circom"),
+            "template Gadget(n) { signal input in; }",
+        )
+        .unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(dir.path().join("build"));
+        let backend = Arc::new(RecordMain::default());
+        let circomkit = Arc::new(
+            Circomkit::new(config)
+                .unwrap()
+                .with_backend(backend.clone()),
+        );
 
-    /// Clean build artifacts for a circuit
-    pub async fn clean(&self, circuit: &CircuitConfig) -> Result<()> {
-        let build_dir = self.config.build_path(&circuit.name);
-        if build_dir.exists() {
-            fs::remove_dir_all(&build_dir).await?;
-            info!("Cleaned build directory: {:?}", build_dir);
+        // One name, configured with different parameters
+        let mut tasks = JoinSet::new();
+        for n in 0..16 {
+            let circomkit = circomkit.clone();
+            tasks.spawn(async move {
+                let circuit = CircuitConfig::new("gadget")
+                    .with_file("gadgets.circom")
+                    .with_template("Gadget")
+                    .with_params(vec![n % 2]);
+                circomkit.compile(&circuit).await.unwrap();
+                circomkit.main_component_source(&circuit)
+            });
         }
-        Ok(())
+        let mut compiled = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            compiled.push(result.unwrap());
+        }
+
+        // Every compile saw its own main component
+        let mut seen = backend.0.lock().unwrap().clone();
+        seen.sort();
+        compiled.sort();
+        assert_eq!(seen, compiled);
     }
 
-    /// Clean all build artifacts
-    pub async fn clean_all(&self) -> Result<()> {
-        if self.config.dir_build.exists() {
-            fs::remove_dir_all(&self.config.dir_build).await?;
-            info!("Cleaned all build artifacts");
+    #[tokio::test]
+    async fn test_source_declaring_main() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("standalone.circom");
+        std::fs::write(
+            &file,
+            "template T() { signal input a; }\ncomponent main {public [a]} = T();\n",
+        )
+        .unwrap();
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path().join("build")))
+                .unwrap();
+        let circuit = CircuitConfig::new("standalone")
+            .with_absolute_file(&file)
+            .with_template("T");
+
+        match circomkit.generate_main_component(&circuit).await {
+            Err(CircomkitError::InvalidConfig(msg)) => {
+                assert!(msg.contains("already declares `component main`"))
+            }
+            other => panic!("Expected InvalidConfig, got {:?}", other),
         }
-        Ok(())
+
+        assert!(declares_main("component main = T();"));
+        assert!(declares_main(
+            "template T() {}\ncomponent main{public [a]} = T();"
+        ));
+        assert!(!declares_main("// component main = T();\ntemplate T() {}"));
+        assert!(!declares_main(
+            "template T() { component mainHasher = H(); }"
+        ));
     }
 
-    /// Read input signals from a JSON file
-    pub async fn read_inputs(&self, circuit: &str, input_name: &str) -> Result<CircuitSignals> {
-        let path = self.config.input_path(circuit, input_name);
-        let content = fs::read_to_string(&path).await.map_err(|_| {
-            CircomkitError::InvalidSignals(format!("Input file not found: {:?}", path))
-        })?;
-        let signals: CircuitSignals = serde_json::from_str(&content)?;
-        Ok(signals)
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_templates_from_one_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let circuits = dir.path().join("circuits");
+        std::fs::create_dir_all(&circuits).unwrap();
+        std::fs::write(
+            circuits.join("gadgets.circom"),
+            "pragma circom 2.0.0;\n\
+             template Add() { signal input a; signal output out; out <== a + 1; }\n\
+             template Double() { signal input a; signal output out; out <== 2 * a; }\n",
+        )
+        .unwrap();
+
+        // Fake circom naming its outputs after the main file, like circom,
+        // and recording the main component in the sym file
+        let circom = dir.path().join("circom");
+        std::fs::write(
+            &circom,
+            "#!/bin/sh\nmain=\"$1\"\n\
+             while [ $# -gt 0 ] && [ \"$1\" != \"-o\" ]; do shift; done\n\
+             [ \"$1\" = \"-o\" ] || exit 1\n\
+             name=$(basename \"$main\" .circom)\n\
+             mkdir -p \"$2/${name}_js\" && touch \"$2/${name}_js/$name.wasm\" \"$2/$name.r1cs\"\n\
+             grep 'component main' \"$main\" > \"$2/$name.sym\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&circom, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(dir.path().join("build"))
+            .with_circom_path(&circom);
+        let circomkit = Circomkit::new(config).unwrap();
+
+        // Circuits named after their templates, sharing one source file
+        let add = CircuitConfig::new("Add")
+            .with_file("gadgets.circom")
+            .with_template("Add");
+        let double = CircuitConfig::new("Double")
+            .with_file("gadgets.circom")
+            .with_template("Double");
+        let add_artifacts = circomkit.compile(&add).await.unwrap();
+        let double_artifacts = circomkit.compile(&double).await.unwrap();
+
+        assert_ne!(add_artifacts.sym, double_artifacts.sym);
+        assert_ne!(add_artifacts.wasm, double_artifacts.wasm);
+        let sym = |artifacts: &CircuitArtifacts| std::fs::read_to_string(&artifacts.sym).unwrap();
+        assert!(sym(&add_artifacts).contains("Add()"));
+        assert!(sym(&double_artifacts).contains("Double()"));
+
+        // Neither compile touched the shared source
+        let source = std::fs::read_to_string(circuits.join("gadgets.circom")).unwrap();
+        assert!(source.contains("template Add()") && source.contains("template Double()"));
+
+        // A main directory next to the sources must not overwrite them
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(&circuits)
+            .with_build_dir(dir.path().join("build"))
+            .with_build_layout(crate::core::BuildLayout::default().with_main_dir(&circuits))
+            .with_circom_path(&circom);
+        let circomkit = Circomkit::new(config).unwrap();
+        let clash = CircuitConfig::new("gadgets").with_template("Add");
+        let err = circomkit.compile(&clash).await.unwrap_err();
+        assert!(err.to_string().contains("overwrite its source file"));
+        assert_eq!(
+            std::fs::read_to_string(circuits.join("gadgets.circom")).unwrap(),
+            source
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_full_check_reports_stages() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let circuit = CircuitConfig::new("stub");
+        let inputs = CircuitSignals::from([("a".to_string(), SignalValue::from(1))]);
+        let expected = CircuitSignals::from([("out".to_string(), SignalValue::from(1))]);
+        let ptau = dir.path().join("missing.ptau");
+
+        // Compilation failing stops the check
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path().join("build"))
+            .with_circom_path(dir.path().join("no-circom"));
+        let report = Circomkit::new(config)
+            .unwrap()
+            .full_check(&circuit, &inputs, &expected, &ptau)
+            .await
+            .unwrap();
+        assert!(report.compile_error.is_some());
+        assert!(report.witness.is_none() && report.proof.is_none());
+        assert!(!report.passed());
+
+        // circom emitting a wasm and a witness calculator copying its input
+        let js = dir.path().join("generate_witness.js");
+        std::fs::write(
+            &js,
+            r#"require("fs").copyFileSync(process.argv[3], process.argv[4]);"#,
+        )
+        .unwrap();
+        let circom = dir.path().join("circom");
+        std::fs::write(
+            &circom,
+            format!(
+                "#!/bin/sh\nwhile [ $# -gt 0 ] && [ \"$1\" != \"-o\" ]; do shift; done\n\
+                 [ \"$1\" = \"-o\" ] || exit 1\n\
+                 mkdir -p \"$2/stub_js\" && touch \"$2/stub_js/stub.wasm\"\n\
+                 cp {} \"$2/stub_js/\"\n",
+                js.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&circom, std::fs::Permissions::from_mode(0o755)).unwrap();
 
+        // Without a sym file or snarkjs the later stages fail, each recorded
+        // on its own
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path().join("build"))
+            .with_circom_path(&circom)
+            .with_snarkjs_path(dir.path().join("no-snarkjs"));
+        let report = Circomkit::new(config)
+            .unwrap()
+            .full_check(&circuit, &inputs, &expected, &ptau)
+            .await
+            .unwrap();
+        assert_eq!(report.compile_error, None);
+        assert!(report.artifacts.is_some());
+        assert_eq!(report.info, None);
+
+        let witness = report.witness.as_ref().unwrap();
+        assert!(!witness.passed);
+        assert!(witness.error.as_ref().unwrap().contains("stub.sym"));
+        assert!(witness.durations.compile.is_some());
+
+        let proof = report.proof.as_ref().unwrap();
+        assert!(!proof.valid);
+        assert!(proof.error_kind.is_some());
+        assert!(proof.durations.setup.is_some());
+        assert!(!report.passed());
+    }
+
+    #[cfg(unix)]
     #[test]
-    fn test_new_circomkit() {
-        let config = CircomkitConfig::default();
-        let circomkit = Circomkit::new(config);
-        assert!(circomkit.is_ok());
+    fn test_doctor_compatibility() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tool = |name: &str, output: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let config = CircomkitConfig::default()
+            .with_circom_path(tool("circom", "circom compiler 2.0.5"))
+            .with_snarkjs_path(tool("snarkjs", "snarkjs@0.7.4"));
+        let circomkit = Circomkit::new(config).unwrap();
+
+        let report = circomkit.doctor().unwrap();
+        assert_eq!(report.circom.as_deref(), Some("2.0.5"));
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].tool, "circom");
+
+        // Checked once per tool
+        circomkit.circom_version().unwrap();
+        circomkit.circom_version().unwrap();
+        let checked = circomkit.checked_tools.lock().unwrap();
+        assert_eq!(checked.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_witness_tools_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("test")).unwrap();
+        std::fs::write(dir.path().join("test/test.sym"), "1,1,0,main.out\n").unwrap();
+        let circomkit =
+            Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path())).unwrap();
+        let circuit = CircuitConfig::new("test");
+
+        // node is looked up on the child's PATH
+        let mut cmd =
+            circomkit.witness_command(&circuit, Path::new("input.json"), Path::new("witness.wtns"));
+        cmd.env("PATH", dir.path());
+        match run_command(&mut cmd) {
+            Err(CircomkitError::ToolNotFound { tool }) => assert_eq!(tool, "node"),
+            other => panic!("Expected ToolNotFound, got {:?}", other),
+        }
+
+        let circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_build_dir(dir.path())
+                .with_snarkjs_path(dir.path().join("snarkjs")),
+        )
+        .unwrap();
+        match circomkit
+            .witness_to_signals(&circuit, &dir.path().join("witness.wtns"))
+            .await
+        {
+            Err(CircomkitError::ToolNotFound { tool }) => assert!(tool.ends_with("snarkjs")),
+            other => panic!("Expected ToolNotFound, got {:?}", other),
+        }
     }
 
     #[test]
-    fn test_add_circuit() {
-        let config = CircomkitConfig::default();
-        let mut circomkit = Circomkit::new(config).unwrap();
+    fn test_snarkjs_flags_and_node_options() {
+        let config = CircomkitConfig::default()
+            .with_snarkjs_flag("-v")
+            .with_node_options("--max-old-space-size=16384");
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("test");
 
-        let circuit = CircuitConfig::new("test")
-            .with_template("TestCircuit")
-            .with_params(vec![10]);
+        let (_, args) = circomkit.prove_command(&circuit, Path::new("w.wtns"));
+        assert_eq!(args.last().map(String::as_str), Some("-v"));
+        let (_, args) = circomkit.export_verifier_command(&circuit);
+        assert_eq!(args.last().map(String::as_str), Some("-v"));
 
-        circomkit.add_circuit(circuit);
+        let cmd =
+            circomkit.witness_command(&circuit, Path::new("input.json"), Path::new("witness.wtns"));
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            [(
+                "NODE_OPTIONS".as_ref(),
+                Some("--max-old-space-size=16384".as_ref())
+            )]
+        );
+    }
 
-        assert!(circomkit.get_circuit("test").is_some());
+    #[test]
+    fn test_witness_command_extra_args() {
+        let config = CircomkitConfig::default()
+            .with_witness_arg("--sanity-check")
+            .with_witness_arg("--verbose");
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("test");
+
+        let cmd =
+            circomkit.witness_command(&circuit, Path::new("input.json"), Path::new("witness.wtns"));
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+
+        assert_eq!(cmd.get_program(), "node");
+        assert_eq!(
+            args,
+            [
+                "build/test/test_js/generate_witness.js",
+                "build/test/test_js/test.wasm",
+                "input.json",
+                "witness.wtns",
+                "--sanity-check",
+                "--verbose",
+            ]
+        );
     }
 }
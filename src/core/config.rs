@@ -2,8 +2,49 @@
 
 use crate::error::{CircomkitError, Result};
 use crate::types::{Prime, Protocol};
+use crate::utils::CircomlibGit;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable overriding the circom compiler path
+pub const CIRCOM_ENV: &str = "CIRCOMKIT_CIRCOM";
+
+/// Environment variable overriding the snarkjs path
+pub const SNARKJS_ENV: &str = "CIRCOMKIT_SNARKJS";
+
+/// Where generated files are placed inside the build directory
+///
+/// Paths are relative to `dir_build`. Generated main components live under
+/// a dot-directory by default so they can't collide with the build
+/// directory of a circuit named `main`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildLayout {
+    /// Directory for generated main components
+    #[serde(default = "default_main_dir")]
+    pub main_dir: PathBuf,
+}
+
+impl Default for BuildLayout {
+    fn default() -> Self {
+        Self {
+            main_dir: default_main_dir(),
+        }
+    }
+}
+
+impl BuildLayout {
+    /// Set the directory for generated main components
+    pub fn with_main_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.main_dir = dir.into();
+        self
+    }
+}
+
+fn default_main_dir() -> PathBuf {
+    PathBuf::from(".circomkit/main")
+}
 
 /// Configuration for Circomkit
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,14 +86,58 @@ pub struct CircomkitConfig {
     #[serde(default = "default_dir_ptau")]
     pub dir_ptau: PathBuf,
 
+    /// Layout of generated files inside the build directory
+    #[serde(default)]
+    pub build_layout: BuildLayout,
+
     /// Path to circuits configuration file
     #[serde(default = "default_circuits_file")]
     pub circuits: PathBuf,
 
+    /// Circom version emitted in the generated main component's pragma
+    #[serde(default = "default_circom_version")]
+    pub circom_version: String,
+
     /// Include paths for circom compiler
     #[serde(default)]
     pub include: Vec<PathBuf>,
 
+    /// Emit the constraint system (`--r1cs`), needed for setup, constraint
+    /// counts, input validation, and the tester checks that read outputs
+    /// (`expect_pass`, `expect_output`, `expect_signal`)
+    ///
+    /// When disabled, a leftover r1cs from an earlier compile is never used.
+    #[serde(default = "default_true")]
+    pub emit_r1cs: bool,
+
+    /// Emit the witness calculator (`--wasm`), needed for witnesses
+    #[serde(default = "default_true")]
+    pub emit_wasm: bool,
+
+    /// Emit the symbol table (`--sym`), needed to read signals by name,
+    /// including input validation and the tester checks that read outputs
+    ///
+    /// When disabled, a leftover sym file from an earlier compile is never
+    /// used.
+    #[serde(default = "default_true")]
+    pub emit_sym: bool,
+
+    /// Emit the constraints as JSON (`--json`)
+    #[serde(default)]
+    pub emit_json: bool,
+
+    /// Emit the WebAssembly text format (`--wat`)
+    #[serde(default)]
+    pub emit_wat: bool,
+
+    /// Report unconstrained and unused signals (`--inspect`)
+    #[serde(default)]
+    pub inspect: bool,
+
+    /// Extra flags appended to the circom command
+    #[serde(default)]
+    pub circom_flags: Vec<String>,
+
     /// Custom circom compiler path
     #[serde(default)]
     pub circom_path: Option<PathBuf>,
@@ -60,12 +145,59 @@ pub struct CircomkitConfig {
     /// Custom snarkjs path
     #[serde(default)]
     pub snarkjs_path: Option<PathBuf>,
+
+    /// Extra arguments appended to the witness calculator command
+    #[serde(default)]
+    pub witness_args: Vec<String>,
+
+    /// Extra flags appended to every snarkjs command
+    #[serde(default)]
+    pub snarkjs_flags: Vec<String>,
+
+    /// `NODE_OPTIONS` for snarkjs and the witness calculator, e.g.
+    /// `--max-old-space-size=16384` for large circuits
+    #[serde(default)]
+    pub node_options: Option<String>,
+
+    /// Path to circomlib's `circuits` directory, added to the include paths
+    #[serde(default)]
+    pub circomlib: Option<PathBuf>,
+
+    /// circomlib revision fetched from git and added to the include paths
+    #[serde(default)]
+    pub circomlib_git: Option<CircomlibGit>,
+
+    /// Cache directory for fetched dependencies (defaults to the user cache dir)
+    #[serde(default)]
+    pub dir_cache: Option<PathBuf>,
+
+    /// Directory for transient files such as inputs and witnesses; `auto`
+    /// uses the system temp directory (defaults to the build directory)
+    #[serde(default)]
+    pub dir_temp: Option<PathBuf>,
+
+    /// Maximum number of external processes run concurrently by batch operations
+    #[serde(default = "default_max_parallel_jobs")]
+    pub max_parallel_jobs: usize,
+
+    /// Maximum number of values, counting array elements, in one set of
+    /// witness inputs
+    #[serde(default = "default_max_input_elements")]
+    pub max_input_elements: usize,
+
+    /// Attempts made to download a PTAU file before giving up
+    #[serde(default = "default_ptau_download_attempts")]
+    pub ptau_download_attempts: u32,
 }
 
 fn default_version() -> String {
     "0.1.0".to_string()
 }
 
+fn default_circom_version() -> String {
+    "2.1.9".to_string()
+}
+
 fn default_optimization() -> u8 {
     1
 }
@@ -90,6 +222,24 @@ fn default_circuits_file() -> PathBuf {
     PathBuf::from("circuits.json")
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_input_elements() -> usize {
+    10_000_000
+}
+
+fn default_ptau_download_attempts() -> u32 {
+    crate::utils::PTAU_DOWNLOAD_ATTEMPTS
+}
+
+fn default_max_parallel_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
 impl Default for CircomkitConfig {
     fn default() -> Self {
         Self {
@@ -102,10 +252,29 @@ impl Default for CircomkitConfig {
             dir_inputs: default_dir_inputs(),
             dir_build: default_dir_build(),
             dir_ptau: default_dir_ptau(),
+            build_layout: BuildLayout::default(),
             circuits: default_circuits_file(),
+            circom_version: default_circom_version(),
             include: Vec::new(),
+            emit_r1cs: true,
+            emit_wasm: true,
+            emit_sym: true,
+            emit_json: false,
+            emit_wat: false,
+            inspect: false,
+            circom_flags: Vec::new(),
             circom_path: None,
             snarkjs_path: None,
+            witness_args: Vec::new(),
+            snarkjs_flags: Vec::new(),
+            node_options: None,
+            circomlib: None,
+            circomlib_git: None,
+            dir_cache: None,
+            dir_temp: None,
+            max_parallel_jobs: default_max_parallel_jobs(),
+            max_input_elements: default_max_input_elements(),
+            ptau_download_attempts: default_ptau_download_attempts(),
         }
     }
 }
@@ -134,6 +303,24 @@ impl CircomkitConfig {
         }
     }
 
+    /// Load configuration from a file without blocking the async runtime
+    pub async fn from_file_async(path: impl AsRef<Path>) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path.as_ref()).await?;
+        let config: Self = serde_json::from_str(&content)?;
+        Ok(config)
+    }
+
+    /// Load configuration from the default file (circomkit.json) without
+    /// blocking the async runtime
+    pub async fn from_default_file_async() -> Result<Self> {
+        let path = PathBuf::from("circomkit.json");
+        if tokio::fs::try_exists(&path).await? {
+            Self::from_file_async(path).await
+        } else {
+            Ok(Self::default())
+        }
+    }
+
     /// Save configuration to a file
     pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
         let content = serde_json::to_string_pretty(self)?;
@@ -154,8 +341,10 @@ impl CircomkitConfig {
     }
 
     /// Set the optimization level
+    ///
+    /// Levels above 2 are rejected by [`CircomkitConfig::validate`].
     pub fn with_optimization(mut self, level: u8) -> Self {
-        self.optimization = level.min(2);
+        self.optimization = level;
         self
     }
 
@@ -183,18 +372,74 @@ impl CircomkitConfig {
         self
     }
 
+    /// Set the layout of generated files inside the build directory
+    pub fn with_build_layout(mut self, layout: BuildLayout) -> Self {
+        self.build_layout = layout;
+        self
+    }
+
     /// Set the PTAU directory
     pub fn with_ptau_dir(mut self, dir: impl Into<PathBuf>) -> Self {
         self.dir_ptau = dir.into();
         self
     }
 
+    /// Set the circom version used in the generated main component's pragma
+    pub fn with_circom_version(mut self, version: impl Into<String>) -> Self {
+        self.circom_version = version.into();
+        self
+    }
+
     /// Add an include path
     pub fn with_include(mut self, path: impl Into<PathBuf>) -> Self {
         self.include.push(path.into());
         self
     }
 
+    /// Emit the r1cs when compiling (on by default)
+    pub fn with_emit_r1cs(mut self, emit: bool) -> Self {
+        self.emit_r1cs = emit;
+        self
+    }
+
+    /// Emit the wasm witness calculator when compiling (on by default)
+    pub fn with_emit_wasm(mut self, emit: bool) -> Self {
+        self.emit_wasm = emit;
+        self
+    }
+
+    /// Emit the symbol table when compiling (on by default)
+    pub fn with_emit_sym(mut self, emit: bool) -> Self {
+        self.emit_sym = emit;
+        self
+    }
+
+    /// Emit the constraints as JSON when compiling
+    pub fn with_emit_json(mut self, emit: bool) -> Self {
+        self.emit_json = emit;
+        self
+    }
+
+    /// Emit the WebAssembly text format when compiling
+    pub fn with_emit_wat(mut self, emit: bool) -> Self {
+        self.emit_wat = emit;
+        self
+    }
+
+    /// Have circom report unconstrained and unused signals
+    ///
+    /// Reported warnings are returned in `CircuitArtifacts::warnings`.
+    pub fn with_inspect(mut self, inspect: bool) -> Self {
+        self.inspect = inspect;
+        self
+    }
+
+    /// Add an extra flag for the circom compiler
+    pub fn with_circom_flag(mut self, flag: impl Into<String>) -> Self {
+        self.circom_flags.push(flag.into());
+        self
+    }
+
     /// Set custom circom compiler path
     pub fn with_circom_path(mut self, path: impl Into<PathBuf>) -> Self {
         self.circom_path = Some(path.into());
@@ -207,6 +452,99 @@ impl CircomkitConfig {
         self
     }
 
+    /// Add an extra flag for snarkjs, e.g. `-v`
+    pub fn with_snarkjs_flag(mut self, flag: impl Into<String>) -> Self {
+        self.snarkjs_flags.push(flag.into());
+        self
+    }
+
+    /// Set `NODE_OPTIONS` for snarkjs and the witness calculator
+    pub fn with_node_options(mut self, options: impl Into<String>) -> Self {
+        self.node_options = Some(options.into());
+        self
+    }
+
+    /// Add an extra argument for the witness calculator
+    pub fn with_witness_arg(mut self, arg: impl Into<String>) -> Self {
+        self.witness_args.push(arg.into());
+        self
+    }
+
+    /// Set the extra arguments for the witness calculator
+    pub fn with_witness_args(mut self, args: Vec<String>) -> Self {
+        self.witness_args = args;
+        self
+    }
+
+    /// Use circomlib from a local `circuits` directory
+    ///
+    /// The directory and the one containing the circomlib package are added
+    /// to the include paths, so both `include "bitify.circom";` and
+    /// `include "circomlib/circuits/bitify.circom";` resolve.
+    pub fn with_circomlib(mut self, path: impl Into<PathBuf>) -> Self {
+        self.circomlib = Some(path.into());
+        self
+    }
+
+    /// Get circomlib's `circuits` directory
+    ///
+    /// Uses the configured path if set, otherwise searches for
+    /// `node_modules/circomlib/circuits` upward from the current directory.
+    pub fn resolve_circomlib(&self) -> Option<PathBuf> {
+        self.circomlib.clone().or_else(|| {
+            std::env::current_dir()
+                .ok()
+                .and_then(|cwd| crate::utils::find_circomlib(&cwd))
+        })
+    }
+
+    /// Use circomlib from a git repository pinned to `rev`
+    ///
+    /// The repository is shallow-cloned into the cache directory on first
+    /// compile and added to the include paths.
+    pub fn with_circomlib_git(mut self, url: impl Into<String>, rev: impl Into<String>) -> Self {
+        self.circomlib_git = Some(CircomlibGit::new(url, rev));
+        self
+    }
+
+    /// Set the cache directory for fetched dependencies
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir_cache = Some(dir.into());
+        self
+    }
+
+    /// Get the cache directory for fetched dependencies
+    pub fn cache_dir(&self) -> PathBuf {
+        self.dir_cache
+            .clone()
+            .unwrap_or_else(crate::utils::default_cache_dir)
+    }
+
+    /// Set the directory for transient files, or `auto` for the system temp
+    /// directory
+    pub fn with_tempdir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir_temp = Some(dir.into());
+        self
+    }
+
+    /// Set the maximum number of values in one set of witness inputs
+    pub fn with_max_input_elements(mut self, max: usize) -> Self {
+        self.max_input_elements = max;
+        self
+    }
+
+    /// Set the number of attempts made to download a PTAU file
+    pub fn with_ptau_download_attempts(mut self, attempts: u32) -> Self {
+        self.ptau_download_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the maximum number of concurrent jobs for batch operations
+    pub fn with_max_parallel_jobs(mut self, jobs: usize) -> Self {
+        self.max_parallel_jobs = jobs.max(1);
+        self
+    }
+
     /// Get the path to a circuit file
     pub fn circuit_path(&self, file: &str) -> PathBuf {
         self.dir_circuits.join(file)
@@ -219,14 +557,51 @@ impl CircomkitConfig {
             .join(format!("{}.json", input))
     }
 
+    /// Find an input file, trying the `.json`, `.toml`, `.yaml` and `.yml`
+    /// extensions in that order
+    ///
+    /// Falls back to [`input_path`](Self::input_path) if none exists.
+    pub fn find_input(&self, circuit: &str, input: &str) -> PathBuf {
+        ["json", "toml", "yaml", "yml"]
+            .iter()
+            .map(|ext| {
+                self.dir_inputs
+                    .join(circuit)
+                    .join(format!("{}.{}", input, ext))
+            })
+            .find(|path| path.exists())
+            .unwrap_or_else(|| self.input_path(circuit, input))
+    }
+
     /// Get the build directory for a circuit
     pub fn build_path(&self, circuit: &str) -> PathBuf {
         self.dir_build.join(circuit)
     }
 
+    /// Get the directory for a circuit's transient files
+    ///
+    /// Falls back to the circuit's build directory when no temp directory is
+    /// configured.
+    pub fn temp_path(&self, circuit: &str) -> PathBuf {
+        match &self.dir_temp {
+            Some(dir) if dir.as_os_str() == "auto" => {
+                std::env::temp_dir().join("circomkit").join(circuit)
+            }
+            Some(dir) => dir.join(circuit),
+            None => self.build_path(circuit),
+        }
+    }
+
+    /// Get the directory generated main components are written to
+    pub fn main_dir(&self) -> PathBuf {
+        self.dir_build.join(&self.build_layout.main_dir)
+    }
+
     /// Get the path to a PTAU file
+    ///
+    /// `CIRCOMKIT_PTAU_DIR` takes precedence over the configured directory.
     pub fn ptau_path(&self, filename: &str) -> PathBuf {
-        self.dir_ptau.join(filename)
+        crate::utils::ptau_dir(&self.dir_ptau).join(filename)
     }
 
     /// Validate the configuration
@@ -236,26 +611,57 @@ impl CircomkitConfig {
                 "Optimization level must be 0, 1, or 2".to_string(),
             ));
         }
+        if self.max_parallel_jobs == 0 {
+            return Err(CircomkitError::InvalidConfig(
+                "max_parallel_jobs must be at least 1".to_string(),
+            ));
+        }
         Ok(())
     }
 
     /// Get the circom compiler command
+    ///
+    /// Resolved from `CIRCOMKIT_CIRCOM`, then `circom_path`, then `circom`
+    /// on the `PATH`.
     pub fn circom_command(&self) -> String {
-        self.circom_path
-            .as_ref()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "circom".to_string())
+        tool_command(CIRCOM_ENV, self.circom_path.as_deref(), "circom")
     }
 
     /// Get the snarkjs command
+    ///
+    /// Resolved from `CIRCOMKIT_SNARKJS`, then `snarkjs_path`, then
+    /// `snarkjs` on the `PATH`.
     pub fn snarkjs_command(&self) -> String {
-        self.snarkjs_path
-            .as_ref()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "snarkjs".to_string())
+        tool_command(SNARKJS_ENV, self.snarkjs_path.as_deref(), "snarkjs")
+    }
+
+    /// snarkjs program and `args` followed by the configured `snarkjs_flags`
+    pub(crate) fn snarkjs_args(&self, mut args: Vec<String>) -> (String, Vec<String>) {
+        args.extend(self.snarkjs_flags.iter().cloned());
+        (self.snarkjs_command(), args)
+    }
+
+    /// Start a command for a node-based tool, with `node_options` set as
+    /// its `NODE_OPTIONS`
+    pub(crate) fn node_process(&self, program: &str) -> Command {
+        let mut cmd = Command::new(program);
+        if let Some(options) = &self.node_options {
+            cmd.env("NODE_OPTIONS", options);
+        }
+        cmd
     }
 }
 
+/// Resolve a tool from an environment variable, a configured path, or its
+/// bare command name, in that order
+fn tool_command(env_var: &str, configured: Option<&Path>, default: &str) -> String {
+    std::env::var(env_var)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .or_else(|| configured.map(|p| p.to_string_lossy().to_string()))
+        .unwrap_or_else(|| default.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +685,56 @@ mod tests {
         assert_eq!(config.protocol, Protocol::Plonk);
         assert_eq!(config.optimization, 2);
         assert!(config.verbose);
+
+        // Out-of-range levels are kept for validation to reject
+        let config = config.with_optimization(3);
+        assert_eq!(config.optimization, 3);
+        assert!(matches!(
+            config.validate(),
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_from_file_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("circomkit.json");
+        std::fs::write(&path, r#"{"protocol": "plonk", "optimization": 2}"#).unwrap();
+
+        let config = CircomkitConfig::from_file_async(&path).await.unwrap();
+        assert_eq!(config.protocol, Protocol::Plonk);
+        assert_eq!(config.optimization, 2);
+
+        assert!(
+            CircomkitConfig::from_file_async(dir.path().join("missing.json"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_tool_command_precedence() {
+        // A variable no other test reads, so setting it can't race them
+        const VAR: &str = "CIRCOMKIT_TEST_TOOL";
+        let configured = Path::new("/opt/bin/circom");
+
+        unsafe { std::env::remove_var(VAR) };
+        assert_eq!(tool_command(VAR, None, "circom"), "circom");
+        assert_eq!(
+            tool_command(VAR, Some(configured), "circom"),
+            "/opt/bin/circom"
+        );
+
+        unsafe { std::env::set_var(VAR, "/ci/circom") };
+        assert_eq!(tool_command(VAR, Some(configured), "circom"), "/ci/circom");
+
+        unsafe { std::env::set_var(VAR, "") };
+        assert_eq!(
+            tool_command(VAR, Some(configured), "circom"),
+            "/opt/bin/circom"
+        );
+
+        unsafe { std::env::remove_var(VAR) };
     }
 
     #[test]
@@ -299,5 +755,71 @@ mod tests {
             config.build_path("multiplier"),
             PathBuf::from("build/multiplier")
         );
+
+        assert_eq!(config.main_dir(), PathBuf::from("build/.circomkit/main"));
+        let config = config.with_build_layout(BuildLayout::default().with_main_dir("generated"));
+        assert_eq!(config.main_dir(), PathBuf::from("build/generated"));
+
+        assert_eq!(
+            config.temp_path("multiplier"),
+            PathBuf::from("build/multiplier")
+        );
+        let config = config.with_tempdir("scratch");
+        assert_eq!(
+            config.temp_path("multiplier"),
+            PathBuf::from("scratch/multiplier")
+        );
+        let config = config.with_tempdir("auto");
+        assert_eq!(
+            config.temp_path("multiplier"),
+            std::env::temp_dir().join("circomkit/multiplier")
+        );
+    }
+
+    #[test]
+    fn test_validate_protocol_prime() {
+        let config = CircomkitConfig::new()
+            .with_protocol(Protocol::Plonk)
+            .with_prime(Prime::Bls12381);
+        assert!(config.validate().is_ok());
+
+        // Only setup needs a pairing-friendly prime, so compile- and
+        // witness-only configurations are valid
+        let config = config.with_protocol(Protocol::Fflonk);
+        assert!(config.validate().is_ok());
+        let goldilocks = CircomkitConfig::new().with_prime(Prime::Goldilocks);
+        assert!(goldilocks.validate().is_ok());
+
+        let circomkit = crate::Circomkit::new(goldilocks).unwrap();
+        let circuit = crate::types::CircuitConfig::new("test");
+        assert!(matches!(
+            circomkit.setup_command(&circuit, std::path::Path::new("test.ptau")),
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CircomkitConfig::new().with_inputs_dir(dir.path());
+        let inputs = dir.path().join("multiplier");
+        std::fs::create_dir_all(&inputs).unwrap();
+
+        assert_eq!(
+            config.find_input("multiplier", "default"),
+            inputs.join("default.json")
+        );
+
+        std::fs::write(inputs.join("default.toml"), "a = 3").unwrap();
+        assert_eq!(
+            config.find_input("multiplier", "default"),
+            inputs.join("default.toml")
+        );
+
+        std::fs::write(inputs.join("default.json"), "{}").unwrap();
+        assert_eq!(
+            config.find_input("multiplier", "default"),
+            inputs.join("default.json")
+        );
     }
 }
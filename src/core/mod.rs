@@ -1,7 +1,9 @@
 //! Core Circomkit functionality
 
+mod backend;
 mod circomkit;
 mod config;
 
+pub use backend::{Backend, CommandBackend, Invocation, MockBackend, Stage};
 pub use circomkit::Circomkit;
-pub use config::CircomkitConfig;
+pub use config::{BuildLayout, CIRCOM_ENV, CircomkitConfig, SNARKJS_ENV};
@@ -25,6 +25,17 @@ pub enum CircomkitError {
     #[error("Witness generation failed: {message}")]
     WitnessGenerationFailed { message: String },
 
+    /// A circuit assertion or constraint failed during witness generation
+    #[error("Assertion failed{}: {message}", location(.template, .line))]
+    AssertFailed {
+        /// Template containing the failing assertion
+        template: Option<String>,
+        /// Source line of the failing assertion
+        line: Option<u32>,
+        /// Raw witness calculator output
+        message: String,
+    },
+
     /// Proof generation failed
     #[error("Proof generation failed: {message}")]
     ProofGenerationFailed { message: String },
@@ -90,6 +101,52 @@ impl CircomkitError {
         }
     }
 
+    /// Create a witness error from the witness calculator's stderr
+    ///
+    /// Failed assertions are reported by circom's witness calculator as
+    /// `Error in template <Name>_<id> line: <n>`, on its own line or after
+    /// `Assert Failed.`, with or without the colon. When present, the
+    /// innermost template and line are extracted into
+    /// [`CircomkitError::AssertFailed`].
+    pub fn from_witness_stderr(stderr: impl Into<String>) -> Self {
+        let message = stderr.into();
+
+        if !message.contains("Assert Failed") {
+            return Self::WitnessGenerationFailed { message };
+        }
+
+        const MARKER: &str = "Error in template ";
+        let location = message
+            .lines()
+            .find_map(|line| line.find(MARKER).map(|i| line[i + MARKER.len()..].trim()));
+
+        let (template, line) = match location {
+            Some(location) => {
+                let (name, line) = match location.split_once(" line") {
+                    Some((name, line)) => {
+                        let line = line.trim_start_matches(':').trim();
+                        let digits = line.find(|c: char| !c.is_ascii_digit());
+                        (name, line[..digits.unwrap_or(line.len())].parse().ok())
+                    }
+                    None => (location, None),
+                };
+                // circom suffixes template instances with `_<id>`
+                let name = match name.rsplit_once('_') {
+                    Some((base, id)) if id.chars().all(|c| c.is_ascii_digit()) => base,
+                    _ => name,
+                };
+                (Some(name.trim().to_string()), line)
+            }
+            None => (None, None),
+        };
+
+        Self::AssertFailed {
+            template,
+            line,
+            message,
+        }
+    }
+
     /// Create a new proof generation error
     pub fn proof_failed(message: impl Into<String>) -> Self {
         Self::ProofGenerationFailed {
@@ -109,3 +166,64 @@ impl CircomkitError {
         Self::ToolNotFound { tool: tool.into() }
     }
 }
+
+/// Format an optional assertion source location
+fn location(template: &Option<String>, line: &Option<u32>) -> String {
+    match (template, line) {
+        (Some(template), Some(line)) => format!(" in template {} at line {}", template, line),
+        (Some(template), None) => format!(" in template {}", template),
+        (None, Some(line)) => format!(" at line {}", line),
+        (None, None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_witness_stderr_assert() {
+        let stderr = "Error: Assert Failed.\nError in template Num2Bits_0 line: 38\nError in template RangeCheck_1 line: 7\n";
+        let err = CircomkitError::from_witness_stderr(stderr);
+
+        match &err {
+            CircomkitError::AssertFailed { template, line, .. } => {
+                assert_eq!(template.as_deref(), Some("Num2Bits"));
+                assert_eq!(*line, Some(38));
+            }
+            other => panic!("Expected AssertFailed, got {:?}", other),
+        }
+        assert!(err.to_string().contains("in template Num2Bits at line 38"));
+
+        // Older witness calculators print the location inline, without a colon
+        let err = CircomkitError::from_witness_stderr(
+            "Error: Assert Failed. Error in template IsZero_3 line 12\n    at Object.<anonymous>",
+        );
+        match &err {
+            CircomkitError::AssertFailed { template, line, .. } => {
+                assert_eq!(template.as_deref(), Some("IsZero"));
+                assert_eq!(*line, Some(12));
+            }
+            other => panic!("Expected AssertFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_from_witness_stderr_other() {
+        let err = CircomkitError::from_witness_stderr("Error: Not all inputs have been set.");
+        assert!(matches!(
+            err,
+            CircomkitError::WitnessGenerationFailed { .. }
+        ));
+
+        let err = CircomkitError::from_witness_stderr("Error: Assert Failed.");
+        assert!(matches!(
+            err,
+            CircomkitError::AssertFailed {
+                template: None,
+                line: None,
+                ..
+            }
+        ));
+    }
+}
@@ -37,6 +37,8 @@
 
 pub mod core;
 pub mod error;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod testers;
 pub mod types;
 pub mod utils;
@@ -45,7 +47,10 @@ pub mod utils;
 mod tests;
 
 // Re-exports for convenience
-pub use core::{Circomkit, CircomkitConfig};
+pub use core::{Backend, Circomkit, CircomkitConfig, MockBackend, Stage};
 pub use error::{CircomkitError, Result};
-pub use testers::{ProofTester, WitnessTester};
-pub use types::{CircuitConfig, CircuitSignals, Proof, VerificationKey};
+pub use testers::{OutputFilter, ProofTester, RunSummary, WitnessTester};
+pub use types::{
+    CircuitConfig, CircuitSignals, CompatibilityWarning, FullCheckReport, InfoDelta, NamedParams,
+    Proof, ToolReport, VerificationKey,
+};
@@ -0,0 +1,279 @@
+//! HTTP proving server
+//!
+//! Serves a [`Circomkit`]'s registered circuits over HTTP:
+//!
+//! - `POST /prove/{circuit}` takes the input signals as a JSON object and
+//!   returns `{"proof": ..., "publicSignals": [...]}`
+//! - `POST /verify/{circuit}` takes a proof in that same shape and returns
+//!   `{"valid": bool}`
+//! - `GET /info/{circuit}` returns the circuit's [`CircuitInfo`]
+//!
+//! Circuits are looked up by name with [`Circomkit::get_circuit`], so load or
+//! add them before serving; an unknown name is a 404. Failures are returned as
+//! `{"error": message}`.
+
+use crate::core::Circomkit;
+use crate::error::{CircomkitError, Result};
+use crate::types::{CircuitConfig, CircuitInfo, Proof, PublicSignals};
+use crate::utils::signals_from_json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+/// A proof with its public signals, as `/prove` returns and `/verify` takes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofPayload {
+    /// The proof
+    pub proof: Proof,
+    /// Public signals the proof commits to
+    pub public_signals: PublicSignals,
+}
+
+/// Response body of `/verify`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    /// Whether the proof is valid
+    pub valid: bool,
+}
+
+/// Build the server's router around `circomkit`
+pub fn router(circomkit: Circomkit) -> Router {
+    Router::new()
+        .route("/prove/{circuit}", post(prove))
+        .route("/verify/{circuit}", post(verify))
+        .route("/info/{circuit}", get(info))
+        .with_state(Arc::new(circomkit))
+}
+
+/// Serve `circomkit`'s circuits on `addr` until the server fails
+pub async fn serve(circomkit: Circomkit, addr: impl ToSocketAddrs) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, router(circomkit)).await?;
+    Ok(())
+}
+
+/// A [`CircomkitError`] as an HTTP response
+struct ServerError(CircomkitError);
+
+impl From<CircomkitError> for ServerError {
+    fn from(error: CircomkitError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            CircomkitError::CircuitNotFound(_) => StatusCode::NOT_FOUND,
+            CircomkitError::InvalidSignals(_)
+            | CircomkitError::InvalidConfig(_)
+            | CircomkitError::Json(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = serde_json::json!({ "error": self.0.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Look up a registered circuit by name
+fn circuit<'a>(
+    circomkit: &'a Circomkit,
+    name: &str,
+) -> std::result::Result<&'a CircuitConfig, ServerError> {
+    circomkit
+        .get_circuit(name)
+        .ok_or_else(|| CircomkitError::CircuitNotFound(name.into()).into())
+}
+
+async fn prove(
+    State(circomkit): State<Arc<Circomkit>>,
+    Path(name): Path<String>,
+    Json(inputs): Json<serde_json::Value>,
+) -> std::result::Result<Json<ProofPayload>, ServerError> {
+    let circuit = circuit(&circomkit, &name)?;
+    let inputs = signals_from_json(inputs)?;
+    let (proof, public_signals) = circomkit.prove(circuit, &inputs).await?;
+    Ok(Json(ProofPayload {
+        proof,
+        public_signals,
+    }))
+}
+
+async fn verify(
+    State(circomkit): State<Arc<Circomkit>>,
+    Path(name): Path<String>,
+    Json(payload): Json<ProofPayload>,
+) -> std::result::Result<Json<VerifyResponse>, ServerError> {
+    let circuit = circuit(&circomkit, &name)?;
+    let valid = circomkit
+        .verify(circuit, &payload.proof, &payload.public_signals)
+        .await?;
+    Ok(Json(VerifyResponse { valid }))
+}
+
+async fn info(
+    State(circomkit): State<Arc<Circomkit>>,
+    Path(name): Path<String>,
+) -> std::result::Result<Json<CircuitInfo>, ServerError> {
+    let circuit = circuit(&circomkit, &name)?;
+    Ok(Json(circomkit.info(circuit).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{CircomkitConfig, MockBackend, Stage};
+    use crate::types::Protocol;
+    use crate::utils::r1cs_bytes;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Method, Request};
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    /// A router serving a compiled and set up `mul` circuit behind `backend`
+    async fn server(dir: &std::path::Path, backend: MockBackend) -> Router {
+        std::fs::write(dir.join("mul.circom"), "template Mul() {}").unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(dir)
+            .with_build_dir(dir.join("build"))
+            .with_protocol(Protocol::Plonk);
+        // 1 output and 2 private inputs, `c <== a * b`
+        let backend = backend
+            .with_file(Stage::Compile, ".r1cs", r1cs_bytes(1, 0, 2, 1))
+            .with_file(
+                Stage::Compile,
+                ".sym",
+                "1,1,0,main.c\n2,2,0,main.a\n3,3,0,main.b\n",
+            );
+        let mut circomkit = Circomkit::new(config).unwrap().with_backend(backend);
+        let circuit = CircuitConfig::new("mul").with_template("Mul");
+        circomkit.compile(&circuit).await.unwrap();
+        let build = circomkit.config().build_path("mul");
+        std::fs::write(build.join("plonk_pkey.zkey"), "").unwrap();
+        std::fs::write(build.join("plonk_vkey.json"), "{}").unwrap();
+        circomkit.add_circuit(circuit);
+        router(circomkit)
+    }
+
+    async fn request(
+        router: Router,
+        method: Method,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_prove_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = MockBackend::new()
+            .with_file(Stage::Prove, "proof.json", r#"{"A": ["1", "2"]}"#)
+            .with_file(Stage::Prove, "public.json", r#"["6"]"#);
+        let router = server(dir.path(), backend).await;
+
+        let (status, body) = request(
+            router.clone(),
+            Method::POST,
+            "/prove/mul",
+            json!({ "a": 2, "b": 3 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["publicSignals"], json!(["6"]));
+        assert_eq!(body["proof"]["protocol"], "plonk");
+
+        let (status, verified) = request(router, Method::POST, "/verify/mul", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(verified, json!({ "valid": true }));
+    }
+
+    #[tokio::test]
+    async fn test_verify_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = MockBackend::new().with_failure(Stage::Verify, "Invalid proof");
+        let router = server(dir.path(), backend).await;
+
+        let payload = json!({
+            "proof": { "protocol": "plonk", "A": ["1", "2"] },
+            "publicSignals": ["7"],
+        });
+        let (status, body) = request(router, Method::POST, "/verify/mul", payload).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "valid": false }));
+    }
+
+    #[tokio::test]
+    async fn test_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = MockBackend::new().with_stdout(
+            Stage::Other,
+            "Constraints: 1\nPrivate Inputs: 2\nPublic Inputs: 0\nOutputs: 1\nLabels: 4\n",
+        );
+        let router = server(dir.path(), backend).await;
+
+        let (status, body) = request(router, Method::GET, "/info/mul", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let info: CircuitInfo = serde_json::from_value(body).unwrap();
+        assert_eq!(
+            info,
+            CircuitInfo {
+                constraints: 1,
+                private_inputs: 2,
+                public_inputs: 0,
+                public_outputs: 1,
+                labels: 4,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = server(dir.path(), MockBackend::new()).await;
+
+        let (status, body) =
+            request(router.clone(), Method::GET, "/info/missing", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].as_str().unwrap().contains("missing"));
+
+        let (status, _) = request(router.clone(), Method::POST, "/prove/mul", json!([1, 2])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = request(
+            router.clone(),
+            Method::POST,
+            "/prove/mul",
+            json!({ "a": 1 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("b"));
+
+        let backend = MockBackend::new().with_failure(Stage::Prove, "out of memory");
+        let router = server(dir.path(), backend).await;
+        let (status, body) = request(
+            router,
+            Method::POST,
+            "/prove/mul",
+            json!({ "a": 1, "b": 1 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["error"].as_str().unwrap().contains("out of memory"));
+    }
+}
@@ -0,0 +1,24 @@
+//! Removal of tester build artifacts on drop
+
+use log::{debug, warn};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Remove a tester's build directory
+///
+/// `Drop` can't await, so inside a tokio runtime the removal runs on the
+/// blocking pool, which the runtime waits for before shutting down.
+pub(crate) fn remove_build_dir(dir: PathBuf) {
+    let remove = move || match std::fs::remove_dir_all(&dir) {
+        Ok(()) => debug!("Removed build directory: {:?}", dir),
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove build directory {:?}: {}", dir, e),
+    };
+
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(remove);
+        }
+        Err(_) => remove(),
+    }
+}
@@ -1,7 +1,10 @@
 //! Testing utilities for Circom circuits
 
+mod cleanup;
 mod proof;
+mod summary;
 mod witness;
 
-pub use proof::ProofTester;
-pub use witness::WitnessTester;
+pub use proof::{ProofTester, flip_proof_point_a, flip_proof_point_b, flip_proof_point_c};
+pub use summary::{CircuitRunResult, RunSummary, SharedRunSummary, StageTimingsMs};
+pub use witness::{OutputFilter, WitnessTester};
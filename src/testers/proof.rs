@@ -1,30 +1,40 @@
 //! Proof testing utilities
 
-use crate::core::{Circomkit, CircomkitConfig};
+use crate::core::{Circomkit, CircomkitConfig, Stage};
 use crate::error::{CircomkitError, Result};
-use crate::types::{CircuitConfig, CircuitSignals, Proof, ProofTestResult, PublicSignals};
-use std::path::PathBuf;
+use crate::testers::cleanup;
+use crate::testers::summary::{self, SharedRunSummary};
+use crate::types::{
+    CircuitArtifacts, CircuitConfig, CircuitSignals, Proof, ProofFailureKind, ProofTestResult,
+    PublicSignals, StageTimings, VerificationKey,
+};
+use crate::utils::{fetch_ptau, get_recommended_ptau, ptau_dir, read_r1cs_header};
+use num_bigint::BigUint;
+use std::path::{Path, PathBuf};
 
 /// Tester for circuit proofs
 pub struct ProofTester {
     circomkit: Circomkit,
     circuit: CircuitConfig,
     ptau_path: PathBuf,
+    /// Fetch the recommended PTAU for the circuit's prime during setup
+    fetch_ptau: bool,
+    /// Existing proving key used instead of running setup
+    zkey_path: Option<PathBuf>,
     setup_complete: bool,
+    /// Artifacts of the completed setup
+    artifacts: Option<CircuitArtifacts>,
+    verification_key: Option<VerificationKey>,
+    summary: Option<SharedRunSummary>,
+    /// Build directory removed when the tester is dropped
+    cleanup_dir: Option<PathBuf>,
 }
 
 impl ProofTester {
     /// Create a new proof tester for a circuit
     pub async fn new(circuit: CircuitConfig, ptau_path: PathBuf) -> Result<Self> {
-        let config = CircomkitConfig::from_default_file()?;
-        let circomkit = Circomkit::new(config)?;
-
-        Ok(Self {
-            circomkit,
-            circuit,
-            ptau_path,
-            setup_complete: false,
-        })
+        let config = CircomkitConfig::from_default_file_async().await?;
+        Self::with_config(circuit, ptau_path, config).await
     }
 
     /// Create a new proof tester with custom configuration
@@ -33,56 +43,356 @@ impl ProofTester {
         ptau_path: PathBuf,
         config: CircomkitConfig,
     ) -> Result<Self> {
-        let circomkit = Circomkit::new(config)?;
+        Self::from_circomkit(circuit, ptau_path, Circomkit::new(config)?).await
+    }
 
+    /// Create a proof tester running `circuit` through an existing
+    /// Circomkit, keeping its backend and input hook
+    ///
+    /// Use this with [`Circomkit::with_backend`] to test a harness against a
+    /// [`MockBackend`](crate::MockBackend).
+    pub async fn from_circomkit(
+        circuit: CircuitConfig,
+        ptau_path: PathBuf,
+        circomkit: Circomkit,
+    ) -> Result<Self> {
         Ok(Self {
             circomkit,
             circuit,
             ptau_path,
+            fetch_ptau: false,
+            zkey_path: None,
             setup_complete: false,
+            artifacts: None,
+            verification_key: None,
+            summary: None,
+            cleanup_dir: None,
         })
     }
 
+    /// Create a proof tester that fetches its PTAU file during setup
+    ///
+    /// Uses the default configuration file; see
+    /// [`ProofTester::with_recommended_ptau`].
+    pub async fn new_auto(circuit: CircuitConfig) -> Result<Self> {
+        let config = CircomkitConfig::from_default_file_async().await?;
+        Self::with_recommended_ptau(circuit, config).await
+    }
+
+    /// Create a proof tester that picks its PTAU file during setup
+    ///
+    /// Once compiled, the PTAU recommended for the circuit's size and prime
+    /// is downloaded (BN128) or generated locally (BLS12-381) into the PTAU
+    /// directory. Goldilocks circuits fail setup, as they have no PTAU.
+    pub async fn with_recommended_ptau(
+        circuit: CircuitConfig,
+        config: CircomkitConfig,
+    ) -> Result<Self> {
+        let mut tester = Self::with_config(circuit, PathBuf::new(), config).await?;
+        tester.fetch_ptau = true;
+        Ok(tester)
+    }
+
+    /// Create a proof tester using an existing proving key
+    ///
+    /// Uses the default configuration file; see
+    /// [`ProofTester::from_zkey_with_config`].
+    pub async fn from_zkey(circuit: CircuitConfig, zkey_path: PathBuf) -> Result<Self> {
+        let config = CircomkitConfig::from_default_file_async().await?;
+        Self::from_zkey_with_config(circuit, zkey_path, config).await
+    }
+
+    /// Create a proof tester using an existing proving key, with custom
+    /// configuration
+    ///
+    /// The circuit is still compiled for its witness calculator, but setup
+    /// is replaced by [`Circomkit::setup_from_zkey`], so proofs are made
+    /// with production keys from a real ceremony.
+    pub async fn from_zkey_with_config(
+        circuit: CircuitConfig,
+        zkey_path: PathBuf,
+        config: CircomkitConfig,
+    ) -> Result<Self> {
+        let mut tester = Self::with_config(circuit, PathBuf::new(), config).await?;
+        tester.zkey_path = Some(zkey_path);
+        Ok(tester)
+    }
+
+    /// Create a proof tester reusing an earlier setup
+    ///
+    /// `artifacts` typically come from [`ProofTester::setup_only`] on another
+    /// tester. Compile and setup are skipped; this fails if the artifacts
+    /// lack keys or aren't the files the configuration would use for
+    /// `circuit`.
+    pub async fn from_artifacts(
+        circuit: CircuitConfig,
+        artifacts: CircuitArtifacts,
+    ) -> Result<Self> {
+        let config = CircomkitConfig::from_default_file_async().await?;
+        Self::from_artifacts_with_config(circuit, artifacts, config).await
+    }
+
+    /// Create a proof tester reusing an earlier setup, with custom configuration
+    pub async fn from_artifacts_with_config(
+        circuit: CircuitConfig,
+        artifacts: CircuitArtifacts,
+        config: CircomkitConfig,
+    ) -> Result<Self> {
+        let mut tester = Self::with_config(circuit, PathBuf::new(), config).await?;
+        let files = tester.circomkit.list_artifacts(&tester.circuit).await?;
+
+        let checks = [
+            ("WASM", Some(&artifacts.wasm), files.wasm.as_ref()),
+            ("proving key", artifacts.pkey.as_ref(), files.pkey.as_ref()),
+            (
+                "verification key",
+                artifacts.vkey.as_ref(),
+                files.vkey.as_ref(),
+            ),
+        ];
+        for (kind, given, expected) in checks {
+            let given = given.ok_or_else(|| {
+                CircomkitError::InvalidConfig(format!("Artifacts have no {}", kind))
+            })?;
+            if !expected.is_some_and(|expected| same_file(given, expected)) {
+                return Err(CircomkitError::InvalidConfig(format!(
+                    "{} {} doesn't belong to circuit '{}' in this configuration",
+                    kind,
+                    given.display(),
+                    tester.circuit.name
+                )));
+            }
+        }
+
+        tester.verification_key = Some(
+            tester
+                .circomkit
+                .export_verification_key(&tester.circuit)
+                .await?,
+        );
+        tester.artifacts = Some(artifacts);
+        tester.setup_complete = true;
+        Ok(tester)
+    }
+
+    /// Record results from this tester into a shared run summary
+    pub fn with_summary(mut self, summary: SharedRunSummary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Run `hook` on the inputs of every witness, as
+    /// [`Circomkit::with_input_hook`]
+    pub fn with_input_hook(
+        mut self,
+        hook: impl Fn(&mut CircuitSignals) + Send + Sync + 'static,
+    ) -> Self {
+        self.circomkit.set_input_hook(hook);
+        self
+    }
+
+    /// Remove the circuit's build directory, keys included, when the tester
+    /// is dropped
+    ///
+    /// Off by default, so artifacts can be inspected after a failure.
+    pub fn cleanup_on_drop(mut self, enabled: bool) -> Self {
+        self.cleanup_dir = enabled.then(|| self.circomkit.config().build_path(&self.circuit.name));
+        self
+    }
+
     /// Ensure the circuit is compiled and keys are set up
+    ///
+    /// Compilation is skipped if [`Circomkit::is_compiled`] finds
+    /// up-to-date artifacts from an earlier run. A manifest saved with
+    /// [`Circomkit::save_artifacts`] locates them but doesn't skip the
+    /// freshness check.
     pub async fn ensure_setup(&mut self) -> Result<()> {
-        if !self.setup_complete {
-            // Compile circuit
-            self.circomkit.compile(&self.circuit).await?;
+        self.setup_if_needed(&mut StageTimings::default()).await
+    }
+
+    /// Compile and set up unless already done, recording the time taken
+    async fn setup_if_needed(&mut self, timings: &mut StageTimings) -> Result<()> {
+        if self.setup_complete {
+            return Ok(());
+        }
 
-            // Set up proving/verification keys
-            self.circomkit.setup(&self.circuit, &self.ptau_path).await?;
+        // Compile circuit, unless an earlier run left it up to date; a saved
+        // manifest only tells where its artifacts are
+        self.circomkit.load_artifacts(&self.circuit).await?;
+        if !self.circomkit.is_compiled(&self.circuit).await {
+            let (result, elapsed) = summary::timed(
+                "compile",
+                &self.circuit.name,
+                self.circomkit.compile(&self.circuit),
+            )
+            .await;
+            timings.compile = Some(elapsed);
+            summary::record(&self.summary, |s| {
+                s.record_compile(&self.circuit.name, result.is_ok(), elapsed)
+            });
+            result?;
+        }
 
-            self.setup_complete = true;
+        if self.fetch_ptau {
+            self.ptau_path = self.recommended_ptau().await?;
+            self.fetch_ptau = false;
         }
+
+        // Set up proving/verification keys
+        let (result, elapsed) = match &self.zkey_path {
+            Some(zkey_path) => {
+                summary::timed(
+                    "setup",
+                    &self.circuit.name,
+                    self.circomkit.setup_from_zkey(&self.circuit, zkey_path),
+                )
+                .await
+            }
+            None => {
+                summary::timed(
+                    "setup",
+                    &self.circuit.name,
+                    self.circomkit.setup(&self.circuit, &self.ptau_path),
+                )
+                .await
+            }
+        };
+        timings.setup = Some(elapsed);
+        summary::record(&self.summary, |s| {
+            s.record_setup(&self.circuit.name, elapsed)
+        });
+        self.artifacts = Some(result?);
+
+        self.verification_key = Some(
+            self.circomkit
+                .export_verification_key(&self.circuit)
+                .await?,
+        );
+        self.setup_complete = true;
         Ok(())
     }
 
+    /// Compile and set up the circuit without proving anything
+    ///
+    /// The returned artifacts can be passed to [`ProofTester::from_artifacts`]
+    /// so other testers of the same circuit skip setup.
+    pub async fn setup_only(&mut self) -> Result<CircuitArtifacts> {
+        self.ensure_setup().await?;
+        self.artifacts
+            .clone()
+            .ok_or_else(|| CircomkitError::Other("Setup produced no artifacts".to_string()))
+    }
+
+    /// Fetch the PTAU recommended for the compiled circuit
+    async fn recommended_ptau(&self) -> Result<PathBuf> {
+        let config = self.circomkit.config();
+        let r1cs_path = config
+            .build_path(&self.circuit.name)
+            .join(format!("{}.r1cs", self.circuit.name));
+        self.circomkit
+            .require_artifact(&r1cs_path, config.emit_r1cs, "emit_r1cs")?;
+        let header = read_r1cs_header(&r1cs_path).await?;
+
+        // Size by the whole setup domain, not just the constraints
+        let prime = self.circuit.prime.unwrap_or(config.prime);
+        let info = get_recommended_ptau(1 << header.ptau_power(), prime)?;
+        let dir = ptau_dir(&config.dir_ptau);
+        fetch_ptau(&info, &dir, config, config.ptau_download_attempts).await
+    }
+
+    /// Verification key of the circuit, available once setup has run
+    pub fn verification_key(&self) -> Option<&VerificationKey> {
+        self.verification_key.as_ref()
+    }
+
     /// Generate and verify a proof
+    ///
+    /// Failures are reported in the result, with `error_kind` telling
+    /// whether setup, proving, or verification failed, or a tool was missing.
     pub async fn prove_and_verify(&mut self, inputs: CircuitSignals) -> Result<ProofTestResult> {
-        self.ensure_setup().await?;
+        let mut durations = StageTimings::default();
+        let result = match self.attempt(&inputs, &mut durations).await {
+            Ok((proof, public_signals, valid)) => ProofTestResult {
+                valid,
+                proof: Some(proof),
+                public_signals: Some(public_signals),
+                error: (!valid).then(|| "Proof verification returned false".to_string()),
+                error_kind: (!valid).then_some(ProofFailureKind::VerificationReturnedFalse),
+                durations,
+            },
+            Err((kind, e)) => ProofTestResult {
+                valid: false,
+                proof: None,
+                public_signals: None,
+                error: Some(e.to_string()),
+                error_kind: Some(kind),
+                durations,
+            },
+        };
+
+        Ok(result)
+    }
+
+    /// Set up, then generate and verify a proof, classifying any failure
+    async fn attempt(
+        &mut self,
+        inputs: &CircuitSignals,
+        timings: &mut StageTimings,
+    ) -> std::result::Result<(Proof, PublicSignals, bool), (ProofFailureKind, CircomkitError)> {
+        self.setup_if_needed(timings)
+            .await
+            .map_err(|e| (ProofFailureKind::of(&e, ProofFailureKind::SetupFailed), e))?;
+
+        let result = self.round_trip(inputs, timings).await;
+        let elapsed = timings.prove.unwrap_or_default() + timings.verify.unwrap_or_default();
+        summary::record(&self.summary, |s| {
+            s.record_proof(
+                &self.circuit.name,
+                matches!(result, Ok((_, _, true))),
+                elapsed,
+            )
+        });
+
+        result
+    }
+
+    /// Generate a proof and verify it, classifying any failure by stage
+    async fn round_trip(
+        &self,
+        inputs: &CircuitSignals,
+        timings: &mut StageTimings,
+    ) -> std::result::Result<(Proof, PublicSignals, bool), (ProofFailureKind, CircomkitError)> {
+        let name = &self.circuit.name;
+        let failed = |stage| move |e| (ProofFailureKind::of(&e, stage), e);
 
         // Generate proof
-        let (proof, public_signals) = self.circomkit.prove(&self.circuit, &inputs).await?;
+        let (result, elapsed) =
+            summary::timed("prove", name, self.circomkit.prove(&self.circuit, inputs)).await;
+        timings.prove = Some(elapsed);
+        let (proof, public_signals) = result.map_err(failed(ProofFailureKind::ProvingFailed))?;
 
         // Verify proof
-        let valid = self
-            .circomkit
-            .verify(&self.circuit, &proof, &public_signals)
-            .await?;
-
-        Ok(ProofTestResult {
-            valid,
-            proof: Some(proof),
-            public_signals: Some(public_signals),
-            error: None,
-        })
+        let (result, elapsed) = summary::timed(
+            "verify",
+            name,
+            self.circomkit
+                .verify(&self.circuit, &proof, &public_signals),
+        )
+        .await;
+        timings.verify = Some(elapsed);
+        let valid = result.map_err(failed(ProofFailureKind::VerificationFailed))?;
+
+        Ok((proof, public_signals, valid))
     }
 
     /// Test that a valid proof can be generated and verified
     pub async fn expect_valid_proof(&mut self, inputs: CircuitSignals) -> Result<()> {
-        let result = self.prove_and_verify(inputs).await?;
+        let (_, _, valid) = self
+            .attempt(&inputs, &mut StageTimings::default())
+            .await
+            .map_err(|(_, e)| e)?;
 
-        if !result.valid {
+        if !valid {
             return Err(CircomkitError::verification_failed(
                 "Proof was generated but verification failed",
             ));
@@ -135,6 +445,34 @@ impl ProofTester {
         Ok(())
     }
 
+    /// Verify a tampered proof (should be rejected)
+    ///
+    /// Generates a valid proof, lets `tamper_fn` modify it (see
+    /// [`flip_proof_point_a`] and friends), and fails unless verification
+    /// returns `false` or snarkjs rejects the proof outright.
+    pub async fn expect_tampered_proof_fails(
+        &mut self,
+        inputs: CircuitSignals,
+        tamper_fn: impl FnOnce(&mut Proof),
+    ) -> Result<()> {
+        self.ensure_setup().await?;
+
+        let (mut proof, public_signals) = self.circomkit.prove(&self.circuit, &inputs).await?;
+        tamper_fn(&mut proof);
+
+        match self
+            .circomkit
+            .verify(&self.circuit, &proof, &public_signals)
+            .await
+        {
+            Ok(false) | Err(CircomkitError::VerificationFailed { .. }) => Ok(()),
+            Ok(true) => Err(CircomkitError::Other(
+                "Expected verification to fail for tampered proof, but it passed".to_string(),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Generate a proof and return it
     pub async fn generate_proof(
         &mut self,
@@ -168,41 +506,50 @@ impl ProofTester {
 
         let (proof, public_signals) = self.circomkit.prove(&self.circuit, &inputs).await?;
 
-        let build_dir = self.circomkit.config().build_path(&self.circuit.name);
-        let protocol = self.circomkit.config().protocol.to_string();
+        let temp_dir = self.circomkit.config().temp_path(&self.circuit.name);
+        tokio::fs::create_dir_all(&temp_dir).await?;
 
         // Write proof and public signals to temp files
-        let proof_path = build_dir.join("calldata_proof.json");
-        let public_path = build_dir.join("calldata_public.json");
+        let proof_path = temp_dir.join("calldata_proof.json");
+        let public_path = temp_dir.join("calldata_public.json");
 
-        tokio::fs::write(&proof_path, serde_json::to_string(&proof.data)?).await?;
+        tokio::fs::write(&proof_path, serde_json::to_string(&proof)?).await?;
         tokio::fs::write(&public_path, serde_json::to_string(&public_signals.0)?).await?;
 
-        let snarkjs = self.circomkit.config().snarkjs_command();
+        let config = self.circomkit.config();
+        let snarkjs = config.snarkjs_command();
+        let mut args = vec![
+            "zkey".to_string(),
+            "export".to_string(),
+            "soliditycalldata".to_string(),
+            public_path.to_string_lossy().to_string(),
+            proof_path.to_string_lossy().to_string(),
+        ];
+        args.extend(config.snarkjs_flags.iter().cloned());
 
-        let output = std::process::Command::new(&snarkjs)
-            .arg("zkey")
-            .arg("export")
-            .arg("soliditycalldata")
-            .arg(&public_path)
-            .arg(&proof_path)
-            .output()
-            .map_err(CircomkitError::Io)?;
+        let output = self.circomkit.run_tool(Stage::Other, &snarkjs, &args, &[]);
 
         // Clean up temp files
         let _ = tokio::fs::remove_file(&proof_path).await;
         let _ = tokio::fs::remove_file(&public_path).await;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: snarkjs,
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
-            });
+        Ok(String::from_utf8_lossy(&output?.stdout).to_string())
+    }
+}
+
+impl Drop for ProofTester {
+    fn drop(&mut self) {
+        if let Some(dir) = self.cleanup_dir.take() {
+            cleanup::remove_build_dir(dir);
         }
+    }
+}
 
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+/// Whether two existing paths refer to the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
     }
 }
 
@@ -215,9 +562,60 @@ macro_rules! proof_test {
     }};
 }
 
+/// BN128 base field modulus, which Groth16 proof coordinates live in
+const BN128_BASE_FIELD: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+/// Negate the `A` point of a Groth16 proof
+///
+/// The result is still on the curve, so verifiers reject it for the
+/// pairing check rather than as malformed. Does nothing for proofs
+/// without a `pi_a` field.
+pub fn flip_proof_point_a(proof: &mut Proof) {
+    negate_point(&mut proof.data, "pi_a");
+}
+
+/// Negate the `B` point of a Groth16 proof; see [`flip_proof_point_a`]
+pub fn flip_proof_point_b(proof: &mut Proof) {
+    negate_point(&mut proof.data, "pi_b");
+}
+
+/// Negate the `C` point of a Groth16 proof; see [`flip_proof_point_a`]
+pub fn flip_proof_point_c(proof: &mut Proof) {
+    negate_point(&mut proof.data, "pi_c");
+}
+
+/// Negate the snarkjs `[x, y, z]` point in `field` by replacing `y` with `-y`
+///
+/// `y` is a decimal string for G1 points and a pair of them for G2 points.
+fn negate_point(data: &mut serde_json::Value, field: &str) {
+    let Some(y) = data.get_mut(field).and_then(|point| point.get_mut(1)) else {
+        return;
+    };
+
+    let modulus: BigUint = BN128_BASE_FIELD.parse().expect("valid modulus");
+    let negate = |value: &mut serde_json::Value| {
+        if let Some(y) = value.as_str().and_then(|y| y.parse::<BigUint>().ok())
+            && y != BigUint::ZERO
+            && y < modulus
+        {
+            *value = serde_json::Value::String((&modulus - y).to_string());
+        }
+    };
+
+    match y.as_array_mut() {
+        Some(coeffs) => coeffs.iter_mut().for_each(negate),
+        None => negate(y),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::MockBackend;
+    use crate::types::Protocol;
+    use crate::utils::r1cs_bytes;
+    use std::sync::Arc;
 
     // Integration tests would require actual circom/snarkjs installation
     // These are placeholder tests for the structure
@@ -232,4 +630,232 @@ mod tests {
         assert_eq!(circuit.name, "test");
         assert_eq!(ptau_path.to_str().unwrap(), "test.ptau");
     }
+
+    #[tokio::test]
+    async fn test_from_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("test");
+        std::fs::create_dir_all(build.join("test_js")).unwrap();
+        std::fs::write(build.join("test_js/test.wasm"), b"").unwrap();
+        std::fs::write(build.join("groth16_pkey.zkey"), b"").unwrap();
+        std::fs::write(
+            build.join("groth16_vkey.json"),
+            r#"{"protocol": "groth16", "nPublic": 1}"#,
+        )
+        .unwrap();
+
+        let config = CircomkitConfig::default().with_build_dir(dir.path());
+        let artifacts = CircuitArtifacts {
+            r1cs: build.join("test.r1cs"),
+            wasm: build.join("test_js/test.wasm"),
+            sym: build.join("test.sym"),
+            pkey: Some(build.join("groth16_pkey.zkey")),
+            vkey: Some(build.join("groth16_vkey.json")),
+            warnings: Vec::new(),
+        };
+
+        let mut tester = ProofTester::from_artifacts_with_config(
+            CircuitConfig::new("test"),
+            artifacts.clone(),
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tester.verification_key().unwrap().num_public().unwrap(), 1);
+        // Already set up, so nothing is compiled
+        assert_eq!(tester.setup_only().await.unwrap().pkey, artifacts.pkey);
+
+        // Keys of another circuit, or no keys at all
+        let other = ProofTester::from_artifacts_with_config(
+            CircuitConfig::new("other"),
+            artifacts.clone(),
+            config.clone(),
+        )
+        .await;
+        assert!(matches!(other, Err(CircomkitError::InvalidConfig(_))));
+        let keyless = CircuitArtifacts {
+            pkey: None,
+            ..artifacts
+        };
+        let keyless =
+            ProofTester::from_artifacts_with_config(CircuitConfig::new("test"), keyless, config)
+                .await;
+        assert!(matches!(keyless, Err(CircomkitError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_from_zkey() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("test.circom");
+        std::fs::write(&file, "template Test() { signal input a; }").unwrap();
+        let zkey = dir.path().join("production.zkey");
+        std::fs::write(&zkey, b"ceremony key").unwrap();
+
+        let config = CircomkitConfig::default().with_build_dir(dir.path().join("build"));
+        let circuit = CircuitConfig::new("test").with_absolute_file(&file);
+        let backend = Arc::new(MockBackend::new().with_file(
+            Stage::Setup,
+            "vkey.json",
+            r#"{"protocol": "groth16", "nPublic": 2}"#,
+        ));
+        let mut tester =
+            ProofTester::from_zkey_with_config(circuit.clone(), zkey.clone(), config.clone())
+                .await
+                .unwrap();
+        tester.circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(backend.clone());
+
+        // Compiled for the witness calculator, with no ceremony
+        let artifacts = tester.setup_only().await.unwrap();
+        assert_eq!(backend.calls(), [Stage::Compile, Stage::Setup]);
+        assert_eq!(
+            std::fs::read(artifacts.pkey.unwrap()).unwrap(),
+            b"ceremony key"
+        );
+        assert_eq!(tester.verification_key().unwrap().num_public().unwrap(), 2);
+
+        // A key for another protocol is rejected and not left behind
+        let circomkit = Circomkit::new(config.clone().with_protocol(Protocol::Plonk))
+            .unwrap()
+            .with_backend(MockBackend::new().with_file(
+                Stage::Setup,
+                "vkey.json",
+                r#"{"protocol": "groth16"}"#,
+            ));
+        let err = circomkit
+            .setup_from_zkey(&circuit, &zkey)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("is for groth16"));
+        assert!(!dir.path().join("build/test/plonk_pkey.zkey").exists());
+
+        assert!(matches!(
+            circomkit
+                .setup_from_zkey(&circuit, &dir.path().join("missing.zkey"))
+                .await,
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_new_auto_fetches_ptau() {
+        let tester = ProofTester::new_auto(CircuitConfig::new("test"))
+            .await
+            .unwrap();
+        assert!(tester.fetch_ptau);
+        assert!(!tester.setup_complete);
+    }
+
+    #[tokio::test]
+    async fn test_prove_and_verify_classifies_corrupted_vkey() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("test.circom");
+        std::fs::write(&file, "template Test() { signal input a; }").unwrap();
+        let zkey = dir.path().join("production.zkey");
+        std::fs::write(&zkey, b"ceremony key").unwrap();
+
+        let config = CircomkitConfig::default().with_build_dir(dir.path().join("build"));
+        let circuit = CircuitConfig::new("test").with_absolute_file(&file);
+        // One private input `a`; the exported key's IC points are garbage
+        let backend = MockBackend::new()
+            .with_file(Stage::Compile, ".r1cs", r1cs_bytes(0, 0, 1, 1))
+            .with_file(Stage::Compile, ".sym", "1,1,0,main.a\n")
+            .with_file(
+                Stage::Setup,
+                "vkey.json",
+                r#"{"protocol": "groth16", "curve": "bn128", "nPublic": 0, "IC": "corrupted"}"#,
+            )
+            .with_file(Stage::Prove, "proof.json", r#"{"pi_a": ["1", "2", "1"]}"#)
+            .with_file(Stage::Prove, "public.json", "[]")
+            .with_failure(
+                Stage::Verify,
+                "TypeError: Cannot read properties of undefined (reading '0')",
+            );
+        let circomkit = Circomkit::new(config).unwrap().with_backend(backend);
+        let mut tester = ProofTester::from_circomkit(circuit, PathBuf::new(), circomkit)
+            .await
+            .unwrap();
+        tester.zkey_path = Some(zkey);
+
+        let result = tester
+            .prove_and_verify(crate::signals! { "a" => 1 })
+            .await
+            .unwrap();
+        assert!(!result.valid);
+        assert_eq!(
+            result.error_kind,
+            Some(ProofFailureKind::VerificationFailed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prove_and_verify_classifies_missing_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("test.circom");
+        std::fs::write(&file, "template Test() { signal input a; }").unwrap();
+
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path().join("build"))
+            .with_circom_path(dir.path().join("no-such-circom"));
+        let circuit = CircuitConfig::new("test").with_absolute_file(&file);
+        let mut tester = ProofTester::with_config(circuit, dir.path().join("test.ptau"), config)
+            .await
+            .unwrap();
+
+        let result = tester
+            .prove_and_verify(CircuitSignals::new())
+            .await
+            .unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.error_kind, Some(ProofFailureKind::ToolMissing));
+        assert!(result.error.unwrap().contains("no-such-circom"));
+    }
+
+    #[test]
+    fn test_flip_proof_points() {
+        let original = Proof {
+            protocol: crate::types::Protocol::Groth16,
+            data: serde_json::json!({
+                "pi_a": ["1", "2", "1"],
+                "pi_b": [["1", "2"], ["3", "0"], ["1", "0"]],
+                "pi_c": ["1", "2", "1"],
+            }),
+        };
+        let p_minus = |n: u32| (BN128_BASE_FIELD.parse::<BigUint>().unwrap() - n).to_string();
+
+        let mut proof = original.clone();
+        flip_proof_point_a(&mut proof);
+        assert_eq!(proof.data["pi_a"][1], p_minus(2));
+        assert_eq!(proof.data["pi_c"], original.data["pi_c"]);
+
+        flip_proof_point_b(&mut proof);
+        assert_eq!(proof.data["pi_b"][1], serde_json::json!([p_minus(3), "0"]));
+
+        // Negating twice restores the proof
+        flip_proof_point_a(&mut proof);
+        flip_proof_point_b(&mut proof);
+        assert_eq!(proof.data, original.data);
+
+        // Non-Groth16 proofs are left alone
+        let mut plonk = Proof {
+            protocol: crate::types::Protocol::Plonk,
+            data: serde_json::json!({"A": ["1", "2", "1"]}),
+        };
+        flip_proof_point_c(&mut plonk);
+        assert_eq!(plonk.data, serde_json::json!({"A": ["1", "2", "1"]}));
+    }
+
+    #[test]
+    fn test_failure_kind() {
+        let setup = ProofFailureKind::SetupFailed;
+        assert_eq!(
+            ProofFailureKind::of(&CircomkitError::tool_not_found("snarkjs"), setup),
+            ProofFailureKind::ToolMissing
+        );
+        assert_eq!(
+            ProofFailureKind::of(&CircomkitError::proof_failed("bad witness"), setup),
+            setup
+        );
+    }
 }
@@ -0,0 +1,206 @@
+//! Run summaries collected across testers
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A run summary shared between testers
+pub type SharedRunSummary = Arc<Mutex<RunSummary>>;
+
+/// Time spent in each pipeline stage, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTimingsMs {
+    /// Circuit compilation
+    pub compile: u64,
+    /// Witness generation
+    pub witness: u64,
+    /// Key setup
+    pub setup: u64,
+    /// Proof generation and verification
+    pub prove: u64,
+}
+
+/// Results recorded for a single circuit
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitRunResult {
+    /// Circuit name
+    pub circuit: String,
+    /// Whether the circuit compiled
+    pub compiled: bool,
+    /// Number of constraints, if known
+    pub constraints: Option<usize>,
+    /// Witness tests that passed
+    pub witness_passed: usize,
+    /// Witness tests that failed
+    pub witness_failed: usize,
+    /// Proofs that verified
+    pub proofs_valid: usize,
+    /// Proofs that failed to generate or verify
+    pub proofs_invalid: usize,
+    /// Accumulated time per stage
+    pub timings_ms: StageTimingsMs,
+}
+
+/// Summary of a full test run across many circuits
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// Per-circuit results, in the order circuits were first seen
+    pub circuits: Vec<CircuitRunResult>,
+}
+
+impl RunSummary {
+    /// Create an empty summary
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty summary that can be shared between testers
+    pub fn shared() -> SharedRunSummary {
+        Arc::new(Mutex::new(Self::new()))
+    }
+
+    /// Get the results for a circuit
+    pub fn circuit(&self, name: &str) -> Option<&CircuitRunResult> {
+        self.circuits.iter().find(|c| c.circuit == name)
+    }
+
+    /// Get the results for a circuit, creating an entry if needed
+    fn entry(&mut self, name: &str) -> &mut CircuitRunResult {
+        let idx = match self.circuits.iter().position(|c| c.circuit == name) {
+            Some(idx) => idx,
+            None => {
+                self.circuits.push(CircuitRunResult {
+                    circuit: name.to_string(),
+                    ..Default::default()
+                });
+                self.circuits.len() - 1
+            }
+        };
+        &mut self.circuits[idx]
+    }
+
+    /// Record a compilation
+    pub fn record_compile(&mut self, name: &str, compiled: bool, elapsed: Duration) {
+        let entry = self.entry(name);
+        entry.compiled = compiled;
+        entry.timings_ms.compile += elapsed.as_millis() as u64;
+    }
+
+    /// Record the constraint count
+    pub fn record_constraints(&mut self, name: &str, constraints: usize) {
+        self.entry(name).constraints = Some(constraints);
+    }
+
+    /// Record a witness test outcome
+    pub fn record_witness(&mut self, name: &str, passed: bool, elapsed: Duration) {
+        let entry = self.entry(name);
+        if passed {
+            entry.witness_passed += 1;
+        } else {
+            entry.witness_failed += 1;
+        }
+        entry.timings_ms.witness += elapsed.as_millis() as u64;
+    }
+
+    /// Record a key setup
+    pub fn record_setup(&mut self, name: &str, elapsed: Duration) {
+        self.entry(name).timings_ms.setup += elapsed.as_millis() as u64;
+    }
+
+    /// Record a proof round-trip outcome
+    pub fn record_proof(&mut self, name: &str, valid: bool, elapsed: Duration) {
+        let entry = self.entry(name);
+        if valid {
+            entry.proofs_valid += 1;
+        } else {
+            entry.proofs_invalid += 1;
+        }
+        entry.timings_ms.prove += elapsed.as_millis() as u64;
+    }
+
+    /// Serialize the summary to pretty JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Record into an optional shared summary
+pub(crate) fn record(summary: &Option<SharedRunSummary>, f: impl FnOnce(&mut RunSummary)) {
+    if let Some(summary) = summary
+        && let Ok(mut summary) = summary.lock()
+    {
+        f(&mut summary);
+    }
+}
+
+/// Run a pipeline stage and time it
+///
+/// With the `tracing` feature the stage runs inside an `info` span carrying
+/// the stage and circuit names, and its elapsed time is logged at `debug`.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) async fn timed<F: Future>(
+    stage: &'static str,
+    circuit: &str,
+    future: F,
+) -> (F::Output, Duration) {
+    #[cfg(feature = "tracing")]
+    let future =
+        tracing::Instrument::instrument(future, tracing::info_span!("circomkit", stage, circuit));
+
+    let start = Instant::now();
+    let output = future.await;
+    let elapsed = start.elapsed();
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        stage,
+        circuit,
+        elapsed_ms = elapsed.as_millis() as u64,
+        "stage finished"
+    );
+
+    (output, elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_json() {
+        let mut summary = RunSummary::new();
+
+        summary.record_compile("adder", true, Duration::from_millis(120));
+        summary.record_constraints("adder", 1);
+        summary.record_witness("adder", true, Duration::from_millis(30));
+        summary.record_witness("adder", false, Duration::from_millis(20));
+
+        summary.record_compile("multiplier", true, Duration::from_millis(80));
+        summary.record_setup("multiplier", Duration::from_millis(500));
+        summary.record_proof("multiplier", true, Duration::from_millis(300));
+
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+        let circuits = json["circuits"].as_array().unwrap();
+        assert_eq!(circuits.len(), 2);
+
+        let adder = &circuits[0];
+        assert_eq!(adder["circuit"], "adder");
+        assert_eq!(adder["compiled"], true);
+        assert_eq!(adder["constraints"], 1);
+        assert_eq!(adder["witnessPassed"], 1);
+        assert_eq!(adder["witnessFailed"], 1);
+        assert_eq!(adder["timingsMs"]["compile"], 120);
+        assert_eq!(adder["timingsMs"]["witness"], 50);
+
+        let multiplier = &circuits[1];
+        assert_eq!(multiplier["circuit"], "multiplier");
+        assert_eq!(multiplier["constraints"], serde_json::Value::Null);
+        assert_eq!(multiplier["proofsValid"], 1);
+        assert_eq!(multiplier["proofsInvalid"], 0);
+        assert_eq!(multiplier["timingsMs"]["setup"], 500);
+        assert_eq!(multiplier["timingsMs"]["prove"], 300);
+    }
+}
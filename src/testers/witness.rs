@@ -2,17 +2,79 @@
 
 use crate::core::{Circomkit, CircomkitConfig};
 use crate::error::{CircomkitError, Result};
-use crate::types::{CircuitConfig, CircuitSignals, SignalValue, WitnessTestResult};
-use std::collections::HashMap;
+use crate::testers::cleanup;
+use crate::testers::summary::{self, SharedRunSummary};
+use crate::types::{
+    CircuitConfig, CircuitInfo, CircuitSignals, InfoDelta, Prime, SignalValue, StageTimings,
+    WitnessTestResult,
+};
+use crate::utils::{R1csHeader, Symbol, SymbolTable, read_r1cs_header, read_symbol_table};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::time::Duration;
 use tokio::fs;
 
+/// Messages the witness calculator emits when a constraint fails
+const CONSTRAINT_FAILURE_MARKERS: &[&str] = &["Assert Failed", "not satisfied"];
+
+/// Phrases in `circom --inspect` warnings about under-constrained signals
+const UNCONSTRAINED_MARKERS: &[&str] = &["does not appear in any constraint", "unconstrained"];
+
 /// Tester for circuit witnesses
 pub struct WitnessTester {
     circomkit: Circomkit,
     circuit: CircuitConfig,
     compiled: bool,
+    summary: Option<SharedRunSummary>,
+    output_filter: OutputFilter,
+    /// Whether `expect_output` fails on outputs missing from `expected`
+    strict_outputs: bool,
+    /// Absolute differences allowed per signal, as field elements
+    tolerances: HashMap<String, BigUint>,
+    /// Build directory removed when the tester is dropped
+    cleanup_dir: Option<PathBuf>,
+}
+
+/// Which signals are read back from a witness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFilter {
+    /// Outputs of the main component
+    #[default]
+    OutputsOnly,
+    /// Public inputs and outputs of the main component
+    PublicAndOutputs,
+    /// Every signal of the main component and its subcomponents, for
+    /// debugging intermediate values
+    All,
+}
+
+impl OutputFilter {
+    /// Signals from `symbols` selected by this filter, for a circuit whose
+    /// top-level instance is `main_name`
+    fn select<'a>(
+        &self,
+        symbols: &'a SymbolTable,
+        header: &R1csHeader,
+        main_name: &str,
+    ) -> Vec<&'a Symbol> {
+        match self {
+            Self::OutputsOnly => symbols.outputs(header),
+            Self::PublicAndOutputs => {
+                let mut selected = symbols.outputs(header);
+                selected.extend(symbols.public_inputs(header));
+                selected
+            }
+            Self::All => symbols
+                .iter()
+                .filter(|s| {
+                    s.name
+                        .strip_prefix(main_name)
+                        .is_some_and(|rest| rest.starts_with('.'))
+                })
+                .collect(),
+        }
+    }
 }
 
 impl WitnessTester {
@@ -42,39 +104,8 @@ impl WitnessTester {
         params: Vec<i64>,
         public: Vec<String>,
     ) -> Result<Self> {
-        let test_name = test_name.into();
-        let file_path = file_path.into();
-        let template = template.into();
-
-        // Resolve to absolute path
-        let abs_path = if file_path.is_absolute() {
-            file_path
-        } else {
-            std::env::current_dir()
-                .map_err(CircomkitError::Io)?
-                .join(&file_path)
-        };
-
-        // Verify the file exists
-        if !abs_path.exists() {
-            return Err(CircomkitError::CircuitNotFound(abs_path));
-        }
-
-        let config = CircomkitConfig::from_default_file()?;
-
-        let circuit = CircuitConfig::new(&test_name)
-            .with_absolute_file(abs_path)
-            .with_template(template)
-            .with_params(params)
-            .with_public(public);
-
-        let circomkit = Circomkit::new(config)?;
-
-        Ok(Self {
-            circomkit,
-            circuit,
-            compiled: false,
-        })
+        let config = CircomkitConfig::from_default_file_async().await?;
+        Self::with_config(test_name, file_path, template, params, public, config).await
     }
 
     /// Create a new witness tester with custom configuration
@@ -109,25 +140,13 @@ impl WitnessTester {
             .with_params(params)
             .with_public(public);
 
-        let circomkit = Circomkit::new(config)?;
-
-        Ok(Self {
-            circomkit,
-            circuit,
-            compiled: false,
-        })
+        Self::from_circomkit(circuit, Circomkit::new(config)?).await
     }
 
     /// Create a witness tester from a pre-configured CircuitConfig
     pub async fn from_circuit_config(circuit: CircuitConfig) -> Result<Self> {
-        let config = CircomkitConfig::from_default_file()?;
-        let circomkit = Circomkit::new(config)?;
-
-        Ok(Self {
-            circomkit,
-            circuit,
-            compiled: false,
-        })
+        let config = CircomkitConfig::from_default_file_async().await?;
+        Self::from_circuit_config_with_settings(circuit, config).await
     }
 
     /// Create a witness tester from CircuitConfig with custom Circomkit config
@@ -135,108 +154,334 @@ impl WitnessTester {
         circuit: CircuitConfig,
         config: CircomkitConfig,
     ) -> Result<Self> {
-        let circomkit = Circomkit::new(config)?;
+        Self::from_circomkit(circuit, Circomkit::new(config)?).await
+    }
 
+    /// Create a witness tester running `circuit` through an existing
+    /// Circomkit, keeping its backend and input hook
+    ///
+    /// Use this with [`Circomkit::with_backend`] to test a harness against a
+    /// [`MockBackend`](crate::MockBackend).
+    pub async fn from_circomkit(circuit: CircuitConfig, circomkit: Circomkit) -> Result<Self> {
         Ok(Self {
             circomkit,
             circuit,
             compiled: false,
+            summary: None,
+            output_filter: OutputFilter::default(),
+            strict_outputs: false,
+            tolerances: HashMap::new(),
+            cleanup_dir: None,
         })
     }
 
+    /// Record results from this tester into a shared run summary
+    pub fn with_summary(mut self, summary: SharedRunSummary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Choose which signals are read back from witnesses
+    pub fn with_output_filter(mut self, filter: OutputFilter) -> Self {
+        self.output_filter = filter;
+        self
+    }
+
+    /// Make [`WitnessTester::expect_output`] also fail on outputs that
+    /// `expected` doesn't list
+    ///
+    /// Off by default. Catches outputs accidentally added to a circuit's
+    /// interface in regression tests.
+    pub fn with_strict_outputs(mut self, strict: bool) -> Self {
+        self.strict_outputs = strict;
+        self
+    }
+
+    /// Let `name` differ from its expected value by up to `delta`
+    ///
+    /// Values are compared as field elements, so `p - 1` is one below
+    /// zero, and arrays element by element. For circuits approximating
+    /// fixed-point or other non-integer arithmetic. Differences beyond
+    /// `delta` still fail, reporting the actual difference.
+    pub fn with_tolerance(mut self, name: impl Into<String>, delta: impl Into<BigUint>) -> Self {
+        self.tolerances.insert(name.into(), delta.into());
+        self
+    }
+
+    /// Run `hook` on the inputs of every witness, as
+    /// [`Circomkit::with_input_hook`]
+    pub fn with_input_hook(
+        mut self,
+        hook: impl Fn(&mut CircuitSignals) + Send + Sync + 'static,
+    ) -> Self {
+        self.circomkit.set_input_hook(hook);
+        self
+    }
+
+    /// Remove the circuit's build directory when the tester is dropped
+    ///
+    /// Off by default, so artifacts can be inspected after a failure.
+    pub fn cleanup_on_drop(mut self, enabled: bool) -> Self {
+        self.cleanup_dir = enabled.then(|| self.circomkit.config().build_path(&self.circuit.name));
+        self
+    }
+
     /// Compile the circuit if not already compiled
+    ///
+    /// Compilation is skipped if [`Circomkit::is_compiled`] finds
+    /// up-to-date artifacts from an earlier run. A manifest saved with
+    /// [`Circomkit::save_artifacts`] locates them, e.g. a wasm written to a
+    /// non-standard directory, but doesn't skip the freshness check.
     pub async fn ensure_compiled(&mut self) -> Result<()> {
-        if !self.compiled {
-            self.circomkit.compile(&self.circuit).await?;
+        self.compile_if_needed().await.map(|_| ())
+    }
+
+    /// Compile the circuit unless already compiled, returning the time taken
+    async fn compile_if_needed(&mut self) -> Result<Option<Duration>> {
+        if self.compiled {
+            return Ok(None);
+        }
+        self.circomkit.load_artifacts(&self.circuit).await?;
+        if self.circomkit.is_compiled(&self.circuit).await {
             self.compiled = true;
+            return Ok(None);
         }
-        Ok(())
+
+        let (result, elapsed) = summary::timed(
+            "compile",
+            &self.circuit.name,
+            self.circomkit.compile(&self.circuit),
+        )
+        .await;
+        summary::record(&self.summary, |s| {
+            s.record_compile(&self.circuit.name, result.is_ok(), elapsed)
+        });
+        result?;
+        self.compiled = true;
+        Ok(Some(elapsed))
     }
 
     /// Test that a witness can be computed for the given inputs
     pub async fn expect_pass(&mut self, inputs: CircuitSignals) -> Result<CircuitSignals> {
         self.ensure_compiled().await?;
 
+        let (result, elapsed) =
+            summary::timed("witness", &self.circuit.name, self.compute_outputs(&inputs)).await;
+        summary::record(&self.summary, |s| {
+            s.record_witness(&self.circuit.name, result.is_ok(), elapsed)
+        });
+
+        result
+    }
+
+    /// Generate a witness and read its output signals
+    async fn compute_outputs(&self, inputs: &CircuitSignals) -> Result<CircuitSignals> {
         let witness = self
             .circomkit
-            .generate_witness(&self.circuit, &inputs)
+            .generate_witness(&self.circuit, inputs)
             .await?;
 
-        // Read the output signals from the witness
-        let outputs = self.read_witness_outputs(&witness.path).await?;
-
-        Ok(outputs)
+        // Read the output signals from the witness, then drop it
+        let outputs = self.read_witness_outputs(&witness.path).await;
+        let _ = fs::remove_file(&witness.path).await;
+        outputs
     }
 
-    /// Test that witness computation fails for the given inputs
+    /// Test that witness computation fails a constraint for the given inputs
+    ///
+    /// Only succeeds when the witness calculator reports a failed assertion
+    /// or unsatisfied constraint. Failures for other reasons, such as
+    /// missing inputs, are returned as errors.
     pub async fn expect_fail(&mut self, inputs: CircuitSignals) -> Result<()> {
+        self.expect_failure_matching(inputs, |message| {
+            CONSTRAINT_FAILURE_MARKERS
+                .iter()
+                .any(|marker| message.contains(marker))
+        })
+        .await
+    }
+
+    /// Test that witness computation fails with a message containing `substring`
+    pub async fn expect_fail_with(
+        &mut self,
+        inputs: CircuitSignals,
+        substring: &str,
+    ) -> Result<()> {
+        self.expect_failure_matching(inputs, |message| message.contains(substring))
+            .await
+    }
+
+    /// Generate a witness expecting a failure whose message satisfies `matches`
+    async fn expect_failure_matching(
+        &mut self,
+        inputs: CircuitSignals,
+        matches: impl Fn(&str) -> bool,
+    ) -> Result<()> {
         self.ensure_compiled().await?;
 
-        let result = self
-            .circomkit
-            .generate_witness(&self.circuit, &inputs)
-            .await;
-
-        match result {
-            Ok(_) => Err(CircomkitError::Other(
-                "Expected witness generation to fail, but it succeeded".to_string(),
-            )),
-            Err(_) => Ok(()),
-        }
+        let (result, elapsed) = summary::timed(
+            "witness",
+            &self.circuit.name,
+            self.circomkit.generate_witness(&self.circuit, &inputs),
+        )
+        .await;
+
+        let outcome = match result {
+            Ok(witness) => {
+                let _ = fs::remove_file(&witness.path).await;
+                Err(CircomkitError::Other(
+                    "Expected witness generation to fail, but it succeeded".to_string(),
+                ))
+            }
+            Err(
+                CircomkitError::WitnessGenerationFailed { message }
+                | CircomkitError::AssertFailed { message, .. },
+            ) if matches(&message) => Ok(()),
+            Err(e) => Err(CircomkitError::Other(format!(
+                "Witness generation failed for an unexpected reason: {}",
+                e
+            ))),
+        };
+
+        summary::record(&self.summary, |s| {
+            s.record_witness(&self.circuit.name, outcome.is_ok(), elapsed)
+        });
+
+        outcome
     }
 
     /// Test that the outputs match expected values
+    ///
+    /// Outputs missing from `expected` are ignored unless the tester was
+    /// created [`with_strict_outputs`](WitnessTester::with_strict_outputs),
+    /// in which case the result fails listing them.
     pub async fn expect_output(
         &mut self,
         inputs: CircuitSignals,
         expected: CircuitSignals,
     ) -> Result<WitnessTestResult> {
-        self.ensure_compiled().await?;
+        self.compare_outputs(inputs, expected, self.strict_outputs)
+            .await
+    }
 
-        let witness = self
-            .circomkit
-            .generate_witness(&self.circuit, &inputs)
+    /// Compare the outputs against `expected`, failing on outputs it
+    /// doesn't list if `strict`
+    async fn compare_outputs(
+        &mut self,
+        inputs: CircuitSignals,
+        expected: CircuitSignals,
+        strict: bool,
+    ) -> Result<WitnessTestResult> {
+        let compile = self.compile_if_needed().await?;
+
+        let (result, elapsed) =
+            summary::timed("witness", &self.circuit.name, self.compute_outputs(&inputs)).await;
+        let outputs = match result {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                summary::record(&self.summary, |s| {
+                    s.record_witness(&self.circuit.name, false, elapsed)
+                });
+                return Err(e);
+            }
+        };
+
+        let unexpected = if strict {
+            self.unexpected_outputs(&outputs, &expected).await?
+        } else {
+            Vec::new()
+        };
+
+        let mut result = WitnessTestResult::compare_within(
+            outputs,
+            expected,
+            &self.tolerances,
+            self.prime(),
+            StageTimings {
+                compile,
+                witness: Some(elapsed),
+                ..Default::default()
+            },
+        );
+        if !unexpected.is_empty() {
+            result.add_error(format!(
+                "Unexpected output signals: {}",
+                unexpected.join(", ")
+            ));
+        }
+        summary::record(&self.summary, |s| {
+            s.record_witness(&self.circuit.name, result.passed, elapsed)
+        });
+
+        Ok(result)
+    }
+
+    /// Test that a single output signal has the expected value
+    ///
+    /// Like [`WitnessTester::expect_output`] for one signal, but fails with
+    /// an error naming the signal and both values. `name` must be among the
+    /// signals read by the tester's [`OutputFilter`].
+    pub async fn expect_signal(
+        &mut self,
+        inputs: CircuitSignals,
+        name: &str,
+        expected: impl Into<SignalValue>,
+    ) -> Result<()> {
+        let expected = expected.into();
+        let result = self
+            .compare_outputs(
+                inputs,
+                CircuitSignals::from([(name.to_string(), expected.clone())]),
+                false,
+            )
             .await?;
-        let outputs = self.read_witness_outputs(&witness.path).await?;
-
-        // Compare outputs with expected
-        let mut passed = true;
-        let mut errors = Vec::new();
-
-        for (name, expected_value) in &expected {
-            if let Some(actual_value) = outputs.get(name) {
-                if !self.compare_signals(actual_value, expected_value) {
-                    passed = false;
-                    errors.push(format!(
-                        "Signal '{}': expected {}, got {}",
+
+        match result.outputs.get(name) {
+            None => Err(CircomkitError::Other(format!(
+                "Signal '{}' not found in outputs",
+                name
+            ))),
+            Some(actual) if !self.compare_signals(name, actual, &expected) => {
+                let delta = match self.tolerances.get(name) {
+                    Some(_) => actual
+                        .field_distance(&expected, self.prime())
+                        .map(|delta| format!(" (off by {})", delta)),
+                    None => None,
+                };
+                Err(CircomkitError::ConstraintNotSatisfied {
+                    expected: format!("{} = {}", name, expected.as_string()),
+                    actual: format!(
+                        "{} = {}{}",
                         name,
-                        expected_value.as_string(),
-                        actual_value.as_string()
-                    ));
-                }
-            } else {
-                passed = false;
-                errors.push(format!("Signal '{}' not found in outputs", name));
+                        actual.as_string(),
+                        delta.unwrap_or_default()
+                    ),
+                })
             }
+            Some(_) => Ok(()),
         }
+    }
 
-        Ok(WitnessTestResult {
-            passed,
-            outputs,
-            expected: Some(expected),
-            error: if errors.is_empty() {
-                None
-            } else {
-                Some(errors.join("; "))
-            },
-        })
+    /// Test the circuit's outputs against a reference implementation
+    ///
+    /// `oracle` computes the expected outputs from the inputs in Rust. Only
+    /// the signals it returns are compared, so it can check a subset of the
+    /// outputs. Pairs well with property-testing input generators.
+    pub async fn check_property<F>(
+        &mut self,
+        inputs: CircuitSignals,
+        oracle: F,
+    ) -> Result<WitnessTestResult>
+    where
+        F: Fn(&CircuitSignals) -> CircuitSignals,
+    {
+        let expected = oracle(&inputs);
+        self.compare_outputs(inputs, expected, false).await
     }
 
     /// Check constraint count
     pub async fn expect_constraint_count(&mut self, expected: usize) -> Result<()> {
-        self.ensure_compiled().await?;
-
-        let info = self.circomkit.info(&self.circuit).await?;
+        let info = self.circuit_info().await?;
 
         if info.constraints != expected {
             return Err(CircomkitError::ConstraintNotSatisfied {
@@ -248,84 +493,263 @@ impl WitnessTester {
         Ok(())
     }
 
-    /// Read output signals from a witness file
-    async fn read_witness_outputs(&self, witness_path: &Path) -> Result<CircuitSignals> {
-        let build_dir = self.circomkit.config().build_path(&self.circuit.name);
-        let sym_path = build_dir.join(format!("{}.sym", self.circuit.name));
+    /// Check constraint count is within `pct` percent of `expected`
+    ///
+    /// Useful for regression tests that should survive small changes in
+    /// circom's optimizer between releases.
+    pub async fn expect_constraint_count_within_pct(
+        &mut self,
+        expected: usize,
+        pct: f64,
+    ) -> Result<()> {
+        let info = self.circuit_info().await?;
 
-        if !sym_path.exists() {
-            return Err(CircomkitError::CircuitNotFound(sym_path));
+        if !within_pct(info.constraints, expected, pct) {
+            return Err(CircomkitError::ConstraintNotSatisfied {
+                expected: format!("{} (±{}%)", expected, pct),
+                actual: info.constraints.to_string(),
+            });
         }
 
-        // Use snarkjs to export witness to json
-        let output_path = build_dir.join("witness.json");
-        let snarkjs = self.circomkit.config().snarkjs_command();
-
-        let wasm_path = build_dir
-            .join(format!("{}_js", self.circuit.name))
-            .join(format!("{}.wasm", self.circuit.name));
-
-        let output = Command::new(&snarkjs)
-            .arg("wtns")
-            .arg("export")
-            .arg("json")
-            .arg(witness_path)
-            .arg(&output_path)
-            .output()
-            .map_err(CircomkitError::Io)?;
-
-        if !output.status.success() {
-            // If export fails, return empty map (some versions don't support this)
-            return Ok(HashMap::new());
+        Ok(())
+    }
+
+    /// Check the circuit's full constraint and signal profile
+    ///
+    /// Fails with an error listing every field of [`CircuitInfo`] that
+    /// differs from `expected`.
+    pub async fn expect_circuit_info(&mut self, expected: CircuitInfo) -> Result<()> {
+        let info = self.circuit_info().await?;
+
+        let differences = info_differences(&info, &expected);
+        if !differences.is_empty() {
+            return Err(CircomkitError::Other(format!(
+                "Circuit info mismatch: {}",
+                differences.join("; ")
+            )));
         }
 
-        // Parse the witness JSON
-        let content = fs::read_to_string(&output_path).await?;
-        let witness_array: Vec<String> = serde_json::from_str(&content)?;
-
-        // Read symbol file to map indices to signal names
-        let sym_content = fs::read_to_string(&sym_path).await?;
-        let mut signals = HashMap::new();
-
-        for line in sym_content.lines() {
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() >= 4 {
-                let idx: usize = parts[0].parse().unwrap_or(0);
-                let name = parts[3].to_string();
-
-                // Only include output signals (those starting with "main.")
-                if name.starts_with("main.") && idx < witness_array.len() {
-                    let signal_name = name.strip_prefix("main.").unwrap_or(&name).to_string();
-                    signals.insert(signal_name, SignalValue::Single(witness_array[idx].clone()));
-                }
-            }
+        Ok(())
+    }
+
+    /// Check constraint count is within `min..=max`
+    pub async fn expect_constraints_within(&mut self, min: usize, max: usize) -> Result<()> {
+        let info = self.circuit_info().await?;
+
+        if info.constraints < min || info.constraints > max {
+            return Err(CircomkitError::ConstraintNotSatisfied {
+                expected: format!("between {} and {}", min, max),
+                actual: info.constraints.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check the constraint count hasn't grown past `baseline` by more than
+    /// `tolerance` constraints
+    ///
+    /// Fewer constraints always pass, so this works as a CI gate against
+    /// accidental blowups while leaving room for optimizations.
+    pub async fn expect_constraints_not_regressed(
+        &mut self,
+        baseline: &CircuitInfo,
+        tolerance: usize,
+    ) -> Result<()> {
+        let info = self.circuit_info().await?;
+        let delta = InfoDelta::between(baseline, &info);
+
+        if delta.constraints > tolerance as i64 {
+            return Err(CircomkitError::ConstraintNotSatisfied {
+                expected: format!("at most {}", baseline.constraints + tolerance),
+                actual: format!("{} (+{})", info.constraints, delta.constraints),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check that circom's `--inspect` reports no unconstrained signals
+    ///
+    /// Recompiles the circuit with `--inspect` and fails with the warnings
+    /// for every signal that doesn't appear in any constraint. Such signals
+    /// can usually be set freely by a prover, so this is worth running on
+    /// any circuit that guards a proof.
+    pub async fn expect_no_unconstrained_signals(&mut self) -> Result<()> {
+        let config = self.circomkit.config().clone().with_inspect(true);
+        let inspector = self.circomkit.reconfigured(config)?;
+
+        let (result, elapsed) = summary::timed(
+            "compile",
+            &self.circuit.name,
+            inspector.compile(&self.circuit),
+        )
+        .await;
+        summary::record(&self.summary, |s| {
+            s.record_compile(&self.circuit.name, result.is_ok(), elapsed)
+        });
+        let artifacts = result?;
+        self.compiled = true;
+
+        let unconstrained = unconstrained_warnings(&artifacts.warnings);
+        if !unconstrained.is_empty() {
+            return Err(CircomkitError::Other(format!(
+                "Unconstrained signals found: {}",
+                unconstrained.join("; ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Compile the circuit and read its info
+    async fn circuit_info(&mut self) -> Result<CircuitInfo> {
+        self.ensure_compiled().await?;
+
+        let info = self.circomkit.info(&self.circuit).await?;
+        summary::record(&self.summary, |s| {
+            s.record_constraints(&self.circuit.name, info.constraints)
+        });
+
+        Ok(info)
+    }
+
+    /// Read the signals selected by the output filter from a witness file
+    async fn read_witness_outputs(&self, witness_path: &Path) -> Result<CircuitSignals> {
+        let mut signals = self
+            .circomkit
+            .witness_to_signals(&self.circuit, witness_path)
+            .await?;
+        if signals.is_empty() {
+            return Ok(signals);
         }
 
+        let (symbols, header) = self.symbols_and_header().await?;
+
+        let selected: HashSet<&str> = self
+            .output_filter
+            .select(&symbols, &header, self.circuit.main_name())
+            .into_iter()
+            .map(Symbol::signal_name)
+            .collect();
+        signals.retain(|name, _| selected.contains(name.as_str()));
+
         Ok(signals)
     }
 
-    /// Compare two signal values for equality
-    fn compare_signals(&self, actual: &SignalValue, expected: &SignalValue) -> bool {
-        match (actual, expected) {
-            (SignalValue::Single(a), SignalValue::Single(e)) => a == e,
-            (SignalValue::Number(a), SignalValue::Number(e)) => a == e,
-            (SignalValue::Single(a), SignalValue::Number(e)) => {
-                a.parse::<i64>().map(|n| n == *e).unwrap_or(false)
-            }
-            (SignalValue::Number(a), SignalValue::Single(e)) => {
-                e.parse::<i64>().map(|n| n == *a).unwrap_or(false)
-            }
-            (SignalValue::Array(a), SignalValue::Array(e)) => {
-                a.len() == e.len()
-                    && a.iter()
-                        .zip(e.iter())
-                        .all(|(av, ev)| self.compare_signals(av, ev))
-            }
-            _ => false,
+    /// Read the compiled circuit's symbol table and r1cs header, failing if
+    /// the configuration doesn't emit them
+    async fn symbols_and_header(&self) -> Result<(SymbolTable, R1csHeader)> {
+        let config = self.circomkit.config();
+        let build_dir = config.build_path(&self.circuit.name);
+        let sym_path = build_dir.join(format!("{}.sym", self.circuit.name));
+        let r1cs_path = build_dir.join(format!("{}.r1cs", self.circuit.name));
+        self.circomkit
+            .require_artifact(&sym_path, config.emit_sym, "emit_sym")?;
+        self.circomkit
+            .require_artifact(&r1cs_path, config.emit_r1cs, "emit_r1cs")?;
+
+        Ok((
+            read_symbol_table(&sym_path).await?,
+            read_r1cs_header(&r1cs_path).await?,
+        ))
+    }
+
+    /// Outputs of the main component in `outputs` that `expected` lists
+    /// neither by name nor by array name, sorted
+    async fn unexpected_outputs(
+        &self,
+        outputs: &CircuitSignals,
+        expected: &CircuitSignals,
+    ) -> Result<Vec<String>> {
+        if outputs.is_empty() {
+            return Ok(Vec::new());
         }
+
+        let (symbols, header) = self.symbols_and_header().await?;
+
+        let mut unexpected: Vec<String> = symbols
+            .outputs(&header)
+            .into_iter()
+            .filter(|symbol| outputs.contains_key(symbol.signal_name()))
+            .filter(|symbol| {
+                !expected.contains_key(symbol.signal_name())
+                    && !expected.contains_key(symbol.base_name_and_indices().0)
+            })
+            .map(|symbol| symbol.signal_name().to_string())
+            .collect();
+        unexpected.sort();
+        Ok(unexpected)
+    }
+
+    /// Compare two values of the signal `name`, allowing its tolerance
+    fn compare_signals(&self, name: &str, actual: &SignalValue, expected: &SignalValue) -> bool {
+        actual.matches(expected)
+            || self.tolerances.get(name).is_some_and(|tolerance| {
+                actual
+                    .field_distance(expected, self.prime())
+                    .is_some_and(|delta| delta <= *tolerance)
+            })
+    }
+
+    /// Prime field the circuit is compiled over
+    fn prime(&self) -> Prime {
+        self.circuit.prime.unwrap_or(self.circomkit.config().prime)
     }
 }
 
+impl Drop for WitnessTester {
+    fn drop(&mut self) {
+        if let Some(dir) = self.cleanup_dir.take() {
+            cleanup::remove_build_dir(dir);
+        }
+    }
+}
+
+/// Compiler warnings that report under-constrained signals
+fn unconstrained_warnings(warnings: &[String]) -> Vec<&str> {
+    warnings
+        .iter()
+        .map(String::as_str)
+        .filter(|w| UNCONSTRAINED_MARKERS.iter().any(|m| w.contains(m)))
+        .collect()
+}
+
+/// Check whether `actual` is within `pct` percent of `expected`
+fn within_pct(actual: usize, expected: usize, pct: f64) -> bool {
+    let tolerance = expected as f64 * pct.abs() / 100.0;
+    (actual as f64 - expected as f64).abs() <= tolerance
+}
+
+/// List the fields that differ between two circuit infos
+fn info_differences(actual: &CircuitInfo, expected: &CircuitInfo) -> Vec<String> {
+    let fields = [
+        ("constraints", actual.constraints, expected.constraints),
+        (
+            "private_inputs",
+            actual.private_inputs,
+            expected.private_inputs,
+        ),
+        (
+            "public_inputs",
+            actual.public_inputs,
+            expected.public_inputs,
+        ),
+        (
+            "public_outputs",
+            actual.public_outputs,
+            expected.public_outputs,
+        ),
+        ("labels", actual.labels, expected.labels),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, actual, expected)| actual != expected)
+        .map(|(name, actual, expected)| format!("{}: expected {}, got {}", name, expected, actual))
+        .collect()
+}
+
 /// Macro for convenient witness testing with file path
 #[macro_export]
 macro_rules! witness_test {
@@ -339,9 +763,74 @@ macro_rules! witness_test {
     }};
 }
 
+/// Compile a circuit, compute its witness, and panic unless the outputs match
+///
+/// Takes a [`CircuitConfig`], optionally a [`CircomkitConfig`] (the default
+/// configuration file is used otherwise), the inputs, and the expected
+/// outputs. Failures panic with the circuit's name and, for mismatched
+/// outputs, the [`WitnessTestResult::format_diff`] table. Evaluates to the
+/// passing [`WitnessTestResult`].
+///
+/// # Example
+/// ```rust,ignore
+/// #[tokio::test]
+/// async fn multiplier() {
+///     let circuit = CircuitConfig::new("multiplier").with_params(vec![2]);
+///     assert_circuit!(circuit, signals! { "in" => vec![2, 3] }, signals! { "out" => 6 });
+/// }
+/// ```
+#[macro_export]
+macro_rules! assert_circuit {
+    ($circuit:expr, $inputs:expr, $expected:expr $(,)?) => {{
+        let circuit: $crate::CircuitConfig = $circuit;
+        let config = $crate::CircomkitConfig::from_default_file_async()
+            .await
+            .unwrap_or_else(|e| panic!("circuit '{}': {}", circuit.name, e));
+        $crate::assert_circuit!(circuit, config, $inputs, $expected)
+    }};
+    ($circuit:expr, $config:expr, $inputs:expr, $expected:expr $(,)?) => {{
+        let circuit: $crate::CircuitConfig = $circuit;
+        let name = circuit.name.clone();
+        let mut tester = $crate::WitnessTester::from_circuit_config_with_settings(circuit, $config)
+            .await
+            .unwrap_or_else(|e| panic!("circuit '{}': {}", name, e));
+        let result = tester
+            .expect_output($inputs, $expected)
+            .await
+            .unwrap_or_else(|e| panic!("circuit '{}': {}", name, e));
+        if !result.passed {
+            panic!(
+                "circuit '{}': {}\n{}",
+                name,
+                result.error.as_deref().unwrap_or("outputs differ"),
+                result.format_diff()
+            );
+        }
+        result
+    }};
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::{MockBackend, Stage};
+    use crate::signals;
+    use crate::types::CircuitArtifacts;
+    use crate::utils::r1cs_bytes;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    #[should_panic(expected = "circuit 'missing': ")]
+    async fn test_assert_circuit_panics() {
+        let dir = tempfile::tempdir().unwrap();
+        let circuit = CircuitConfig::new("missing").with_absolute_file(dir.path().join("x.circom"));
+        crate::assert_circuit!(
+            circuit,
+            CircomkitConfig::default().with_build_dir(dir.path()),
+            signals! { "in" => 1 },
+            signals! { "out" => 1 },
+        );
+    }
 
     #[test]
     fn test_signal_comparison() {
@@ -349,16 +838,493 @@ mod tests {
             circomkit: Circomkit::with_defaults().unwrap(),
             circuit: CircuitConfig::new("test"),
             compiled: false,
+            summary: None,
+            output_filter: OutputFilter::default(),
+            strict_outputs: false,
+            tolerances: HashMap::new(),
+            cleanup_dir: None,
+        };
+
+        assert!(tester.compare_signals(
+            "out",
+            &SignalValue::Single("42".into()),
+            &SignalValue::Number(42)
+        ));
+        assert!(tester.compare_signals(
+            "out",
+            &SignalValue::Number(42),
+            &SignalValue::Single("42".into())
+        ));
+        assert!(!tester.compare_signals(
+            "out",
+            &SignalValue::Single("42".into()),
+            &SignalValue::Number(43)
+        ));
+    }
+
+    /// Tester whose "compiled" circuit runs a stub witness calculator script
+    fn stub_tester(script: &str) -> (tempfile::TempDir, WitnessTester) {
+        let dir = tempfile::tempdir().unwrap();
+        let js_dir = dir.path().join("stub").join("stub_js");
+        std::fs::create_dir_all(&js_dir).unwrap();
+        std::fs::write(js_dir.join("stub.wasm"), b"").unwrap();
+        std::fs::write(js_dir.join("generate_witness.js"), script).unwrap();
+
+        let config = CircomkitConfig::default().with_build_dir(dir.path());
+        let tester = WitnessTester {
+            circomkit: Circomkit::new(config).unwrap(),
+            circuit: CircuitConfig::new("stub"),
+            compiled: true,
+            summary: None,
+            output_filter: OutputFilter::default(),
+            strict_outputs: false,
+            tolerances: HashMap::new(),
+            cleanup_dir: None,
+        };
+        (dir, tester)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_expect_signal() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (dir, mut tester) =
+            stub_tester(r#"require("fs").copyFileSync(process.argv[3], process.argv[4]);"#);
+        let build = dir.path().join("stub");
+        std::fs::write(build.join("stub.sym"), "1,1,0,main.out\n2,2,0,main.in\n").unwrap();
+
+        std::fs::write(build.join("stub.r1cs"), r1cs_bytes(1, 0, 1, 1)).unwrap();
+
+        // snarkjs exporting the witness `[1, out = 6, in = 3]`
+        let snarkjs = dir.path().join("snarkjs");
+        std::fs::write(&snarkjs, "#!/bin/sh\necho '[\"1\",\"6\",\"3\"]' > \"$5\"\n").unwrap();
+        std::fs::set_permissions(&snarkjs, std::fs::Permissions::from_mode(0o755)).unwrap();
+        tester.circomkit = Circomkit::new(
+            CircomkitConfig::default()
+                .with_build_dir(dir.path())
+                .with_snarkjs_path(snarkjs),
+        )
+        .unwrap();
+
+        tester
+            .expect_signal(signals! { "in" => 3 }, "out", 6)
+            .await
+            .unwrap();
+        // Hex inputs and expectations compare by value
+        tester
+            .expect_signal(signals! { "in" => "0x3" }, "out", "0x6")
+            .await
+            .unwrap();
+
+        match tester
+            .expect_signal(signals! { "in" => 3 }, "out", "5")
+            .await
+        {
+            Err(CircomkitError::ConstraintNotSatisfied { expected, actual }) => {
+                assert_eq!(expected, "out = 5");
+                assert_eq!(actual, "out = 6");
+            }
+            other => panic!("Expected ConstraintNotSatisfied, got {:?}", other),
+        }
+
+        // Inputs aren't outputs
+        let err = tester
+            .expect_signal(signals! { "in" => 3 }, "in", 3)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'in' not found"));
+
+        // Already compiled by the stub, so only the witness stage is timed
+        let result = tester
+            .expect_output(signals! { "in" => 3 }, signals! { "out" => 6 })
+            .await
+            .unwrap();
+        assert_eq!(result.durations.compile, None);
+        assert!(result.durations.witness.is_some());
+        assert_eq!(result.durations.total(), result.durations.witness.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_strict_outputs() {
+        let (dir, mut tester) = stub_tester("");
+        let build = dir.path().join("stub");
+        std::fs::write(
+            build.join("stub.sym"),
+            "1,1,0,main.out\n2,2,0,main.extra[0]\n3,3,0,main.extra[1]\n4,4,0,main.in\n",
+        )
+        .unwrap();
+        std::fs::write(build.join("stub.r1cs"), r1cs_bytes(3, 0, 1, 1)).unwrap();
+
+        // The witness `[1, out = 6, extra = [7, 8], in = 3]`
+        let backend = MockBackend::new().with_file(
+            Stage::ExportWitness,
+            ".json",
+            r#"["1", "6", "7", "8", "3"]"#,
+        );
+        tester.circomkit = Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path()))
+            .unwrap()
+            .with_backend(backend);
+
+        // Lenient by default
+        let result = tester
+            .expect_output(signals! { "in" => 3 }, signals! { "out" => 6 })
+            .await
+            .unwrap();
+        assert!(result.passed);
+
+        let mut tester = tester.with_strict_outputs(true);
+        let result = tester
+            .expect_output(signals! { "in" => 3 }, signals! { "out" => 5 })
+            .await
+            .unwrap();
+        assert!(!result.passed);
+        assert_eq!(
+            result.error.as_deref(),
+            Some(
+                "Signal 'out': expected 5, got 6; \
+                 Unexpected output signals: extra[0], extra[1]"
+            )
+        );
+
+        // Elements or the whole array cover the extra outputs
+        let expected = signals! { "out" => 6, "extra[0]" => 7, "extra[1]" => 8 };
+        let result = tester
+            .expect_output(signals! { "in" => 3 }, expected)
+            .await
+            .unwrap();
+        assert!(result.passed, "{:?}", result.error);
+        let result = tester
+            .expect_output(
+                signals! { "in" => 3 },
+                signals! { "out" => 6, "extra" => vec![7, 8] },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Signal 'extra' not found in outputs")
+        );
+
+        // Single-signal checks stay lenient
+        tester
+            .expect_signal(signals! { "in" => 3 }, "out", 6)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tolerance() {
+        let (dir, tester) = stub_tester("");
+        let build = dir.path().join("stub");
+        std::fs::write(build.join("stub.sym"), "1,1,0,main.out\n2,2,0,main.in\n").unwrap();
+        std::fs::write(build.join("stub.r1cs"), r1cs_bytes(1, 0, 1, 1)).unwrap();
+
+        // The witness `[1, out = p - 1, in = 3]`, one below zero
+        let minus_one = (Prime::Bn128.modulus() - 1u32).to_string();
+        let backend = MockBackend::new().with_file(
+            Stage::ExportWitness,
+            ".json",
+            format!(r#"["1", "{}", "3"]"#, minus_one),
+        );
+        let mut tester = tester.with_tolerance("out", 1u32);
+        tester.circomkit = Circomkit::new(CircomkitConfig::default().with_build_dir(dir.path()))
+            .unwrap()
+            .with_backend(backend);
+
+        for expected in [0, -2] {
+            let result = tester
+                .expect_output(signals! { "in" => 3 }, signals! { "out" => expected })
+                .await
+                .unwrap();
+            assert!(result.passed, "{:?}", result.error);
+        }
+        tester
+            .expect_signal(signals! { "in" => 3 }, "out", 0)
+            .await
+            .unwrap();
+
+        let result = tester
+            .expect_output(signals! { "in" => 3 }, signals! { "out" => 2 })
+            .await
+            .unwrap();
+        assert_eq!(
+            result.error,
+            Some(format!(
+                "Signal 'out': expected 2, got {} (off by 3, tolerance 1)",
+                minus_one
+            ))
+        );
+        match tester.expect_signal(signals! { "in" => 3 }, "out", 2).await {
+            Err(CircomkitError::ConstraintNotSatisfied { actual, .. }) => {
+                assert_eq!(actual, format!("out = {} (off by 3)", minus_one));
+            }
+            other => panic!("Expected ConstraintNotSatisfied, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_read_witness_outputs_export_failure() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("stub");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(build.join("stub.sym"), "1,1,0,main.out\n").unwrap();
+
+        // Tester whose snarkjs is a shell script
+        let tester_with_snarkjs = |script: &str| {
+            let path = dir.path().join("snarkjs");
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            let config = CircomkitConfig::default()
+                .with_build_dir(dir.path())
+                .with_snarkjs_path(path);
+            WitnessTester {
+                circomkit: Circomkit::new(config).unwrap(),
+                circuit: CircuitConfig::new("stub"),
+                compiled: true,
+                summary: None,
+                output_filter: OutputFilter::default(),
+                strict_outputs: false,
+                tolerances: HashMap::new(),
+                cleanup_dir: None,
+            }
         };
 
+        let witness = build.join("witness.wtns");
+        let broken = tester_with_snarkjs("#!/bin/sh\necho 'Invalid witness length' >&2\nexit 1\n");
+        match broken.read_witness_outputs(&witness).await {
+            Err(CircomkitError::WitnessGenerationFailed { message }) => {
+                assert!(message.contains("Invalid witness length"))
+            }
+            other => panic!("Expected WitnessGenerationFailed, got {:?}", other),
+        }
+
+        let old = tester_with_snarkjs("#!/bin/sh\necho 'Invalid command'\nexit 1\n");
+        assert!(old.read_witness_outputs(&witness).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_manifest_requires_fresh_build() {
+        let (dir, mut tester) = stub_tester("");
+        std::fs::write(dir.path().join("stub.circom"), "template Stub() {}").unwrap();
+        tester.circuit = tester.circuit.clone().with_template("Stub");
+        tester.compiled = false;
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(dir.path())
+            .with_build_dir(dir.path());
+
+        // A manifest listing existing files doesn't stand in for a build
+        let build = dir.path().join("stub");
+        let artifacts = CircuitArtifacts {
+            r1cs: build.join("stub.r1cs"),
+            wasm: build.join("stub_js/stub.wasm"),
+            sym: build.join("stub.sym"),
+            pkey: None,
+            vkey: None,
+            warnings: Vec::new(),
+        };
+        std::fs::write(&artifacts.r1cs, b"").unwrap();
+        std::fs::write(&artifacts.sym, b"").unwrap();
+        tester.circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(MockBackend::new().with_failure(Stage::Compile, "no circom"));
+        tester
+            .circomkit
+            .save_artifacts(&tester.circuit, &artifacts)
+            .await
+            .unwrap();
+        assert!(tester.ensure_compiled().await.is_err());
+
+        // Once compiled, later testers skip compiling
+        let backend = Arc::new(MockBackend::new());
+        tester.circomkit = Circomkit::new(config.clone())
+            .unwrap()
+            .with_backend(backend.clone());
+        tester.ensure_compiled().await.unwrap();
+        tester.compiled = false;
+        tester.ensure_compiled().await.unwrap();
+        assert!(tester.compiled);
+        assert_eq!(backend.calls(), [Stage::Compile]);
+
+        // But not after the source changes
+        let later = std::time::SystemTime::now() + Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(dir.path().join("stub.circom"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        tester.compiled = false;
+        tester.ensure_compiled().await.unwrap();
+        assert_eq!(backend.calls(), [Stage::Compile, Stage::Compile]);
+    }
+
+    #[tokio::test]
+    async fn test_unconstrained_signals_use_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("stub.circom"), "template Stub() {}").unwrap();
+        let config = CircomkitConfig::default()
+            .with_circuits_dir(dir.path())
+            .with_build_dir(dir.path());
+        let circuit = CircuitConfig::new("stub").with_template("Stub");
+
+        // The inspecting compile runs through the tester's backend
+        let backend = Arc::new(MockBackend::new().with_stdout(
+            Stage::Compile,
+            "warning[CA01]: In template \"Stub\": Local signal x does not appear in any constraint",
+        ));
+        let circomkit = Circomkit::new(config)
+            .unwrap()
+            .with_backend(backend.clone());
+        let mut tester = WitnessTester::from_circomkit(circuit, circomkit)
+            .await
+            .unwrap();
+
+        let err = tester.expect_no_unconstrained_signals().await.unwrap_err();
+        assert!(err.to_string().contains("Local signal x"));
+        assert_eq!(backend.calls(), [Stage::Compile]);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_on_drop() {
+        let (dir, tester) = stub_tester("");
+        let build = dir.path().join("stub");
+
+        // Artifacts are kept by default
+        drop(tester);
+        assert!(build.exists());
+
+        let (dir, tester) = stub_tester("");
+        let build = dir.path().join("stub");
+        drop(tester.cleanup_on_drop(true));
+        // Removal runs on the blocking pool
+        for _ in 0..100 {
+            if !build.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!build.exists());
+    }
+
+    #[test]
+    fn test_cleanup_on_drop_outside_runtime() {
+        let (dir, tester) = stub_tester("");
+        drop(tester.cleanup_on_drop(true));
+        assert!(!dir.path().join("stub").exists());
+    }
+
+    #[tokio::test]
+    async fn test_expect_fail_requires_constraint_failure() {
+        let (_dir, mut tester) = stub_tester(
+            r#"
+            const input = JSON.parse(require("fs").readFileSync(process.argv[3]));
+            if (input.a === undefined) {
+                console.error("Error: Not all inputs have been set. Only 0 out of 1");
+            } else {
+                console.error("Error: Assert Failed.\nError in template ForceEqual_0 line: 6");
+            }
+            process.exit(1);
+            "#,
+        );
+
+        assert!(tester.expect_fail(signals! { "a" => 1 }).await.is_ok());
         assert!(
-            tester.compare_signals(&SignalValue::Single("42".into()), &SignalValue::Number(42))
+            tester
+                .expect_fail_with(signals! { "a" => 1 }, "ForceEqual_0")
+                .await
+                .is_ok()
         );
         assert!(
-            tester.compare_signals(&SignalValue::Number(42), &SignalValue::Single("42".into()))
+            tester
+                .expect_fail_with(signals! { "a" => 1 }, "IsZero")
+                .await
+                .is_err()
         );
+        assert!(tester.expect_fail(CircuitSignals::new()).await.is_err());
+    }
+
+    #[test]
+    fn test_info_differences() {
+        let expected = CircuitInfo {
+            constraints: 64,
+            private_inputs: 1,
+            public_inputs: 0,
+            public_outputs: 0,
+            labels: 67,
+        };
+        assert!(info_differences(&expected, &expected).is_empty());
+
+        let actual = CircuitInfo {
+            constraints: 65,
+            public_outputs: 1,
+            ..expected.clone()
+        };
+        assert_eq!(
+            info_differences(&actual, &expected),
+            vec![
+                "constraints: expected 64, got 65",
+                "public_outputs: expected 0, got 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unconstrained_warnings() {
+        let warnings = vec![
+            "warning[CA01]: In template \"A\": Local signal x does not appear in any constraint"
+                .to_string(),
+            "warning[CA10]: In template \"A\": Variable i is not used".to_string(),
+        ];
+
+        assert_eq!(unconstrained_warnings(&warnings), [warnings[0].as_str()]);
+        assert!(unconstrained_warnings(&warnings[1..]).is_empty());
+    }
+
+    #[test]
+    fn test_output_filter() {
+        let symbols = SymbolTable::parse(
+            "1,1,0,main.out\n2,2,0,main.pub\n3,3,0,main.secret\n4,4,1,main.sub.x\n",
+        )
+        .unwrap();
+        let header = R1csHeader {
+            field_size: 32,
+            prime: Default::default(),
+            n_wires: 5,
+            n_pub_out: 1,
+            n_pub_in: 1,
+            n_prv_in: 1,
+            n_labels: 4,
+            n_constraints: 1,
+        };
+        let names = |filter: OutputFilter| {
+            filter
+                .select(&symbols, &header, "main")
+                .iter()
+                .map(|s| s.signal_name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(OutputFilter::default(), OutputFilter::OutputsOnly);
+        assert_eq!(names(OutputFilter::OutputsOnly), ["out"]);
+        assert_eq!(names(OutputFilter::PublicAndOutputs), ["out", "pub"]);
+        assert_eq!(names(OutputFilter::All), ["out", "pub", "secret", "sub.x"]);
         assert!(
-            !tester.compare_signals(&SignalValue::Single("42".into()), &SignalValue::Number(43))
+            OutputFilter::All
+                .select(&symbols, &header, "top")
+                .is_empty()
         );
     }
+
+    #[test]
+    fn test_within_pct() {
+        assert!(within_pct(100, 100, 0.0));
+        assert!(within_pct(110, 100, 10.0));
+        assert!(within_pct(90, 100, 10.0));
+        assert!(!within_pct(111, 100, 10.0));
+        assert!(!within_pct(89, 100, 10.0));
+    }
 }
@@ -2,7 +2,7 @@
 //!
 //! This module contains circuit code as string constants for testing.
 
-/// TODO: Circuits with 2D array inputs to be added.
+// TODO: Circuits with 2D array inputs to be added.
 
 /// Simple adder circuit
 pub const ADDER: &str = r#"
@@ -108,7 +108,7 @@ template Mux1() {
 pub const RANGE_CHECK_8: &str = r#"
 pragma circom 2.0.0;
 
-include "../node_modules/circomlib/circuits/bitify.circom";
+include "circomlib/circuits/bitify.circom";
 
 template RangeCheck(n) {
     signal input in;
@@ -121,7 +121,7 @@ template RangeCheck(n) {
 pub const RANGE_CHECK_64: &str = r#"
 pragma circom 2.0.0;
 
-include "../node_modules/circomlib/circuits/bitify.circom";
+include "circomlib/circuits/bitify.circom";
 
 template RangeCheck64() {
     signal input in;
@@ -129,3 +129,16 @@ template RangeCheck64() {
     bits.in <== in;
 }
 "#;
+
+/// Range check circuit including circomlib by package path
+pub const RANGE_CHECK_CIRCOMLIB: &str = r#"
+pragma circom 2.0.0;
+
+include "circomlib/circuits/bitify.circom";
+
+template RangeCheckLib(n) {
+    signal input in;
+    component bits = Num2Bits(n);
+    bits.in <== in;
+}
+"#;
@@ -1,9 +1,22 @@
+//! Tests against real circuits
+//!
+//! These run circom, and snarkjs or circomlib where needed, so they are
+//! ignored by default; run them with `cargo test -- --ignored` where the
+//! toolchain is installed.
+
 mod circuits;
 mod testing;
 
-use testing::{CircuitTester, inputs};
+use crate::core::{Circomkit, CircomkitConfig};
+use crate::error::CircomkitError;
+use crate::types::{CircuitConfig, CircuitSignals, Prime, Protocol};
+use crate::utils::{generate_ptau, get_recommended_ptau};
+use std::path::Path;
+use std::time::Instant;
+use testing::{CircuitTester, TEST_BUILD_DIR, TEST_CIRCUITS_DIR, inputs};
 
 #[test]
+#[ignore = "requires circom"]
 fn test_mock_adder() {
     let tester = CircuitTester::new();
     let result = tester.test_circuit(
@@ -16,6 +29,7 @@ fn test_mock_adder() {
 }
 
 #[test]
+#[ignore = "requires circom"]
 fn test_mock_multiplier() {
     let tester = CircuitTester::new();
     let result = tester.test_circuit(
@@ -28,6 +42,7 @@ fn test_mock_multiplier() {
 }
 
 #[test]
+#[ignore = "requires circom"]
 fn test_mock_multiplier_array() {
     let tester = CircuitTester::new();
     let result = tester.test_circuit(
@@ -40,6 +55,27 @@ fn test_mock_multiplier_array() {
 }
 
 #[test]
+#[ignore = "requires circom"]
+fn test_mock_multiplier_property() {
+    let tester = CircuitTester::new();
+
+    let result = tester.test_circuit_property(
+        "Multiplier",
+        circuits::MULTIPLIER,
+        vec![],
+        inputs(&[("a", vec!["6"]), ("b", vec!["7"])]),
+        |signals| {
+            let value = |name: &str| signals[name].as_string().parse::<i64>().unwrap();
+            let mut expected = CircuitSignals::new();
+            expected.insert("product".to_string(), (value("a") * value("b")).into());
+            expected
+        },
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+#[ignore = "requires circom"]
 fn test_mock_is_zero() {
     let tester = CircuitTester::new();
 
@@ -62,6 +98,7 @@ fn test_mock_is_zero() {
 }
 
 #[test]
+#[ignore = "requires circom"]
 fn test_mock_is_equal() {
     let tester = CircuitTester::new();
 
@@ -85,6 +122,7 @@ fn test_mock_is_equal() {
 }
 
 #[test]
+#[ignore = "requires circom"]
 fn test_mock_force_equal() {
     let tester = CircuitTester::new();
 
@@ -108,6 +146,30 @@ fn test_mock_force_equal() {
 }
 
 #[test]
+#[ignore = "requires circom"]
+fn test_mock_force_equal_assert_location() {
+    let tester = CircuitTester::new();
+
+    let err = tester
+        .witness_error(
+            "ForceEqual",
+            circuits::FORCE_EQUAL,
+            vec![],
+            inputs(&[("a", vec!["42"]), ("b", vec!["43"])]),
+        )
+        .unwrap();
+
+    match err {
+        CircomkitError::AssertFailed { template, line, .. } => {
+            assert_eq!(template.as_deref(), Some("ForceEqual"));
+            assert_eq!(line, Some(7));
+        }
+        other => panic!("Expected AssertFailed, got {:?}", other),
+    }
+}
+
+#[test]
+#[ignore = "requires circom"]
 fn test_mock_mux1() {
     let tester = CircuitTester::new();
 
@@ -129,6 +191,7 @@ fn test_mock_mux1() {
 }
 
 #[test]
+#[ignore = "requires circom and circomlib"]
 fn test_mock_range_check_8bit() {
     let tester = CircuitTester::new();
 
@@ -152,6 +215,22 @@ fn test_mock_range_check_8bit() {
 }
 
 #[test]
+#[ignore = "requires circomlib"]
+fn test_mock_range_check_flatten_source() {
+    let tester = CircuitTester::new();
+
+    let flat = tester
+        .flatten_source("RangeCheck", circuits::RANGE_CHECK_8, vec![8])
+        .unwrap();
+
+    assert!(flat.contains("template Num2Bits(n)"));
+    assert!(flat.contains("template RangeCheck(n)"));
+    assert!(!flat.lines().any(|l| l.trim_start().starts_with("include")));
+    assert_eq!(flat.matches("pragma circom").count(), 1);
+}
+
+#[test]
+#[ignore = "requires circom and circomlib"]
 fn test_mock_range_check_64bit() {
     let tester = CircuitTester::new();
     let max_u64 = "18446744073709551615";
@@ -164,3 +243,90 @@ fn test_mock_range_check_64bit() {
     );
     assert!(result.is_ok());
 }
+
+#[test]
+#[ignore = "requires circom and circomlib"]
+fn test_mock_range_check_64bit_constraint_count() {
+    let tester = CircuitTester::new();
+
+    // Num2Bits(64): one booleanity constraint per bit
+    let result = tester.test_constraint_count_within_pct(
+        "RangeCheck64",
+        circuits::RANGE_CHECK_64,
+        vec![],
+        64,
+        10.0,
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+#[ignore = "requires network access to fetch circomlib"]
+fn test_mock_circomlib_git() {
+    let config = CircomkitConfig::new()
+        .with_circuits_dir(TEST_CIRCUITS_DIR)
+        .with_build_dir(TEST_BUILD_DIR)
+        .with_circomlib_git("https://github.com/iden3/circomlib", "v2.0.5");
+    let tester = CircuitTester::from_config(config);
+
+    let result = tester.test_circuit(
+        "RangeCheckLib",
+        circuits::RANGE_CHECK_CIRCOMLIB,
+        vec![8],
+        inputs(&[("in", vec!["200"])]),
+    );
+    assert!(result.is_ok());
+}
+
+/// Reports the speedup of `verify_batch` over a loop of `verify`, which starts snarkjs
+/// per proof. PLONK keeps the batch on the node path even with
+/// `native-verify`. Run with `cargo test --release test_mock_verify_batch_speedup
+/// -- --ignored --nocapture`.
+#[test]
+#[ignore = "benchmark; requires circom and snarkjs"]
+fn test_mock_verify_batch_speedup() {
+    const PROOFS: usize = 20;
+
+    let rt = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+    rt.block_on(async {
+        let config = CircomkitConfig::new()
+            .with_circuits_dir(TEST_CIRCUITS_DIR)
+            .with_build_dir(TEST_BUILD_DIR)
+            .with_protocol(Protocol::Plonk);
+        let circomkit = Circomkit::new(config.clone()).unwrap();
+        let circuit = CircuitConfig::new("MultiplierBatch")
+            .with_file("Multiplier.circom")
+            .with_template("Multiplier");
+
+        circomkit.compile(&circuit).await.unwrap();
+        let info = circomkit.info(&circuit).await.unwrap();
+        let ptau = get_recommended_ptau(info.constraints, Prime::Bn128).unwrap();
+        let ptau = generate_ptau(&ptau, Path::new(TEST_BUILD_DIR), &config)
+            .await
+            .unwrap();
+        circomkit.setup(&circuit, &ptau).await.unwrap();
+
+        let inputs = crate::signals! { "a" => 6, "b" => 7 };
+        let item = circomkit.prove(&circuit, &inputs).await.unwrap();
+        let items = vec![item; PROOFS];
+
+        let start = Instant::now();
+        for (proof, public) in &items {
+            assert!(circomkit.verify(&circuit, proof, public).await.unwrap());
+        }
+        let looped = start.elapsed();
+
+        let start = Instant::now();
+        let results = circomkit.verify_batch(&circuit, &items).await.unwrap();
+        let batched = start.elapsed();
+
+        assert_eq!(results, vec![true; PROOFS]);
+        println!(
+            "{} proofs: verify loop {:?}, verify_batch {:?} ({:.1}x)",
+            PROOFS,
+            looped,
+            batched,
+            looped.as_secs_f64() / batched.as_secs_f64()
+        );
+    });
+}
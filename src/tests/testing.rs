@@ -1,6 +1,7 @@
 //! Circuit testing utilities
 
 use crate::core::{Circomkit, CircomkitConfig};
+use crate::error::CircomkitError;
 use crate::testers::WitnessTester;
 use crate::types::{CircuitConfig, CircuitSignals, SignalValue};
 use std::collections::HashMap;
@@ -45,21 +46,17 @@ impl CircuitTester {
         }
     }
 
-    /// Create a circuit tester with custom directories
-    pub fn with_dirs(circuits_dir: &str, build_dir: &str) -> Self {
-        fs::create_dir_all(circuits_dir).ok();
-        fs::create_dir_all(build_dir).ok();
-
-        let config = CircomkitConfig::new()
-            .with_circuits_dir(circuits_dir)
-            .with_build_dir(build_dir)
-            .with_optimization(1);
+    /// Create a circuit tester from a custom configuration
+    pub fn from_config(config: CircomkitConfig) -> Self {
+        fs::create_dir_all(&config.dir_circuits).ok();
+        fs::create_dir_all(&config.dir_build).ok();
 
+        let circuits_dir = config.dir_circuits.clone();
         let circomkit = Circomkit::new(config).expect("Failed to create Circomkit");
 
         Self {
             circomkit,
-            circuits_dir: PathBuf::from(circuits_dir),
+            circuits_dir,
         }
     }
 
@@ -70,13 +67,6 @@ impl CircuitTester {
         path
     }
 
-    /// Create a CircuitConfig for a circuit
-    pub fn circuit_config(&self, name: &str) -> CircuitConfig {
-        CircuitConfig::new(name)
-            .with_file(&format!("{}.circom", name))
-            .with_template("main")
-    }
-
     /// Compile and test a circuit with given inputs (expects success)
     pub fn test_circuit(
         &self,
@@ -90,7 +80,7 @@ impl CircuitTester {
 
         // Create circuit config pointing to the file directly
         let circuit = CircuitConfig::new(name)
-            .with_file(&format!("{}.circom", name))
+            .with_file(format!("{}.circom", name))
             .with_params(params);
 
         // Use tokio runtime for async operations
@@ -129,7 +119,7 @@ impl CircuitTester {
         self.write_circuit(name, code);
 
         let circuit = CircuitConfig::new(name)
-            .with_file(&format!("{}.circom", name))
+            .with_file(format!("{}.circom", name))
             .with_params(params);
 
         let rt = tokio::runtime::Runtime::new()
@@ -153,40 +143,73 @@ impl CircuitTester {
         })
     }
 
-    /// Test circuit with expected outputs
-    pub fn test_circuit_output(
+    /// Compile a circuit and return the witness generation error for the given inputs
+    pub fn witness_error(
         &self,
         name: &str,
         code: &str,
         params: Vec<i64>,
         inputs: HashMap<String, Vec<String>>,
-        expected_outputs: HashMap<String, Vec<String>>,
-    ) -> std::result::Result<(), String> {
+    ) -> std::result::Result<CircomkitError, String> {
         self.write_circuit(name, code);
 
-        let circuit = CircuitConfig::new(name)
-            .with_file(&format!("{}.circom", name))
+        // Separate build name so this doesn't race witness tests on the same circuit
+        let circuit = CircuitConfig::new(format!("{}_error", name))
+            .with_file(format!("{}.circom", name))
+            .with_template(name)
             .with_params(params);
 
         let rt = tokio::runtime::Runtime::new()
             .map_err(|e| format!("Failed to create runtime: {}", e))?;
 
         rt.block_on(async {
-            // Create WitnessTester
-            let config = CircomkitConfig::new()
-                .with_circuits_dir(&self.circuits_dir)
-                .with_build_dir(TEST_BUILD_DIR)
-                .with_optimization(1);
-
-            let mut tester = WitnessTester::from_circuit_config_with_settings(circuit, config)
+            self.circomkit
+                .compile(&circuit)
                 .await
-                .map_err(|e| format!("Failed to create tester: {}", e))?;
+                .map_err(|e| format!("Compilation failed: {}", e))?;
+
+            let signals = convert_inputs(&inputs);
+
+            match self.circomkit.generate_witness(&circuit, &signals).await {
+                Ok(_) => Err("Expected circuit to fail but it passed".to_string()),
+                Err(e) => Ok(e),
+            }
+        })
+    }
+
+    /// Test circuit outputs against a Rust reference implementation
+    pub fn test_circuit_property<F>(
+        &self,
+        name: &str,
+        code: &str,
+        params: Vec<i64>,
+        inputs: HashMap<String, Vec<String>>,
+        oracle: F,
+    ) -> std::result::Result<(), String>
+    where
+        F: Fn(&CircuitSignals) -> CircuitSignals,
+    {
+        self.write_circuit(name, code);
+
+        // Separate build name so this doesn't race witness tests on the same circuit
+        let circuit = CircuitConfig::new(format!("{}_property", name))
+            .with_file(format!("{}.circom", name))
+            .with_template(name)
+            .with_params(params);
+
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("Failed to create runtime: {}", e))?;
 
-            let input_signals = convert_inputs(&inputs);
-            let expected_signals = convert_inputs(&expected_outputs);
+        rt.block_on(async {
+            let mut tester = WitnessTester::from_circuit_config_with_settings(
+                circuit,
+                self.circomkit.config().clone(),
+            )
+            .await
+            .map_err(|e| format!("Failed to create tester: {}", e))?;
 
             let result = tester
-                .expect_output(input_signals, expected_signals)
+                .check_property(convert_inputs(&inputs), oracle)
                 .await
                 .map_err(|e| format!("Test failed: {}", e))?;
 
@@ -198,9 +221,64 @@ impl CircuitTester {
         })
     }
 
-    /// Get the underlying Circomkit instance
-    pub fn circomkit(&self) -> &Circomkit {
-        &self.circomkit
+    /// Test that a circuit's constraint count is within `pct` percent of `expected`
+    pub fn test_constraint_count_within_pct(
+        &self,
+        name: &str,
+        code: &str,
+        params: Vec<i64>,
+        expected: usize,
+        pct: f64,
+    ) -> std::result::Result<(), String> {
+        self.write_circuit(name, code);
+
+        // Separate build name so this doesn't race witness tests on the same circuit
+        let circuit = CircuitConfig::new(format!("{}_constraints", name))
+            .with_file(format!("{}.circom", name))
+            .with_template(name)
+            .with_params(params);
+
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("Failed to create runtime: {}", e))?;
+
+        rt.block_on(async {
+            let mut tester = WitnessTester::from_circuit_config_with_settings(
+                circuit,
+                self.circomkit.config().clone(),
+            )
+            .await
+            .map_err(|e| format!("Failed to create tester: {}", e))?;
+
+            tester
+                .expect_constraint_count_within_pct(expected, pct)
+                .await
+                .map_err(|e| format!("Constraint check failed: {}", e))
+        })
+    }
+
+    /// Flatten a circuit and its includes into a single source
+    pub fn flatten_source(
+        &self,
+        name: &str,
+        code: &str,
+        params: Vec<i64>,
+    ) -> std::result::Result<String, String> {
+        self.write_circuit(name, code);
+
+        let circuit = CircuitConfig::new(name)
+            .with_file(format!("{}.circom", name))
+            .with_template(name)
+            .with_params(params);
+
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("Failed to create runtime: {}", e))?;
+
+        rt.block_on(async {
+            self.circomkit
+                .flatten_source(&circuit)
+                .await
+                .map_err(|e| format!("Flattening failed: {}", e))
+        })
     }
 }
 
@@ -1,8 +1,10 @@
 //! Type definitions for Circomkit-rs
 
+use crate::error::{CircomkitError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Supported proving protocols
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -27,6 +29,64 @@ impl std::fmt::Display for Protocol {
     }
 }
 
+/// Primes each protocol supports in snarkjs
+///
+/// Goldilocks has no pairing-friendly curve, and snarkjs only implements
+/// FFLONK over BN128.
+const SUPPORTED_PRIMES: [(Protocol, &[Prime]); 3] = [
+    (Protocol::Groth16, &[Prime::Bn128, Prime::Bls12381]),
+    (Protocol::Plonk, &[Prime::Bn128, Prime::Bls12381]),
+    (Protocol::Fflonk, &[Prime::Bn128]),
+];
+
+impl Protocol {
+    /// Whether snarkjs can set up and prove this protocol over `prime`
+    pub fn supports_prime(&self, prime: Prime) -> bool {
+        SUPPORTED_PRIMES
+            .iter()
+            .any(|(protocol, primes)| protocol == self && primes.contains(&prime))
+    }
+
+    /// Error unless this protocol supports `prime`
+    pub(crate) fn check_prime(&self, prime: Prime) -> Result<()> {
+        if self.supports_prime(prime) {
+            return Ok(());
+        }
+        let supported = SUPPORTED_PRIMES
+            .iter()
+            .find(|(protocol, _)| protocol == self)
+            .map(|(_, primes)| {
+                primes
+                    .iter()
+                    .map(Prime::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        Err(CircomkitError::InvalidConfig(format!(
+            "Protocol {} does not support prime {} (supported: {})",
+            self, prime, supported
+        )))
+    }
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = CircomkitError;
+
+    /// Parse a protocol name, case-insensitively; `groth` is accepted for Groth16
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "groth16" | "groth" => Ok(Protocol::Groth16),
+            "plonk" => Ok(Protocol::Plonk),
+            "fflonk" => Ok(Protocol::Fflonk),
+            _ => Err(CircomkitError::InvalidConfig(format!(
+                "Unknown protocol: {:?} (expected groth16, plonk or fflonk)",
+                s
+            ))),
+        }
+    }
+}
+
 /// Supported prime fields
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -40,6 +100,22 @@ pub enum Prime {
     Goldilocks,
 }
 
+impl Prime {
+    /// Order of the field, the modulus signal values are reduced by
+    pub fn modulus(&self) -> num_bigint::BigUint {
+        let decimal: &[u8] = match self {
+            Prime::Bn128 => {
+                b"21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            }
+            Prime::Bls12381 => {
+                b"52435875175126190479447740508185965837690552500527637822603658699938581184513"
+            }
+            Prime::Goldilocks => b"18446744069414584321",
+        };
+        num_bigint::BigUint::parse_bytes(decimal, 10).expect("valid modulus")
+    }
+}
+
 impl std::fmt::Display for Prime {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -50,6 +126,24 @@ impl std::fmt::Display for Prime {
     }
 }
 
+impl std::str::FromStr for Prime {
+    type Err = CircomkitError;
+
+    /// Parse a prime name, case-insensitively; `bn254` and `bls12-381` style
+    /// spellings are accepted
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bn128" | "bn254" | "alt_bn128" => Ok(Prime::Bn128),
+            "bls12381" | "bls12-381" | "bls12_381" => Ok(Prime::Bls12381),
+            "goldilocks" => Ok(Prime::Goldilocks),
+            _ => Err(CircomkitError::InvalidConfig(format!(
+                "Unknown prime: {:?} (expected bn128, bls12381 or goldilocks)",
+                s
+            ))),
+        }
+    }
+}
+
 /// Signal value type - can be a single value or an array
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -77,6 +171,178 @@ impl SignalValue {
         Self::Array(values.into_iter().map(|v| Self::single(v)).collect())
     }
 
+    /// Number of leaf values, counting every element of nested arrays
+    pub fn num_elements(&self) -> usize {
+        match self {
+            SignalValue::Single(_) | SignalValue::Number(_) => 1,
+            SignalValue::Array(arr) => arr.iter().map(|v| v.num_elements()).sum(),
+        }
+    }
+
+    /// Create a single value from a hex string, with or without `0x`
+    ///
+    /// The value is stored in decimal, the form snarkjs accepts.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let digits = hex.strip_prefix("0x").unwrap_or(hex);
+        num_bigint::BigUint::parse_bytes(digits.as_bytes(), 16)
+            .map(Self::single)
+            .ok_or_else(|| CircomkitError::InvalidSignals(format!("invalid hex value '{}'", hex)))
+    }
+
+    /// Whether this value equals `expected`, comparing numbers and numeric
+    /// strings, decimal or `0x` hex, by value
+    pub fn matches(&self, expected: &SignalValue) -> bool {
+        match (self, expected) {
+            (SignalValue::Array(a), SignalValue::Array(e)) => {
+                a.len() == e.len() && a.iter().zip(e.iter()).all(|(av, ev)| av.matches(ev))
+            }
+            (SignalValue::Array(_), _) | (_, SignalValue::Array(_)) => false,
+            (a, e) => match (a.to_bigint(), e.to_bigint()) {
+                (Some(a), Some(e)) => a == e,
+                _ => matches!((a, e), (SignalValue::Single(a), SignalValue::Single(e)) if a == e),
+            },
+        }
+    }
+
+    /// Value of a single decimal or `0x` hex number
+    fn to_bigint(&self) -> Option<num_bigint::BigInt> {
+        match self {
+            SignalValue::Number(n) => Some((*n).into()),
+            SignalValue::Single(s) => match s.strip_prefix("0x") {
+                Some(hex) => num_bigint::BigInt::parse_bytes(hex.as_bytes(), 16),
+                None => s.parse().ok(),
+            },
+            SignalValue::Array(_) => None,
+        }
+    }
+
+    /// `a + b` in the field of `prime`
+    ///
+    /// Arrays of the same shape are added element-wise. The result holds
+    /// reduced decimal strings, so it compares equal to circuit outputs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use circomkit::types::{Prime, SignalValue};
+    ///
+    /// let a = SignalValue::single(3);
+    /// let b = SignalValue::single(4);
+    /// assert_eq!(
+    ///     SignalValue::add(&a, &b, Prime::Bn128).unwrap(),
+    ///     SignalValue::single(7)
+    /// );
+    /// ```
+    pub fn add(a: &SignalValue, b: &SignalValue, prime: Prime) -> Result<SignalValue> {
+        Self::field_op(a, b, prime, |a, b| a + b)
+    }
+
+    /// `a - b` in the field of `prime`, wrapping below zero
+    pub fn sub(a: &SignalValue, b: &SignalValue, prime: Prime) -> Result<SignalValue> {
+        Self::field_op(a, b, prime, |a, b| a - b)
+    }
+
+    /// `a * b` in the field of `prime`
+    pub fn mul(a: &SignalValue, b: &SignalValue, prime: Prime) -> Result<SignalValue> {
+        Self::field_op(a, b, prime, |a, b| a * b)
+    }
+
+    /// `-a` in the field of `prime`
+    pub fn neg(a: &SignalValue, prime: Prime) -> Result<SignalValue> {
+        Self::field_op(a, a, prime, |a, _| -a)
+    }
+
+    /// Largest absolute difference between this value and `expected` as
+    /// field elements of `prime`, where `p - 1` counts as one below zero
+    ///
+    /// Arrays are compared element-wise. `None` if the shapes differ or a
+    /// value isn't a number.
+    pub fn field_distance(
+        &self,
+        expected: &SignalValue,
+        prime: Prime,
+    ) -> Option<num_bigint::BigUint> {
+        fn largest(
+            diff: &SignalValue,
+            modulus: &num_bigint::BigUint,
+        ) -> Option<num_bigint::BigUint> {
+            match diff {
+                SignalValue::Array(arr) => arr
+                    .iter()
+                    .map(|v| largest(v, modulus))
+                    .try_fold(num_bigint::BigUint::default(), |max, d| Some(max.max(d?))),
+                v => {
+                    let d: num_bigint::BigUint = v.as_string().parse().ok()?;
+                    Some((modulus - &d).min(d))
+                }
+            }
+        }
+
+        let diff = Self::sub(self, expected, prime).ok()?;
+        largest(&diff, &prime.modulus())
+    }
+
+    /// Apply `op` to the leaves of `a` and `b`, reducing modulo the field
+    fn field_op(
+        a: &SignalValue,
+        b: &SignalValue,
+        prime: Prime,
+        op: impl Fn(num_bigint::BigInt, num_bigint::BigInt) -> num_bigint::BigInt + Copy,
+    ) -> Result<SignalValue> {
+        match (a, b) {
+            (SignalValue::Array(a), SignalValue::Array(b)) if a.len() == b.len() => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| Self::field_op(a, b, prime, op))
+                .collect::<Result<_>>()
+                .map(SignalValue::Array),
+            (SignalValue::Array(_), _) | (_, SignalValue::Array(_)) => {
+                Err(CircomkitError::InvalidSignals(format!(
+                    "cannot combine {} and {}: shapes differ",
+                    a.as_string(),
+                    b.as_string()
+                )))
+            }
+            _ => {
+                let value = |v: &SignalValue| {
+                    v.to_bigint().ok_or_else(|| {
+                        CircomkitError::InvalidSignals(format!(
+                            "'{}' is not a number",
+                            v.as_string()
+                        ))
+                    })
+                };
+                let modulus = num_bigint::BigInt::from(prime.modulus());
+                let result = op(value(a)?, value(b)?) % &modulus;
+                let reduced = if result.sign() == num_bigint::Sign::Minus {
+                    result + modulus
+                } else {
+                    result
+                };
+                Ok(SignalValue::single(reduced))
+            }
+        }
+    }
+
+    /// Copy of this value with every leaf as a decimal string
+    ///
+    /// Numbers and `0x` hex strings are converted; other strings are kept
+    /// as they are.
+    pub fn normalize(&self) -> SignalValue {
+        match self {
+            SignalValue::Single(s) => {
+                let decimal = s
+                    .strip_prefix("0x")
+                    .and_then(|hex| num_bigint::BigUint::parse_bytes(hex.as_bytes(), 16));
+                SignalValue::Single(decimal.map_or_else(|| s.clone(), |n| n.to_string()))
+            }
+            SignalValue::Number(n) => SignalValue::Single(n.to_string()),
+            SignalValue::Array(arr) => {
+                SignalValue::Array(arr.iter().map(Self::normalize).collect())
+            }
+        }
+    }
+
     /// Convert to a string representation
     pub fn as_string(&self) -> String {
         match self {
@@ -114,11 +380,40 @@ impl<T: Into<SignalValue>> From<Vec<T>> for SignalValue {
     }
 }
 
+impl TryFrom<serde_json::Value> for SignalValue {
+    type Error = CircomkitError;
+
+    /// Convert a JSON string, integer, or (nested) array of them
+    fn try_from(value: serde_json::Value) -> Result<Self> {
+        match value {
+            serde_json::Value::String(s) => Ok(Self::Single(s)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Ok(Self::Number(i)),
+                // Integers beyond i64 keep their exact decimal form
+                None if n.is_u64() => Ok(Self::Single(n.to_string())),
+                None => Err(CircomkitError::InvalidSignals(format!(
+                    "signal values must be integers, got {}",
+                    n
+                ))),
+            },
+            serde_json::Value::Array(values) => values
+                .into_iter()
+                .map(Self::try_from)
+                .collect::<Result<Vec<_>>>()
+                .map(Self::Array),
+            other => Err(CircomkitError::InvalidSignals(format!(
+                "signal values must be strings, integers, or arrays, got {}",
+                other
+            ))),
+        }
+    }
+}
+
 /// Circuit input/output signals
 pub type CircuitSignals = HashMap<String, SignalValue>;
 
 /// Configuration for a circuit
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CircuitConfig {
     /// Name of the circuit instance
     pub name: String,
@@ -132,9 +427,26 @@ pub struct CircuitConfig {
     /// Template parameters
     #[serde(default)]
     pub params: Vec<i64>,
+    /// Named template parameters, passed positionally in order and taking
+    /// precedence over `params`
+    #[serde(default, skip_serializing_if = "NamedParams::is_empty")]
+    pub named_params: NamedParams,
     /// Public signals
     #[serde(default)]
     pub public: Vec<String>,
+    /// Optimization level, overriding the global configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimization: Option<u8>,
+    /// Prime field, overriding the global configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prime: Option<Prime>,
+    /// Name of the generated top-level component instance, `main` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_name: Option<String>,
+    /// Source of the generated main component, with placeholders (see
+    /// [`CircuitConfig::with_main_template`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_template: Option<String>,
 }
 
 impl CircuitConfig {
@@ -147,7 +459,12 @@ impl CircuitConfig {
             absolute_file: None,
             template: name,
             params: Vec::new(),
+            named_params: NamedParams::default(),
             public: Vec::new(),
+            optimization: None,
+            prime: None,
+            main_name: None,
+            main_template: None,
         }
     }
 
@@ -183,9 +500,29 @@ impl CircuitConfig {
     /// Set template parameters
     pub fn with_params(mut self, params: Vec<i64>) -> Self {
         self.params = params;
+        self.named_params = NamedParams::default();
         self
     }
 
+    /// Set template parameters by name
+    ///
+    /// Names only document the parameters; they are passed to the template
+    /// positionally in the given order.
+    pub fn with_named_params(mut self, pairs: Vec<(String, i64)>) -> Self {
+        self.named_params = NamedParams(pairs);
+        self.params = Vec::new();
+        self
+    }
+
+    /// Template parameter values in positional order
+    pub fn param_values(&self) -> Vec<i64> {
+        if self.named_params.is_empty() {
+            self.params.clone()
+        } else {
+            self.named_params.values().collect()
+        }
+    }
+
     /// Set public signals
     pub fn with_public(mut self, public: Vec<String>) -> Self {
         self.public = public;
@@ -197,10 +534,173 @@ impl CircuitConfig {
         self.public.push(signal.into());
         self
     }
+
+    /// Override the optimization level for this circuit
+    ///
+    /// Levels above 2 are rejected by [`CircuitConfig::validate`].
+    pub fn with_optimization(mut self, level: u8) -> Self {
+        self.optimization = Some(level);
+        self
+    }
+
+    /// Override the prime field for this circuit
+    pub fn with_prime(mut self, prime: Prime) -> Self {
+        self.prime = Some(prime);
+        self
+    }
+
+    /// Name the generated top-level component instance
+    ///
+    /// Released circom versions only accept `main`.
+    pub fn with_main_name(mut self, name: impl Into<String>) -> Self {
+        self.main_name = Some(name.into());
+        self
+    }
+
+    /// Replace the generated main component source
+    ///
+    /// Placeholders are substituted before compiling:
+    ///
+    /// - `{include}` - path to the circuit file, to use inside `include "..."`
+    /// - `{template}` - template name
+    /// - `{params}` - comma-separated template parameters
+    /// - `{public}` - the ` {public [...]}` clause, empty with no public signals
+    /// - `{version}` - the configured circom version
+    /// - `{main}` - the main component name
+    pub fn with_main_template(mut self, template: impl Into<String>) -> Self {
+        self.main_template = Some(template.into());
+        self
+    }
+
+    /// Name of the generated top-level component instance
+    pub fn main_name(&self) -> &str {
+        self.main_name.as_deref().unwrap_or("main")
+    }
+
+    /// Check that the template and file can be compiled
+    ///
+    /// The template must be a circom identifier, an optimization override
+    /// must be 0, 1, or 2, and the file must either be a `.circom` path or
+    /// be overridden by an existing `absolute_file`.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| {
+            Err(CircomkitError::InvalidConfig(format!(
+                "circuit '{}': {}",
+                self.name, reason
+            )))
+        };
+
+        if !is_identifier(&self.template) {
+            return invalid(format!(
+                "template '{}' is not a valid circom identifier",
+                self.template
+            ));
+        }
+
+        if let Some(level) = self.optimization.filter(|&level| level > 2) {
+            return invalid(format!(
+                "optimization level must be 0, 1, or 2, got {}",
+                level
+            ));
+        }
+
+        match &self.absolute_file {
+            Some(path) if !path.is_file() => {
+                invalid(format!("file {} does not exist", path.display()))
+            }
+            Some(_) => Ok(()),
+            None if !self.file.ends_with(".circom") => invalid(format!(
+                "file '{}' does not have a .circom extension",
+                self.file
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether a name is a valid circom identifier
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Template parameters keyed by name, kept in insertion order
+///
+/// Serialized as a JSON object whose key order is the parameter order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamedParams(pub Vec<(String, i64)>);
+
+impl NamedParams {
+    /// Whether there are no parameters
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Parameter values in order
+    pub fn values(&self) -> impl Iterator<Item = i64> + '_ {
+        self.0.iter().map(|(_, value)| *value)
+    }
+}
+
+impl Serialize for NamedParams {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for NamedParams {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = NamedParams;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map of parameter names to integers")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<NamedParams, A::Error> {
+                let mut pairs = Vec::new();
+                while let Some((name, value)) = map.next_entry::<String, i64>()? {
+                    if pairs.iter().any(|(n, _)| *n == name) {
+                        return Err(serde::de::Error::custom(format!(
+                            "duplicate parameter '{}'",
+                            name
+                        )));
+                    }
+                    pairs.push((name, value));
+                }
+                Ok(NamedParams(pairs))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
 }
 
 /// Zero-knowledge proof
+///
+/// Deserializes from a proof JSON with a `protocol` field, as snarkjs
+/// writes; use [`Proof::from_snarkjs_json`] for proofs without one.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "serde_json::Value")]
 pub struct Proof {
     /// Protocol used to generate the proof
     pub protocol: Protocol,
@@ -209,6 +709,58 @@ pub struct Proof {
     pub data: serde_json::Value,
 }
 
+impl Proof {
+    /// Proof from the contents of a snarkjs `proof.json`
+    ///
+    /// `protocol` is used when the JSON has no `protocol` field, and must
+    /// match it otherwise. The field is moved out of `data`, so serializing
+    /// the proof writes it once.
+    pub fn from_snarkjs_json(protocol: Protocol, json: serde_json::Value) -> Result<Self> {
+        match split_protocol(json)? {
+            (Some(named), _) if named != protocol => Err(CircomkitError::InvalidConfig(format!(
+                "Proof is for {}, expected {}",
+                named, protocol
+            ))),
+            (_, data) => Ok(Self { protocol, data }),
+        }
+    }
+}
+
+/// Separate the `protocol` field from the rest of a proof JSON object
+fn split_protocol(json: serde_json::Value) -> Result<(Option<Protocol>, serde_json::Value)> {
+    let serde_json::Value::Object(mut map) = json else {
+        return Err(CircomkitError::InvalidConfig(
+            "Proof JSON must be an object".to_string(),
+        ));
+    };
+
+    let protocol = match map.remove("protocol") {
+        Some(serde_json::Value::String(name)) => Some(name.parse()?),
+        Some(other) => {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "Proof protocol must be a string, got {}",
+                other
+            )));
+        }
+        None => None,
+    };
+    Ok((protocol, serde_json::Value::Object(map)))
+}
+
+impl TryFrom<serde_json::Value> for Proof {
+    type Error = CircomkitError;
+
+    fn try_from(json: serde_json::Value) -> Result<Self> {
+        let (protocol, data) = split_protocol(json)?;
+        let protocol = protocol.ok_or_else(|| {
+            CircomkitError::InvalidConfig(
+                "Proof has no protocol field; load it with Proof::from_snarkjs_json".to_string(),
+            )
+        })?;
+        Ok(Self { protocol, data })
+    }
+}
+
 /// Verification key
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationKey {
@@ -219,6 +771,31 @@ pub struct VerificationKey {
     pub data: serde_json::Value,
 }
 
+impl VerificationKey {
+    /// Number of public inputs the key expects
+    ///
+    /// Reads `nPublic` from the snarkjs key JSON. For Groth16 keys that lack
+    /// it, the count is derived from the `IC` points (one per public input,
+    /// plus one).
+    pub fn num_public(&self) -> Result<usize> {
+        if let Some(n) = self.data.get("nPublic").and_then(|v| v.as_u64()) {
+            return Ok(n as usize);
+        }
+
+        if self.protocol == Protocol::Groth16
+            && let Some(ic) = self.data.get("IC").and_then(|v| v.as_array())
+            && !ic.is_empty()
+        {
+            return Ok(ic.len() - 1);
+        }
+
+        Err(CircomkitError::InvalidConfig(format!(
+            "Verification key for {} has no public input count",
+            self.protocol
+        )))
+    }
+}
+
 /// Public signals from a proof
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicSignals(pub Vec<String>);
@@ -233,6 +810,69 @@ impl PublicSignals {
     pub fn as_slice(&self) -> &[String] {
         &self.0
     }
+
+    /// Number of public signals
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no public signals
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Signal at `index`, if any
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.0.get(index).map(String::as_str)
+    }
+
+    /// Iterate over the signals in order
+    pub fn iter(&self) -> std::slice::Iter<'_, String> {
+        self.0.iter()
+    }
+
+    /// Signals as numbers, accepting decimal or `0x` hex strings
+    pub fn as_field_elements(&self) -> Result<Vec<num_bigint::BigInt>> {
+        self.0
+            .iter()
+            .map(|signal| {
+                SignalValue::Single(signal.clone())
+                    .to_bigint()
+                    .ok_or_else(|| {
+                        CircomkitError::InvalidSignals(format!(
+                            "Public signal '{}' is not a number",
+                            signal
+                        ))
+                    })
+            })
+            .collect()
+    }
+}
+
+impl std::ops::Index<usize> for PublicSignals {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &str {
+        &self.0[index]
+    }
+}
+
+impl IntoIterator for PublicSignals {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PublicSignals {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
 }
 
 /// Witness data
@@ -245,7 +885,7 @@ pub struct Witness {
 }
 
 /// Build artifacts for a circuit
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CircuitArtifacts {
     /// Path to the R1CS file
     pub r1cs: PathBuf,
@@ -257,10 +897,130 @@ pub struct CircuitArtifacts {
     pub pkey: Option<PathBuf>,
     /// Path to the verification key (if generated)
     pub vkey: Option<PathBuf>,
+    /// Warnings reported by the compiler, e.g. unconstrained signals from `--inspect`
+    pub warnings: Vec<String>,
+}
+
+impl CircuitArtifacts {
+    /// Whether every file the artifacts refer to exists
+    pub fn files_exist(&self) -> bool {
+        [&self.r1cs, &self.wasm, &self.sym]
+            .into_iter()
+            .chain(self.pkey.as_ref())
+            .chain(self.vkey.as_ref())
+            .all(|path| path.exists())
+    }
+}
+
+/// Build artifacts of a circuit currently on disk
+///
+/// Each path is `None` if the file doesn't exist.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactFiles {
+    /// Path to the R1CS file
+    pub r1cs: Option<PathBuf>,
+    /// Path to the WASM file
+    pub wasm: Option<PathBuf>,
+    /// Path to the symbol file
+    pub sym: Option<PathBuf>,
+    /// Path to the proving key
+    pub pkey: Option<PathBuf>,
+    /// Path to the verification key
+    pub vkey: Option<PathBuf>,
+}
+
+/// Versions of the external tools, `None` for tools that weren't found
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolReport {
+    /// circom compiler version
+    pub circom: Option<String>,
+    /// snarkjs version
+    pub snarkjs: Option<String>,
+    /// Node.js version
+    pub node: Option<String>,
+    /// Tools whose version is outside the supported range
+    #[serde(default)]
+    pub warnings: Vec<CompatibilityWarning>,
+}
+
+impl ToolReport {
+    /// Names of the tools that weren't found
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            ("circom", &self.circom),
+            ("snarkjs", &self.snarkjs),
+            ("node", &self.node),
+        ]
+        .into_iter()
+        .filter(|(_, version)| version.is_none())
+        .map(|(tool, _)| tool)
+        .collect()
+    }
+
+    /// Fail with a single error naming every missing tool
+    pub fn require_all(&self) -> Result<()> {
+        let missing = self.missing();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(CircomkitError::tool_not_found(missing.join(", ")))
+        }
+    }
+}
+
+/// A `major.minor.patch` version
+type Version = (u64, u64, u64);
+
+/// Tool versions circomkit supports, as `[min, max)` ranges
+const SUPPORTED_VERSIONS: [(&str, Version, Version); 2] = [
+    ("circom", (2, 1, 0), (3, 0, 0)),
+    ("snarkjs", (0, 7, 0), (0, 8, 0)),
+];
+
+/// An installed tool whose version is outside the supported range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatibilityWarning {
+    /// Tool name, `circom` or `snarkjs`
+    pub tool: String,
+    /// Installed version
+    pub version: String,
+    /// Supported range, e.g. `>=0.7.0, <0.8.0`
+    pub supported: String,
+}
+
+impl CompatibilityWarning {
+    /// Warning for `tool` at `version`, `None` if the version is supported
+    /// or can't be parsed
+    pub fn check(tool: &str, version: &str) -> Option<Self> {
+        let (_, min, max) = SUPPORTED_VERSIONS.iter().find(|(name, ..)| *name == tool)?;
+        let mut parts = version.split('.').map(|p| p.parse::<u64>().ok());
+        let parsed = (parts.next()??, parts.next()??, parts.next()??);
+        if (*min..*max).contains(&parsed) {
+            return None;
+        }
+
+        let show =
+            |(major, minor, patch): (u64, u64, u64)| format!("{}.{}.{}", major, minor, patch);
+        Some(Self {
+            tool: tool.to_string(),
+            version: version.to_string(),
+            supported: format!(">={}, <{}", show(*min), show(*max)),
+        })
+    }
+}
+
+impl std::fmt::Display for CompatibilityWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} is outside the supported range {}",
+            self.tool, self.version, self.supported
+        )
+    }
 }
 
 /// Circuit information from compilation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CircuitInfo {
     /// Number of constraints
     pub constraints: usize,
@@ -274,6 +1034,34 @@ pub struct CircuitInfo {
     pub labels: usize,
 }
 
+/// Signed change in circuit size relative to a baseline compilation
+///
+/// Positive values mean the current circuit is larger.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InfoDelta {
+    /// Change in the number of constraints
+    pub constraints: i64,
+    /// Change in the number of inputs, public and private
+    pub inputs: i64,
+    /// Change in the number of outputs
+    pub outputs: i64,
+}
+
+impl InfoDelta {
+    /// Delta from `baseline` to `current`
+    pub fn between(baseline: &CircuitInfo, current: &CircuitInfo) -> Self {
+        let diff = |current: usize, baseline: usize| current as i64 - baseline as i64;
+        Self {
+            constraints: diff(current.constraints, baseline.constraints),
+            inputs: diff(
+                current.public_inputs + current.private_inputs,
+                baseline.public_inputs + baseline.private_inputs,
+            ),
+            outputs: diff(current.public_outputs, baseline.public_outputs),
+        }
+    }
+}
+
 /// Result of witness testing
 #[derive(Debug, Clone)]
 pub struct WitnessTestResult {
@@ -285,6 +1073,202 @@ pub struct WitnessTestResult {
     pub expected: Option<CircuitSignals>,
     /// Error message if failed
     pub error: Option<String>,
+    /// Expected signals that differ from their output by no more than their
+    /// tolerance, sorted
+    pub tolerated: Vec<String>,
+    /// Time spent in each stage of the test
+    pub durations: StageTimings,
+}
+
+impl WitnessTestResult {
+    /// Result of checking `outputs` against the `expected` values
+    ///
+    /// Passes when every expected signal is present with a matching value;
+    /// outputs that weren't expected are ignored.
+    pub(crate) fn compare(
+        outputs: CircuitSignals,
+        expected: CircuitSignals,
+        durations: StageTimings,
+    ) -> Self {
+        Self::compare_within(
+            outputs,
+            expected,
+            &HashMap::new(),
+            Prime::default(),
+            durations,
+        )
+    }
+
+    /// Like [`WitnessTestResult::compare`], letting the signals in
+    /// `tolerances` differ from their expected value by up to the given
+    /// amount as field elements of `prime`
+    pub(crate) fn compare_within(
+        outputs: CircuitSignals,
+        expected: CircuitSignals,
+        tolerances: &HashMap<String, num_bigint::BigUint>,
+        prime: Prime,
+        durations: StageTimings,
+    ) -> Self {
+        let mut errors = Vec::new();
+        let mut tolerated = Vec::new();
+        for (name, expected_value) in &expected {
+            match outputs.get(name) {
+                Some(actual) if actual.matches(expected_value) => {}
+                Some(actual) => {
+                    let mismatch = format!(
+                        "Signal '{}': expected {}, got {}",
+                        name,
+                        expected_value.as_string(),
+                        actual.as_string()
+                    );
+                    let Some(tolerance) = tolerances.get(name) else {
+                        errors.push(mismatch);
+                        continue;
+                    };
+                    match actual.field_distance(expected_value, prime) {
+                        Some(delta) if delta <= *tolerance => tolerated.push(name.clone()),
+                        Some(delta) => errors.push(format!(
+                            "{} (off by {}, tolerance {})",
+                            mismatch, delta, tolerance
+                        )),
+                        None => errors.push(mismatch),
+                    }
+                }
+                None => errors.push(format!("Signal '{}' not found in outputs", name)),
+            }
+        }
+
+        tolerated.sort();
+        Self {
+            passed: errors.is_empty(),
+            outputs,
+            expected: Some(expected),
+            error: (!errors.is_empty()).then(|| errors.join("; ")),
+            tolerated,
+            durations,
+        }
+    }
+
+    /// Fail the result, appending `error` to any earlier errors
+    pub(crate) fn add_error(&mut self, error: String) {
+        self.passed = false;
+        self.error = Some(match self.error.take() {
+            Some(earlier) => format!("{}; {}", earlier, error),
+            None => error,
+        });
+    }
+
+    /// Result of a witness that couldn't be generated or read
+    pub(crate) fn failed(error: &CircomkitError, durations: StageTimings) -> Self {
+        Self {
+            passed: false,
+            outputs: CircuitSignals::new(),
+            expected: None,
+            error: Some(error.to_string()),
+            tolerated: Vec::new(),
+            durations,
+        }
+    }
+
+    /// Table of expected and actual values for the mismatched signals
+    ///
+    /// Arrays are expanded element by element, with `<-` marking the first
+    /// element that differs. Signals within their tolerance are left out.
+    /// Empty if nothing was expected or every expected signal matches.
+    pub fn format_diff(&self) -> String {
+        let Some(expected) = &self.expected else {
+            return String::new();
+        };
+
+        let mut names: Vec<_> = expected.keys().collect();
+        names.sort();
+
+        let mut rows = Vec::new();
+        for name in names {
+            let expected = &expected[name];
+            let actual = self.outputs.get(name);
+            if actual.is_some_and(|actual| actual.matches(expected))
+                || self.tolerated.contains(name)
+            {
+                continue;
+            }
+
+            let start = rows.len();
+            diff_rows(name.clone(), Some(expected), actual, &mut rows);
+            if let Some(first) = rows[start..].iter_mut().find(|row| row.differs) {
+                first.marker = true;
+            }
+        }
+
+        if rows.is_empty() {
+            return String::new();
+        }
+
+        let width = |header: &str, column: fn(&DiffRow) -> &str| {
+            rows.iter()
+                .map(|row| column(row).len())
+                .chain([header.len()])
+                .max()
+                .unwrap_or(0)
+        };
+        let name_width = width("signal", |row| &row.name);
+        let expected_width = width("expected", |row| &row.expected);
+
+        let mut table = format!(
+            "{:name_width$}  {:expected_width$}  actual",
+            "signal", "expected"
+        );
+        for row in &rows {
+            let line = format!(
+                "{:name_width$}  {:expected_width$}  {}{}",
+                row.name,
+                row.expected,
+                row.actual,
+                if row.marker { "  <-" } else { "" }
+            );
+            table.push('\n');
+            table.push_str(line.trim_end());
+        }
+        table
+    }
+}
+
+/// A line of [`WitnessTestResult::format_diff`]
+struct DiffRow {
+    name: String,
+    expected: String,
+    actual: String,
+    /// Whether the values on this line differ
+    differs: bool,
+    /// Whether this is the first differing line of its signal
+    marker: bool,
+}
+
+/// Append rows comparing `expected` and `actual`, expanding arrays when
+/// both sides are arrays
+fn diff_rows(
+    name: String,
+    expected: Option<&SignalValue>,
+    actual: Option<&SignalValue>,
+    rows: &mut Vec<DiffRow>,
+) {
+    if let (Some(SignalValue::Array(e)), Some(SignalValue::Array(a))) = (expected, actual) {
+        for i in 0..e.len().max(a.len()) {
+            diff_rows(format!("{}[{}]", name, i), e.get(i), a.get(i), rows);
+        }
+        return;
+    }
+
+    let show = |value: Option<&SignalValue>| {
+        value.map_or_else(|| "(missing)".to_string(), SignalValue::as_string)
+    };
+    rows.push(DiffRow {
+        differs: !matches!((actual, expected), (Some(a), Some(e)) if a.matches(e)),
+        name,
+        expected: show(expected),
+        actual: show(actual),
+        marker: false,
+    });
 }
 
 /// Result of proof testing
@@ -298,4 +1282,655 @@ pub struct ProofTestResult {
     pub public_signals: Option<PublicSignals>,
     /// Error message if failed
     pub error: Option<String>,
+    /// Category of the failure, if failed
+    pub error_kind: Option<ProofFailureKind>,
+    /// Time spent in each stage of the test
+    pub durations: StageTimings,
+}
+
+/// Time spent in each pipeline stage of a single test
+///
+/// Stages that didn't run are `None`, including compilation and setup
+/// already done by an earlier test on the same tester.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    /// Circuit compilation
+    pub compile: Option<Duration>,
+    /// Key setup
+    pub setup: Option<Duration>,
+    /// Witness generation, including reading the outputs back
+    pub witness: Option<Duration>,
+    /// Proof generation
+    pub prove: Option<Duration>,
+    /// Proof verification
+    pub verify: Option<Duration>,
+}
+
+impl StageTimings {
+    /// Total time across the stages that ran
+    pub fn total(&self) -> Duration {
+        [
+            self.compile,
+            self.setup,
+            self.witness,
+            self.prove,
+            self.verify,
+        ]
+        .into_iter()
+        .flatten()
+        .sum()
+    }
+}
+
+/// Why a proof round trip failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofFailureKind {
+    /// Compiling the circuit or generating the keys failed
+    SetupFailed,
+    /// Generating the witness or proof failed
+    ProvingFailed,
+    /// Running the verifier failed, e.g. on a corrupted verification key
+    VerificationFailed,
+    /// The verifier ran and rejected the proof
+    VerificationReturnedFalse,
+    /// circom, snarkjs, or node could not be found
+    ToolMissing,
+}
+
+impl ProofFailureKind {
+    /// Classify an error from a proof stage, where `stage` is the kind used
+    /// unless a tool was missing
+    pub(crate) fn of(error: &CircomkitError, stage: ProofFailureKind) -> Self {
+        match error {
+            CircomkitError::ToolNotFound { .. } => ProofFailureKind::ToolMissing,
+            _ => stage,
+        }
+    }
+}
+
+/// Report of [`Circomkit::full_check`](crate::Circomkit::full_check)
+///
+/// Later stages are `None` only when compilation failed; otherwise each
+/// stage runs and records its own failure.
+#[derive(Debug, Clone)]
+pub struct FullCheckReport {
+    /// Compiled artifacts, `None` if compilation failed
+    pub artifacts: Option<CircuitArtifacts>,
+    /// Error message if compilation failed
+    pub compile_error: Option<String>,
+    /// Circuit size, `None` if it couldn't be read
+    pub info: Option<CircuitInfo>,
+    /// Witness generation and output comparison
+    pub witness: Option<WitnessTestResult>,
+    /// Setup, proof generation and verification
+    pub proof: Option<ProofTestResult>,
+}
+
+impl FullCheckReport {
+    /// Whether the circuit compiled, its outputs matched, and the proof verified
+    pub fn passed(&self) -> bool {
+        self.compile_error.is_none()
+            && self.witness.as_ref().is_some_and(|w| w.passed)
+            && self.proof.as_ref().is_some_and(|p| p.valid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_from_str() {
+        assert_eq!("groth16".parse::<Protocol>().unwrap(), Protocol::Groth16);
+        assert_eq!("Groth".parse::<Protocol>().unwrap(), Protocol::Groth16);
+        assert_eq!(" PLONK ".parse::<Protocol>().unwrap(), Protocol::Plonk);
+        assert_eq!("FFlonk".parse::<Protocol>().unwrap(), Protocol::Fflonk);
+        assert!(matches!(
+            "marlin".parse::<Protocol>(),
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+
+        for protocol in [Protocol::Groth16, Protocol::Plonk, Protocol::Fflonk] {
+            assert_eq!(protocol.to_string().parse::<Protocol>().unwrap(), protocol);
+        }
+    }
+
+    #[test]
+    fn test_signal_normalize() {
+        let value = SignalValue::Array(vec![
+            SignalValue::Number(-3),
+            SignalValue::from("0xff"),
+            SignalValue::from(vec!["7", "0x10"]),
+            SignalValue::from("not a number"),
+        ]);
+        assert_eq!(
+            serde_json::to_string(&value.normalize()).unwrap(),
+            r#"["-3","255",["7","16"],"not a number"]"#
+        );
+    }
+
+    /// Groth16 `proof.json` in the layout `snarkjs groth16 prove` writes
+    const SNARKJS_GROTH16_PROOF: &str = r#"{
+ "pi_a": [
+  "4340298347541787463858893435262436474591468049474536446736419604473580138812",
+  "18069468213656733342539402599536349428366405566626620016683513427543398573620",
+  "1"
+ ],
+ "pi_b": [
+  [
+   "7396536633298532713404011932404497622219548466359106802626553419367394429745",
+   "9391853457005734298587812358302617066186609364063286536096346633262598785413"
+  ],
+  [
+   "12585493567536436669683102788302436346113779599493478227434869406478596463463",
+   "15853924406417564373463050066434359011604773452498656802655449618848437394034"
+  ],
+  [
+   "1",
+   "0"
+  ]
+ ],
+ "pi_c": [
+  "17432186431698356934869651413543735937530826476212862617316522745113735458536",
+  "3051373590405315427386036788106722616022186271233006434014474488736451245406",
+  "1"
+ ],
+ "protocol": "groth16",
+ "curve": "bn128"
+}"#;
+
+    #[test]
+    fn test_proof_from_snarkjs_json() {
+        let proof: Proof = serde_json::from_str(SNARKJS_GROTH16_PROOF).unwrap();
+        assert_eq!(proof.protocol, Protocol::Groth16);
+        assert_eq!(proof.data["curve"], "bn128");
+        assert_eq!(proof.data["pi_b"][2], serde_json::json!(["1", "0"]));
+        assert!(proof.data.get("protocol").is_none());
+
+        // Round trips with a single protocol field
+        let written = serde_json::to_string(&proof).unwrap();
+        assert_eq!(written.matches("\"protocol\"").count(), 1);
+        let reread: Proof = serde_json::from_str(&written).unwrap();
+        assert_eq!(reread.data, proof.data);
+
+        // Without a protocol field, it has to be supplied
+        let mut json: serde_json::Value = serde_json::from_str(SNARKJS_GROTH16_PROOF).unwrap();
+        json.as_object_mut().unwrap().remove("protocol");
+        let err = serde_json::from_value::<Proof>(json.clone()).unwrap_err();
+        assert!(err.to_string().contains("from_snarkjs_json"));
+        let proof = Proof::from_snarkjs_json(Protocol::Groth16, json).unwrap();
+        assert_eq!(proof.data["pi_a"][2], "1");
+
+        let json: serde_json::Value = serde_json::from_str(SNARKJS_GROTH16_PROOF).unwrap();
+        assert!(Proof::from_snarkjs_json(Protocol::Plonk, json).is_err());
+        assert!(Proof::from_snarkjs_json(Protocol::Groth16, serde_json::json!([1])).is_err());
+    }
+
+    #[test]
+    fn test_signal_hex() {
+        assert_eq!(
+            SignalValue::from_hex("0x1f").unwrap(),
+            SignalValue::from("31")
+        );
+        assert_eq!(
+            SignalValue::from_hex("FF").unwrap(),
+            SignalValue::from("255")
+        );
+        assert!(SignalValue::from_hex("0xzz").is_err());
+
+        // Hex and decimal compare by value, including past i64
+        assert!(SignalValue::from("0xff").matches(&SignalValue::Number(255)));
+        assert!(SignalValue::from("255").matches(&SignalValue::from("0xff")));
+        let big = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000";
+        let big_decimal =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+        assert!(SignalValue::from(big_decimal).matches(&SignalValue::from(big)));
+
+        let outputs = SignalValue::from(vec!["1", "16", "0x2"]);
+        assert!(outputs.matches(&SignalValue::from(vec!["0x1", "0x10", "2"])));
+        assert!(!outputs.matches(&SignalValue::from(vec!["0x1", "0x11", "2"])));
+        assert!(!SignalValue::from("0x10").matches(&SignalValue::from(vec!["16"])));
+        assert!(SignalValue::from("abc").matches(&SignalValue::from("abc")));
+    }
+
+    #[test]
+    fn test_named_params() {
+        let circuit = CircuitConfig::new("mux").with_named_params(vec![
+            ("n".to_string(), 4),
+            ("bits".to_string(), 2),
+            ("a".to_string(), 9),
+        ]);
+        assert_eq!(circuit.param_values(), vec![4, 2, 9]);
+
+        // Names survive serde, in order
+        let json = serde_json::to_string(&circuit).unwrap();
+        assert!(json.contains(r#""named_params":{"n":4,"bits":2,"a":9}"#));
+        let parsed: CircuitConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, circuit);
+
+        let duplicate = r#"{"name": "m", "file": "m.circom", "template": "M",
+            "named_params": {"n": 1, "n": 2}}"#;
+        assert!(serde_json::from_str::<CircuitConfig>(duplicate).is_err());
+
+        let positional = circuit.with_params(vec![1]);
+        assert!(positional.named_params.is_empty());
+        assert_eq!(positional.param_values(), vec![1]);
+    }
+
+    #[test]
+    fn test_circuit_config_validate() {
+        assert!(CircuitConfig::new("multiplier").validate().is_ok());
+        assert!(
+            CircuitConfig::new("mux")
+                .with_template("Mux_1$")
+                .validate()
+                .is_ok()
+        );
+
+        // `new` derives the template from the name, which may not be usable
+        let err = CircuitConfig::new("my-circuit").validate().unwrap_err();
+        assert!(matches!(err, CircomkitError::InvalidConfig(_)));
+        assert!(err.to_string().contains("'my-circuit' is not a valid"));
+        assert!(
+            CircuitConfig::new("test")
+                .with_template("1Test")
+                .validate()
+                .is_err()
+        );
+        assert!(
+            CircuitConfig::new("test")
+                .with_template("")
+                .validate()
+                .is_err()
+        );
+
+        let err = CircuitConfig::new("test")
+            .with_file("test.cir")
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains(".circom extension"));
+
+        // Optimization levels are kept as given and checked here
+        let circuit = CircuitConfig::new("test").with_optimization(7);
+        assert_eq!(circuit.optimization, Some(7));
+        let err = circuit.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("optimization level must be 0, 1, or 2")
+        );
+        assert!(
+            CircuitConfig::new("test")
+                .with_optimization(2)
+                .validate()
+                .is_ok()
+        );
+
+        // An absolute file replaces the extension check, but must exist
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let circuit = CircuitConfig::new("test").with_file("test");
+        assert!(
+            circuit
+                .clone()
+                .with_absolute_file(file.path())
+                .validate()
+                .is_ok()
+        );
+        let err = circuit
+            .with_absolute_file("/nonexistent/test.circom")
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_stage_timings_total() {
+        assert_eq!(StageTimings::default().total(), Duration::ZERO);
+
+        let timings = StageTimings {
+            compile: Some(Duration::from_millis(120)),
+            witness: Some(Duration::from_millis(30)),
+            ..Default::default()
+        };
+        assert_eq!(timings.total(), Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_format_diff() {
+        let result = WitnessTestResult {
+            passed: false,
+            outputs: HashMap::from([
+                ("ok".to_string(), SignalValue::from(1)),
+                ("out".to_string(), SignalValue::from("6")),
+                ("arr".to_string(), SignalValue::from(vec![1, 3, 4])),
+            ]),
+            expected: Some(HashMap::from([
+                ("ok".to_string(), SignalValue::from("1")),
+                ("out".to_string(), SignalValue::from(5)),
+                ("arr".to_string(), SignalValue::from(vec![1, 2, 5])),
+                ("gone".to_string(), SignalValue::from(7)),
+            ])),
+            error: None,
+            tolerated: Vec::new(),
+            durations: StageTimings::default(),
+        };
+
+        assert_eq!(
+            result.format_diff(),
+            "signal  expected  actual\n\
+             arr[0]  1         1\n\
+             arr[1]  2         3  <-\n\
+             arr[2]  5         4\n\
+             gone    7         (missing)  <-\n\
+             out     5         6  <-"
+        );
+
+        let passing = WitnessTestResult {
+            expected: Some(HashMap::from([("ok".to_string(), SignalValue::from(1))])),
+            ..result
+        };
+        assert_eq!(passing.format_diff(), "");
+
+        // Signals within their tolerance aren't listed
+        let tolerances = HashMap::from([("out".to_string(), num_bigint::BigUint::from(1u32))]);
+        let within = WitnessTestResult::compare_within(
+            HashMap::from([
+                ("out".to_string(), SignalValue::from(6)),
+                ("ok".to_string(), SignalValue::from(2)),
+            ]),
+            HashMap::from([
+                ("out".to_string(), SignalValue::from(5)),
+                ("ok".to_string(), SignalValue::from(1)),
+            ]),
+            &tolerances,
+            Prime::Bn128,
+            StageTimings::default(),
+        );
+        assert_eq!(within.tolerated, ["out"]);
+        assert_eq!(
+            within.format_diff(),
+            "signal  expected  actual\n\
+             ok      1         2  <-"
+        );
+    }
+
+    #[test]
+    fn test_info_delta() {
+        let baseline = CircuitInfo {
+            constraints: 100,
+            private_inputs: 2,
+            public_inputs: 1,
+            public_outputs: 1,
+            labels: 10,
+        };
+        let current = CircuitInfo {
+            constraints: 64,
+            private_inputs: 3,
+            labels: 20,
+            ..baseline.clone()
+        };
+
+        assert_eq!(
+            InfoDelta::between(&baseline, &current),
+            InfoDelta {
+                constraints: -36,
+                inputs: 1,
+                outputs: 0,
+            }
+        );
+        assert_eq!(
+            InfoDelta::between(&baseline, &baseline),
+            InfoDelta::default()
+        );
+    }
+
+    #[test]
+    fn test_supports_prime() {
+        let cells = [
+            (Protocol::Groth16, Prime::Bn128, true),
+            (Protocol::Groth16, Prime::Bls12381, true),
+            (Protocol::Groth16, Prime::Goldilocks, false),
+            (Protocol::Plonk, Prime::Bn128, true),
+            (Protocol::Plonk, Prime::Bls12381, true),
+            (Protocol::Plonk, Prime::Goldilocks, false),
+            (Protocol::Fflonk, Prime::Bn128, true),
+            (Protocol::Fflonk, Prime::Bls12381, false),
+            (Protocol::Fflonk, Prime::Goldilocks, false),
+        ];
+
+        for (protocol, prime, supported) in cells {
+            assert_eq!(
+                protocol.supports_prime(prime),
+                supported,
+                "{} over {}",
+                protocol,
+                prime
+            );
+            assert_eq!(protocol.check_prime(prime).is_ok(), supported);
+        }
+
+        match Protocol::Fflonk.check_prime(Prime::Goldilocks) {
+            Err(CircomkitError::InvalidConfig(msg)) => assert!(msg.contains("supported: bn128")),
+            other => panic!("Expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_prime_from_str() {
+        assert_eq!("BN254".parse::<Prime>().unwrap(), Prime::Bn128);
+        assert_eq!("bls12-381".parse::<Prime>().unwrap(), Prime::Bls12381);
+        assert_eq!("Goldilocks".parse::<Prime>().unwrap(), Prime::Goldilocks);
+        assert!(matches!(
+            "pallas".parse::<Prime>(),
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+
+        for prime in [Prime::Bn128, Prime::Bls12381, Prime::Goldilocks] {
+            assert_eq!(prime.to_string().parse::<Prime>().unwrap(), prime);
+        }
+    }
+
+    #[test]
+    fn test_signal_value_from_json() {
+        let value = serde_json::json!([1, "2", [18446744073709551615u64]]);
+        assert_eq!(
+            SignalValue::try_from(value).unwrap().as_string(),
+            "[1, 2, [18446744073709551615]]"
+        );
+
+        assert!(SignalValue::try_from(serde_json::json!(1.5)).is_err());
+        assert!(SignalValue::try_from(serde_json::json!({"a": 1})).is_err());
+        assert!(SignalValue::try_from(serde_json::json!(null)).is_err());
+    }
+
+    #[test]
+    fn test_signal_value_field_ops() {
+        let p = Prime::Bn128.modulus();
+        let minus_one = SignalValue::single(&p - 1u32);
+        let (one, two) = (SignalValue::single(1), SignalValue::Number(2));
+
+        assert_eq!(
+            SignalValue::add(&minus_one, &two, Prime::Bn128).unwrap(),
+            one
+        );
+        assert_eq!(
+            SignalValue::sub(&one, &two, Prime::Bn128).unwrap(),
+            minus_one
+        );
+        assert_eq!(SignalValue::neg(&one, Prime::Bn128).unwrap(), minus_one);
+        assert_eq!(
+            SignalValue::neg(&SignalValue::single(0), Prime::Bn128).unwrap(),
+            SignalValue::single(0)
+        );
+        assert_eq!(
+            SignalValue::mul(&minus_one, &minus_one, Prime::Bn128).unwrap(),
+            one
+        );
+        // Hex inputs, results in decimal
+        assert_eq!(
+            SignalValue::mul(&"0x10".into(), &two, Prime::Goldilocks).unwrap(),
+            SignalValue::single(32)
+        );
+
+        // Element-wise on arrays of the same shape
+        let a = SignalValue::array([1, 2]);
+        assert_eq!(
+            SignalValue::add(&a, &SignalValue::array([3, 4]), Prime::Bn128).unwrap(),
+            SignalValue::array([4, 6])
+        );
+        assert!(SignalValue::add(&a, &SignalValue::array([3]), Prime::Bn128).is_err());
+        assert!(SignalValue::add(&a, &one, Prime::Bn128).is_err());
+        assert!(SignalValue::add(&"x".into(), &one, Prime::Bn128).is_err());
+    }
+
+    #[test]
+    fn test_field_distance() {
+        let p = Prime::Bn128.modulus();
+        let distance = |a: SignalValue, e: SignalValue| a.field_distance(&e, Prime::Bn128);
+
+        assert_eq!(distance(5.into(), 3.into()), Some(2u32.into()));
+        assert_eq!(distance(3.into(), 5.into()), Some(2u32.into()));
+        // Wrapping around zero
+        assert_eq!(
+            distance(SignalValue::single(&p - 1u32), 1.into()),
+            Some(2u32.into())
+        );
+        assert_eq!(
+            distance(SignalValue::array([1, 9]), SignalValue::array([2, 6])),
+            Some(3u32.into())
+        );
+        assert_eq!(distance(SignalValue::array([1]), 1.into()), None);
+        assert_eq!(distance("x".into(), 1.into()), None);
+
+        let outputs = CircuitSignals::from([
+            ("near".to_string(), SignalValue::from(99)),
+            ("far".to_string(), SignalValue::from(90)),
+        ]);
+        let expected = CircuitSignals::from([
+            ("near".to_string(), SignalValue::from(100)),
+            ("far".to_string(), SignalValue::from(100)),
+        ]);
+        let tolerances = HashMap::from([
+            ("near".to_string(), 1u32.into()),
+            ("far".to_string(), 1u32.into()),
+        ]);
+        let result = WitnessTestResult::compare_within(
+            outputs,
+            expected,
+            &tolerances,
+            Prime::Bn128,
+            StageTimings::default(),
+        );
+        assert!(!result.passed);
+        let error = result.error.unwrap();
+        assert!(!error.contains("'near'"));
+        assert!(error.contains("Signal 'far': expected 100, got 90 (off by 10, tolerance 1)"));
+    }
+
+    #[test]
+    fn test_tool_report_missing() {
+        let report = ToolReport {
+            circom: Some("2.1.9".to_string()),
+            snarkjs: None,
+            node: None,
+            warnings: Vec::new(),
+        };
+        assert_eq!(report.missing(), ["snarkjs", "node"]);
+        assert!(matches!(
+            report.require_all(),
+            Err(CircomkitError::ToolNotFound { tool }) if tool == "snarkjs, node"
+        ));
+    }
+
+    #[test]
+    fn test_compatibility_warning() {
+        assert_eq!(CompatibilityWarning::check("circom", "2.1.9"), None);
+        assert_eq!(CompatibilityWarning::check("snarkjs", "0.7.4"), None);
+        assert_eq!(CompatibilityWarning::check("node", "22.1.0"), None);
+        assert_eq!(CompatibilityWarning::check("circom", "garbage"), None);
+
+        let warning = CompatibilityWarning::check("circom", "2.0.9").unwrap();
+        assert_eq!(
+            warning.to_string(),
+            "circom 2.0.9 is outside the supported range >=2.1.0, <3.0.0"
+        );
+        let warning = CompatibilityWarning::check("snarkjs", "0.8.0").unwrap();
+        assert_eq!(warning.supported, ">=0.7.0, <0.8.0");
+    }
+
+    #[test]
+    fn test_vkey_num_public_groth16() {
+        let vkey: VerificationKey = serde_json::from_str(
+            r#"{
+                "protocol": "groth16",
+                "curve": "bn128",
+                "nPublic": 2,
+                "vk_alpha_1": ["1", "2", "1"],
+                "IC": [["1", "2", "1"], ["3", "4", "1"], ["5", "6", "1"]]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(vkey.protocol, Protocol::Groth16);
+        assert_eq!(vkey.num_public().unwrap(), 2);
+
+        // Falls back to the IC length when nPublic is absent
+        let vkey: VerificationKey = serde_json::from_str(
+            r#"{"protocol": "groth16", "IC": [["1", "2", "1"], ["3", "4", "1"]]}"#,
+        )
+        .unwrap();
+        assert_eq!(vkey.num_public().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_vkey_num_public_plonk() {
+        let vkey: VerificationKey = serde_json::from_str(
+            r#"{
+                "protocol": "plonk",
+                "curve": "bn128",
+                "nPublic": 3,
+                "power": 4,
+                "k1": "2",
+                "k2": "3"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(vkey.protocol, Protocol::Plonk);
+        assert_eq!(vkey.num_public().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_vkey_num_public_missing() {
+        let vkey: VerificationKey =
+            serde_json::from_str(r#"{"protocol": "plonk", "curve": "bn128"}"#).unwrap();
+
+        assert!(vkey.num_public().is_err());
+    }
+
+    #[test]
+    fn test_public_signals_access() {
+        let public = PublicSignals::new(vec!["1".into(), "0x10".into(), "33".into()]);
+        assert_eq!(public.len(), 3);
+        assert!(!public.is_empty());
+        assert_eq!(&public[2], "33");
+        assert_eq!(public.get(1), Some("0x10"));
+        assert_eq!(public.get(3), None);
+        assert_eq!(public.iter().count(), 3);
+        assert_eq!(
+            (&public).into_iter().collect::<Vec<_>>(),
+            public.as_slice().iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            public.as_field_elements().unwrap(),
+            [1, 16, 33].map(num_bigint::BigInt::from)
+        );
+        assert_eq!(public.clone().into_iter().last().as_deref(), Some("33"));
+
+        let invalid = PublicSignals::new(vec!["x".into()]);
+        assert!(matches!(
+            invalid.as_field_elements(),
+            Err(CircomkitError::InvalidSignals(_))
+        ));
+        assert!(PublicSignals::new(Vec::new()).is_empty());
+    }
 }
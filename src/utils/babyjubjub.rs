@@ -0,0 +1,137 @@
+//! Baby Jubjub curve operations
+//!
+//! Uses circomlib's twisted Edwards form `a*x^2 + y^2 = 1 + d*x^2*y^2` over
+//! the BN128 scalar field, so points computed here match those computed by
+//! `BabyAdd`, `EscalarMulAny`, and `BabyPbk` inside a circuit.
+
+use ark_bn254::Fr;
+use ark_ff::{Field, One, Zero};
+use num_bigint::BigUint;
+
+/// Curve coefficient `a`
+const A: u64 = 168700;
+
+/// Curve coefficient `d`
+const D: u64 = 168696;
+
+/// Coordinates of circomlib's `Base8`, the generator of the prime-order
+/// subgroup
+const BASE8: (&str, &str) = (
+    "5299619240641551281634865583518297030282874472190772894086521144482721001553",
+    "16950150798460657717958625567821834550301663161624707787222815936182638968203",
+);
+
+/// Order of the subgroup generated by [`base_point`]
+pub const BABYJUBJUB_SUBORDER: &str =
+    "2736030358979909402780800718157159386076813972158567259200215660948447373041";
+
+/// Point on Baby Jubjub in affine coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    /// x coordinate
+    pub x: Fr,
+    /// y coordinate
+    pub y: Fr,
+}
+
+impl Point {
+    /// The identity `(0, 1)`
+    pub fn identity() -> Self {
+        Self {
+            x: Fr::zero(),
+            y: Fr::one(),
+        }
+    }
+
+    /// Whether the point satisfies the curve equation
+    pub fn is_on_curve(&self) -> bool {
+        let (x2, y2) = (self.x.square(), self.y.square());
+        Fr::from(A) * x2 + y2 == Fr::one() + Fr::from(D) * x2 * y2
+    }
+}
+
+/// circomlib's `Base8`, the generator public keys are derived from
+pub fn base_point() -> Point {
+    let coordinate = |s: &str| Fr::from(BigUint::parse_bytes(s.as_bytes(), 10).expect("valid"));
+    Point {
+        x: coordinate(BASE8.0),
+        y: coordinate(BASE8.1),
+    }
+}
+
+/// Add two points, as circomlib's `BabyAdd`
+pub fn point_add(p: &Point, q: &Point) -> Point {
+    let x1y2 = p.x * q.y;
+    let y1x2 = p.y * q.x;
+    let dxy = Fr::from(D) * x1y2 * y1x2;
+    let denominator_x = (Fr::one() + dxy).inverse().expect("complete addition law");
+    let denominator_y = (Fr::one() - dxy).inverse().expect("complete addition law");
+
+    Point {
+        x: (x1y2 + y1x2) * denominator_x,
+        y: (p.y * q.y - Fr::from(A) * p.x * q.x) * denominator_y,
+    }
+}
+
+/// Multiply a point by a scalar
+pub fn scalar_mul(point: &Point, scalar: &BigUint) -> Point {
+    let mut result = Point::identity();
+    for i in (0..scalar.bits()).rev() {
+        result = point_add(&result, &result);
+        if scalar.bit(i) {
+            result = point_add(&result, point);
+        }
+    }
+    result
+}
+
+/// Decimal strings of a point's coordinates, ready to use as signal values
+pub fn point_to_strings(point: &Point) -> (String, String) {
+    (point.x.to_string(), point.y.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_point() {
+        let base = base_point();
+        assert!(base.is_on_curve());
+        assert_eq!(
+            point_to_strings(&base),
+            (BASE8.0.to_string(), BASE8.1.to_string())
+        );
+
+        // Base8 generates the prime-order subgroup
+        let order = BigUint::parse_bytes(BABYJUBJUB_SUBORDER.as_bytes(), 10).unwrap();
+        assert_eq!(scalar_mul(&base, &order), Point::identity());
+        assert_ne!(scalar_mul(&base, &(order - 1u32)), Point::identity());
+
+        // circomlibjs `Generator`, whose multiple by 8 is `Base8`
+        let coordinate = |s: &str| Fr::from(BigUint::parse_bytes(s.as_bytes(), 10).unwrap());
+        let generator = Point {
+            x: coordinate(
+                "995203441582195749578291179787384436505546430278305826713579947235728471134",
+            ),
+            y: coordinate(
+                "5472060717959818805561601436314318772137091100104008585924551046643952123905",
+            ),
+        };
+        assert!(generator.is_on_curve());
+        assert_eq!(scalar_mul(&generator, &BigUint::from(8u32)), base);
+    }
+
+    #[test]
+    fn test_scalar_mul() {
+        let base = base_point();
+        assert_eq!(scalar_mul(&base, &BigUint::from(0u32)), Point::identity());
+        assert_eq!(scalar_mul(&base, &BigUint::from(1u32)), base);
+
+        let two = point_add(&base, &base);
+        let three = point_add(&two, &base);
+        assert!(three.is_on_curve());
+        assert_eq!(scalar_mul(&base, &BigUint::from(3u32)), three);
+        assert_eq!(point_add(&base, &Point::identity()), base);
+    }
+}
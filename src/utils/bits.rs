@@ -0,0 +1,79 @@
+//! Bit decomposition utilities matching circomlib's `Num2Bits`/`Bits2Num`
+
+use crate::error::{CircomkitError, Result};
+use num_bigint::BigUint;
+
+/// Decompose a number into `n` little-endian bits
+///
+/// Returns `"0"`/`"1"` strings with the least significant bit first, as
+/// produced by circomlib's `Num2Bits(n)`. Accepts decimal or `0x`-prefixed
+/// hex values.
+///
+/// # Example
+///
+/// ```
+/// use circomkit::utils::num_to_bits;
+///
+/// let bits = num_to_bits("5", 4).unwrap();
+/// assert_eq!(bits, ["1", "0", "1", "0"]);
+/// ```
+pub fn num_to_bits(value: &str, n: usize) -> Result<Vec<String>> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(value.as_bytes(), 10),
+    };
+
+    let num = parsed
+        .ok_or_else(|| CircomkitError::InvalidSignals(format!("Invalid number: {}", value)))?;
+
+    if num.bits() > n as u64 {
+        return Err(CircomkitError::InvalidSignals(format!(
+            "{} does not fit in {} bits",
+            value, n
+        )));
+    }
+
+    Ok((0..n as u64)
+        .map(|i| if num.bit(i) { "1" } else { "0" }.to_string())
+        .collect())
+}
+
+/// Recompose little-endian bits into a decimal number string
+///
+/// The inverse of [`num_to_bits`], matching circomlib's `Bits2Num`. A bit is
+/// set when its value is `"1"`.
+pub fn bits_to_num(bits: &[&str]) -> String {
+    let mut num = BigUint::default();
+    for (i, bit) in bits.iter().enumerate() {
+        if *bit == "1" {
+            num.set_bit(i as u64, true);
+        }
+    }
+    num.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_num_to_bits() {
+        let bits = num_to_bits("5", 8).unwrap();
+        assert_eq!(bits, ["1", "0", "1", "0", "0", "0", "0", "0"]);
+
+        assert_eq!(num_to_bits("0xff", 8).unwrap(), ["1"; 8]);
+        assert!(num_to_bits("256", 8).is_err());
+        assert!(num_to_bits("abc", 8).is_err());
+    }
+
+    #[test]
+    fn test_bits_to_num() {
+        assert_eq!(bits_to_num(&["1", "0", "1", "0", "0", "0", "0", "0"]), "5");
+        assert_eq!(bits_to_num(&[]), "0");
+
+        let value = "18446744073709551615";
+        let bits = num_to_bits(value, 64).unwrap();
+        let bits: Vec<&str> = bits.iter().map(String::as_str).collect();
+        assert_eq!(bits_to_num(&bits), value);
+    }
+}
@@ -0,0 +1,165 @@
+//! circomlib dependency utilities
+
+use crate::error::{CircomkitError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::fs;
+
+/// A circomlib checkout pinned to a git revision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircomlibGit {
+    /// Repository URL
+    pub url: String,
+    /// Branch, tag, or commit to check out
+    pub rev: String,
+}
+
+impl CircomlibGit {
+    /// Create a new pinned circomlib reference
+    pub fn new(url: impl Into<String>, rev: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            rev: rev.into(),
+        }
+    }
+
+    /// Directory this revision is cached in under `cache_root`
+    ///
+    /// The key combines the revision with a short hash of the URL so forks
+    /// pinned to the same tag don't share a checkout.
+    pub fn cache_dir(&self, cache_root: &Path) -> PathBuf {
+        let rev: String = self
+            .rev
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let url_hash = hex::encode(&Sha256::digest(self.url.as_bytes())[..4]);
+
+        cache_root
+            .join("circomlib")
+            .join(format!("{}-{}", rev, url_hash))
+    }
+}
+
+/// Default cache root for fetched dependencies
+pub fn default_cache_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "circomkit")
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+        .unwrap_or_else(|| std::env::temp_dir().join("circomkit"))
+}
+
+/// Find `node_modules/circomlib/circuits` in `start` or any of its ancestors
+pub fn find_circomlib(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join("node_modules").join("circomlib").join("circuits"))
+        .find(|path| path.is_dir())
+}
+
+/// Fetch a pinned circomlib checkout, reusing the cache if present
+///
+/// The repository is checked out to `<cache>/circomlib`, so the returned
+/// directory can be passed to circom with `-l` and circuits can use
+/// `include "circomlib/circuits/...";`.
+pub async fn fetch_circomlib(dep: &CircomlibGit, cache_root: &Path) -> Result<PathBuf> {
+    let cache_dir = dep.cache_dir(cache_root);
+    let checkout = cache_dir.join("circomlib");
+
+    if checkout.join("circuits").exists() {
+        log::debug!("Using cached circomlib {}: {:?}", dep.rev, checkout);
+        return Ok(cache_dir);
+    }
+
+    log::info!("Fetching circomlib {} from {}", dep.rev, dep.url);
+
+    // Clone into a scratch directory first so an interrupted fetch is never
+    // mistaken for a valid cache entry
+    let staging = cache_dir.with_extension(format!("tmp{}", std::process::id()));
+    if staging.exists() {
+        fs::remove_dir_all(&staging).await?;
+    }
+    let staging_checkout = staging.join("circomlib");
+    fs::create_dir_all(&staging_checkout).await?;
+
+    // `fetch --depth 1 <rev>` works for branches, tags, and commit hashes
+    let steps: [&[&str]; 4] = [
+        &["init", "--quiet"],
+        &["remote", "add", "origin", &dep.url],
+        &["fetch", "--quiet", "--depth", "1", "origin", &dep.rev],
+        &["checkout", "--quiet", "FETCH_HEAD"],
+    ];
+
+    for args in steps {
+        if let Err(e) = run_git(&staging_checkout, args) {
+            let _ = fs::remove_dir_all(&staging).await;
+            return Err(e);
+        }
+    }
+
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir).await?;
+    }
+    fs::rename(&staging, &cache_dir).await?;
+
+    log::info!("Fetched circomlib to: {:?}", cache_dir);
+
+    Ok(cache_dir)
+}
+
+/// Run a git command in `dir`
+fn run_git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                CircomkitError::tool_not_found("git")
+            } else {
+                CircomkitError::Io(e)
+            }
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CircomkitError::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: stderr.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_circomlib_searches_upward() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("project").join("circuits");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_circomlib(&nested), None);
+
+        let circomlib = dir.path().join("node_modules/circomlib/circuits");
+        std::fs::create_dir_all(&circomlib).unwrap();
+        assert_eq!(find_circomlib(&nested), Some(circomlib));
+    }
+
+    #[test]
+    fn test_cache_dir_keyed_by_rev() {
+        let root = Path::new("/cache");
+        let a = CircomlibGit::new("https://github.com/iden3/circomlib", "v2.0.5");
+        let b = CircomlibGit::new("https://github.com/iden3/circomlib", "v2.0.4");
+        let fork = CircomlibGit::new("https://github.com/example/circomlib", "v2.0.5");
+
+        assert!(a.cache_dir(root).starts_with("/cache/circomlib"));
+        assert_ne!(a.cache_dir(root), b.cache_dir(root));
+        assert_ne!(a.cache_dir(root), fork.cache_dir(root));
+        assert_eq!(a.cache_dir(root), a.clone().cache_dir(root));
+    }
+}
@@ -0,0 +1,318 @@
+//! Native Groth16 verification over BN128
+//!
+//! Verifies snarkjs proofs in-process instead of starting node for every
+//! `snarkjs groth16 verify`.
+
+use crate::error::{CircomkitError, Result};
+use crate::types::{Protocol, VerificationKey};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{One, PrimeField, Zero};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey, prepare_verifying_key};
+use num_bigint::BigUint;
+use serde_json::Value;
+
+/// Verify a snarkjs Groth16 proof over BN128
+///
+/// `vkey` and `proof` are the JSON objects snarkjs writes to
+/// `verification_key.json` and `proof.json`. Malformed proofs, points off
+/// the curve, and public signals outside the scalar field verify as `false`,
+/// as they do in snarkjs; a malformed verification key is an error.
+///
+/// Use a [`Groth16Verifier`] to check several proofs against one key.
+pub fn verify_groth16(vkey: &Value, proof: &Value, public_signals: &[String]) -> Result<bool> {
+    Groth16Verifier::new(vkey)?.verify(proof, public_signals)
+}
+
+/// Groth16 verifier holding a parsed and prepared BN128 verification key
+///
+/// Preparing the key computes a pairing, so reusing a verifier across
+/// proofs is cheaper than calling [`verify_groth16`] for each.
+pub struct Groth16Verifier {
+    pvk: PreparedVerifyingKey<Bn254>,
+}
+
+impl Groth16Verifier {
+    /// Parse and prepare a snarkjs verification key, failing if it's
+    /// malformed
+    pub fn new(vkey: &Value) -> Result<Self> {
+        Ok(Self {
+            pvk: parse_vkey(vkey)?,
+        })
+    }
+
+    /// Prepare a loaded [`VerificationKey`], failing unless it's a Groth16
+    /// key over BN128
+    pub fn from_verification_key(vkey: &VerificationKey) -> Result<Self> {
+        if vkey.protocol != Protocol::Groth16 {
+            return Err(CircomkitError::verification_failed(format!(
+                "Expected a groth16 verification key, got {}",
+                vkey.protocol
+            )));
+        }
+        match vkey.data.get("curve").and_then(Value::as_str) {
+            None | Some("bn128") => Self::new(&vkey.data),
+            Some(curve) => Err(CircomkitError::verification_failed(format!(
+                "Expected a bn128 verification key, got {}",
+                curve
+            ))),
+        }
+    }
+
+    /// Verify a snarkjs proof, as [`verify_groth16`] does
+    pub fn verify(&self, proof: &Value, public_signals: &[String]) -> Result<bool> {
+        let Some(proof) = parse_proof(proof) else {
+            return Ok(false);
+        };
+        let Some(inputs) = public_signals
+            .iter()
+            .map(|s| canonical::<Fr>(s))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(false);
+        };
+        if inputs.len() + 1 != self.pvk.vk.gamma_abc_g1.len() {
+            return Ok(false);
+        }
+
+        Groth16::<Bn254>::verify_proof(&self.pvk, &proof, &inputs)
+            .map_err(|e| CircomkitError::verification_failed(e.to_string()))
+    }
+}
+
+/// Parse and prepare a snarkjs verification key
+fn parse_vkey(vkey: &Value) -> Result<PreparedVerifyingKey<Bn254>> {
+    let malformed = |field: &str| {
+        CircomkitError::verification_failed(format!("Malformed vkey field: {}", field))
+    };
+    let g1_field = |field: &str| g1(&vkey[field]).ok_or_else(|| malformed(field));
+    let g2_field = |field: &str| g2(&vkey[field]).ok_or_else(|| malformed(field));
+
+    let gamma_abc_g1 = vkey["IC"]
+        .as_array()
+        .and_then(|points| points.iter().map(g1).collect::<Option<Vec<_>>>())
+        .ok_or_else(|| malformed("IC"))?;
+
+    Ok(prepare_verifying_key(&VerifyingKey {
+        alpha_g1: g1_field("vk_alpha_1")?,
+        beta_g2: g2_field("vk_beta_2")?,
+        gamma_g2: g2_field("vk_gamma_2")?,
+        delta_g2: g2_field("vk_delta_2")?,
+        gamma_abc_g1,
+    }))
+}
+
+/// Parse a snarkjs proof, `None` if any point is malformed
+fn parse_proof(proof: &Value) -> Option<Proof<Bn254>> {
+    Some(Proof {
+        a: g1(&proof["pi_a"])?,
+        b: g2(&proof["pi_b"])?,
+        c: g1(&proof["pi_c"])?,
+    })
+}
+
+/// Parse a decimal field element
+fn fq(value: &Value) -> Option<Fq> {
+    canonical(value.as_str()?)
+}
+
+/// Parse a field element in canonical form, a plain decimal below the
+/// modulus
+///
+/// `from_str` would reduce larger or negative values, accepting encodings
+/// snarkjs rejects.
+fn canonical<F: PrimeField>(decimal: &str) -> Option<F> {
+    if decimal.is_empty() || !decimal.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value = BigUint::parse_bytes(decimal.as_bytes(), 10)?;
+    (value < F::MODULUS.into()).then(|| F::from(value))
+}
+
+/// Parse an `[c0, c1]` extension field element
+fn fq2(value: &Value) -> Option<Fq2> {
+    match value.as_array()?.as_slice() {
+        [c0, c1] => Some(Fq2::new(fq(c0)?, fq(c1)?)),
+        _ => None,
+    }
+}
+
+/// Parse a G1 point in snarkjs' `[x, y, z]` form, with `z` 1 or 0 (infinity)
+fn g1(value: &Value) -> Option<G1Affine> {
+    let [x, y, z] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let z = fq(z)?;
+    if z.is_zero() {
+        return Some(G1Affine::identity());
+    }
+    if !z.is_one() {
+        return None;
+    }
+
+    let point = G1Affine::new_unchecked(fq(x)?, fq(y)?);
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+}
+
+/// Parse a G2 point in snarkjs' `[x, y, z]` form over `Fq2`
+fn g2(value: &Value) -> Option<G2Affine> {
+    let [x, y, z] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let z = fq2(z)?;
+    if z.is_zero() {
+        return Some(G2Affine::identity());
+    }
+    if !z.is_one() {
+        return None;
+    }
+
+    let point = G2Affine::new_unchecked(fq2(x)?, fq2(y)?);
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Circomkit, CircomkitConfig};
+    use crate::types::{CircuitConfig, PublicSignals};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use serde_json::json;
+
+    fn g1_json(point: G1Affine) -> Value {
+        json!([point.x.to_string(), point.y.to_string(), "1"])
+    }
+
+    fn g2_json(point: G2Affine) -> Value {
+        json!([
+            [point.x.c0.to_string(), point.x.c1.to_string()],
+            [point.y.c0.to_string(), point.y.c1.to_string()],
+            ["1", "0"]
+        ])
+    }
+
+    /// A verification key and proof satisfying the Groth16 equation for one
+    /// public input, built directly from chosen scalars
+    fn instance(public: u64) -> (Value, Value) {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let (alpha, beta, ic0, ic1, c) = (3u64, 5u64, 7u64, 11u64, 13u64);
+
+        // With gamma = delta = 1: a * 1 = alpha * beta + (ic0 + ic1 * x) + c
+        let a = alpha * beta + ic0 + ic1 * public + c;
+        let mul1 = |k: u64| (g1 * Fr::from(k)).into_affine();
+        let mul2 = |k: u64| (g2 * Fr::from(k)).into_affine();
+
+        let vkey = json!({
+            "protocol": "groth16",
+            "curve": "bn128",
+            "nPublic": 1,
+            "vk_alpha_1": g1_json(mul1(alpha)),
+            "vk_beta_2": g2_json(mul2(beta)),
+            "vk_gamma_2": g2_json(g2),
+            "vk_delta_2": g2_json(g2),
+            "IC": [g1_json(mul1(ic0)), g1_json(mul1(ic1))],
+        });
+        let proof = json!({
+            "pi_a": g1_json(mul1(a)),
+            "pi_b": g2_json(g2),
+            "pi_c": g1_json(mul1(c)),
+            "protocol": "groth16",
+            "curve": "bn128",
+        });
+        (vkey, proof)
+    }
+
+    #[test]
+    fn test_verify_groth16() {
+        let (vkey, proof) = instance(42);
+        assert!(verify_groth16(&vkey, &proof, &["42".to_string()]).unwrap());
+
+        // Wrong, missing, or out-of-field public signals
+        assert!(!verify_groth16(&vkey, &proof, &["43".to_string()]).unwrap());
+        assert!(!verify_groth16(&vkey, &proof, &[]).unwrap());
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        assert!(!verify_groth16(&vkey, &proof, &[modulus.to_string()]).unwrap());
+        // Non-canonical encodings of an accepted signal
+        let reduced = (BigUint::parse_bytes(modulus.as_bytes(), 10).unwrap() + 42u32).to_string();
+        for encoding in [reduced.as_str(), "-0", "+42", "042x"] {
+            assert!(!verify_groth16(&vkey, &proof, &[encoding.to_string()]).unwrap());
+        }
+
+        // A proof coordinate offset by the base field modulus
+        let mut offset = proof.clone();
+        let x = BigUint::parse_bytes(proof["pi_c"][0].as_str().unwrap().as_bytes(), 10).unwrap();
+        offset["pi_c"][0] = json!((x + BigUint::from(Fq::MODULUS)).to_string());
+        assert!(!verify_groth16(&vkey, &offset, &["42".to_string()]).unwrap());
+
+        // A proof point off the curve
+        let mut tampered = proof.clone();
+        tampered["pi_a"][1] = json!("1");
+        assert!(!verify_groth16(&vkey, &tampered, &["42".to_string()]).unwrap());
+
+        // A malformed verification key
+        let mut broken = vkey.clone();
+        broken["IC"] = json!("nope");
+        assert!(verify_groth16(&broken, &proof, &["42".to_string()]).is_err());
+        assert!(Groth16Verifier::new(&broken).is_err());
+    }
+
+    #[test]
+    fn test_verifier_reuses_key() {
+        let (vkey, proof) = instance(42);
+        let verifier = Groth16Verifier::new(&vkey).unwrap();
+        for _ in 0..2 {
+            assert!(verifier.verify(&proof, &["42".to_string()]).unwrap());
+            assert!(!verifier.verify(&proof, &["43".to_string()]).unwrap());
+        }
+        assert!(!verifier.verify(&json!({}), &["42".to_string()]).unwrap());
+    }
+
+    #[test]
+    fn test_verifier_from_verification_key() {
+        let (vkey, _) = instance(0);
+        let key: VerificationKey = serde_json::from_value(vkey.clone()).unwrap();
+        let verifier = Groth16Verifier::from_verification_key(&key).unwrap();
+
+        // The prepared key agrees with verify_groth16 on distinct proofs
+        for public in 1..=5 {
+            let (_, proof) = instance(public);
+            for signal in [public, public + 1] {
+                let signals = [signal.to_string()];
+                assert_eq!(
+                    verifier.verify(&proof, &signals).unwrap(),
+                    verify_groth16(&vkey, &proof, &signals).unwrap()
+                );
+            }
+            assert!(verifier.verify(&proof, &[public.to_string()]).unwrap());
+        }
+
+        let mut plonk = key.clone();
+        plonk.protocol = Protocol::Plonk;
+        assert!(Groth16Verifier::from_verification_key(&plonk).is_err());
+        let mut bls = key;
+        bls.data["curve"] = json!("bls12381");
+        assert!(Groth16Verifier::from_verification_key(&bls).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_uses_native_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let (vkey, proof) = instance(7);
+        std::fs::create_dir_all(dir.path().join("stub")).unwrap();
+        std::fs::write(dir.path().join("stub/groth16_vkey.json"), vkey.to_string()).unwrap();
+
+        // A missing snarkjs proves verification never shells out
+        let config = CircomkitConfig::default()
+            .with_build_dir(dir.path())
+            .with_snarkjs_path("/nonexistent/snarkjs");
+        let circomkit = Circomkit::new(config).unwrap();
+        let circuit = CircuitConfig::new("stub");
+        let proof = serde_json::from_value(proof).unwrap();
+
+        let valid = PublicSignals(vec!["7".to_string()]);
+        assert!(circomkit.verify(&circuit, &proof, &valid).await.unwrap());
+        let invalid = PublicSignals(vec!["8".to_string()]);
+        assert!(!circomkit.verify(&circuit, &proof, &invalid).await.unwrap());
+    }
+}
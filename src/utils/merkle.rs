@@ -0,0 +1,253 @@
+//! Poseidon Merkle trees for Merkle inclusion circuits
+//!
+//! Nodes are `Poseidon(left, right)` over the BN128 scalar field, with empty
+//! leaves set to zero, the layout used by circomlib-based Merkle verifiers
+//! that take `pathElements` and `pathIndices` inputs.
+
+use crate::error::{CircomkitError, Result};
+use crate::types::{CircuitSignals, SignalValue};
+use crate::utils::poseidon::poseidon_hash;
+use crate::utils::signals::signal_array;
+use num_bigint::BigInt;
+
+/// Maximum supported tree depth
+pub const MERKLE_MAX_DEPTH: usize = 32;
+
+/// Binary Merkle tree hashed with circomlib-compatible Poseidon
+///
+/// # Example
+///
+/// ```
+/// use circomkit::utils::PoseidonMerkleTree;
+/// use num_bigint::BigInt;
+///
+/// let leaves: Vec<BigInt> = (1..=3).map(BigInt::from).collect();
+/// let tree = PoseidonMerkleTree::new(2, &leaves).unwrap();
+/// let proof = tree.proof(1).unwrap();
+/// assert!(proof.verify().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoseidonMerkleTree {
+    /// Nodes per level with a non-empty subtree, from the leaves up to the root
+    levels: Vec<Vec<BigInt>>,
+    /// Root of an empty subtree at each level
+    zeros: Vec<BigInt>,
+}
+
+/// Inclusion proof for one leaf of a [`PoseidonMerkleTree`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleProof {
+    /// The proven leaf
+    pub leaf: BigInt,
+    /// Root of the tree
+    pub root: BigInt,
+    /// Sibling at each level, from the leaves up
+    pub path_elements: SignalValue,
+    /// Position at each level, `0` when the path node is the left child
+    pub path_indices: SignalValue,
+}
+
+impl PoseidonMerkleTree {
+    /// Build a tree of the given depth, padding the leaves with zeros
+    ///
+    /// Only the given leaves are stored and hashed; empty subtrees reuse
+    /// precomputed zero hashes, so deep trees are cheap.
+    pub fn new(depth: usize, leaves: &[BigInt]) -> Result<Self> {
+        if depth == 0 || depth > MERKLE_MAX_DEPTH {
+            return Err(CircomkitError::InvalidSignals(format!(
+                "Merkle tree depth must be 1 to {}, got {}",
+                MERKLE_MAX_DEPTH, depth
+            )));
+        }
+        let capacity = 1usize << depth;
+        if leaves.len() > capacity {
+            return Err(CircomkitError::InvalidSignals(format!(
+                "{} leaves don't fit in a tree of depth {}",
+                leaves.len(),
+                depth
+            )));
+        }
+
+        let mut zeros = vec![BigInt::from(0)];
+        let mut levels = vec![leaves.to_vec()];
+        for level in 0..depth {
+            let zero = &zeros[level];
+            let parents = levels[level]
+                .chunks(2)
+                .map(|pair| {
+                    let right = pair.get(1).unwrap_or(zero);
+                    poseidon_hash(&[pair[0].clone(), right.clone()])
+                })
+                .collect::<Result<Vec<_>>>()?;
+            zeros.push(poseidon_hash(&[zero.clone(), zero.clone()])?);
+            levels.push(parents);
+        }
+
+        Ok(Self { levels, zeros })
+    }
+
+    /// Depth of the tree
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Leaves the tree was built from, without the zero padding
+    pub fn leaves(&self) -> &[BigInt] {
+        &self.levels[0]
+    }
+
+    /// Root of the tree
+    pub fn root(&self) -> &BigInt {
+        self.node(self.depth(), 0)
+    }
+
+    /// Node at a level, counting from the leaves
+    fn node(&self, level: usize, index: usize) -> &BigInt {
+        self.levels[level].get(index).unwrap_or(&self.zeros[level])
+    }
+
+    /// Inclusion proof for the leaf at `index`
+    ///
+    /// Indices past the given leaves prove a zero leaf.
+    pub fn proof(&self, index: usize) -> Result<MerkleProof> {
+        let capacity = 1usize << self.depth();
+        if index >= capacity {
+            return Err(CircomkitError::InvalidSignals(format!(
+                "Leaf index {} out of range for a tree of {} leaves",
+                index, capacity
+            )));
+        }
+
+        let (elements, indices): (Vec<_>, Vec<_>) = (0..self.depth())
+            .map(|level| {
+                let position = index >> level;
+                (self.node(level, position ^ 1).clone(), position & 1)
+            })
+            .unzip();
+
+        Ok(MerkleProof {
+            leaf: self.node(0, index).clone(),
+            root: self.root().clone(),
+            path_elements: signal_array(&elements),
+            path_indices: signal_array(&indices),
+        })
+    }
+}
+
+impl MerkleProof {
+    /// Recompute the root from the leaf and path, and compare it to `root`
+    pub fn verify(&self) -> Result<bool> {
+        let (SignalValue::Array(elements), SignalValue::Array(indices)) =
+            (&self.path_elements, &self.path_indices)
+        else {
+            return Ok(false);
+        };
+        if elements.len() != indices.len() {
+            return Ok(false);
+        }
+
+        let mut node = self.leaf.clone();
+        for (sibling, index) in elements.iter().zip(indices) {
+            let Ok(sibling) = sibling.normalize().as_string().parse::<BigInt>() else {
+                return Ok(false);
+            };
+            node = match index.as_string().as_str() {
+                "0" => poseidon_hash(&[node, sibling])?,
+                "1" => poseidon_hash(&[sibling, node])?,
+                _ => return Ok(false),
+            };
+        }
+
+        Ok(node == self.root)
+    }
+
+    /// Signals named `leaf`, `root`, `pathElements`, and `pathIndices`
+    ///
+    /// Rename or drop entries to match the verifier circuit's inputs.
+    pub fn to_signals(&self) -> CircuitSignals {
+        CircuitSignals::from([
+            ("leaf".to_string(), SignalValue::single(&self.leaf)),
+            ("root".to_string(), SignalValue::single(&self.root)),
+            ("pathElements".to_string(), self.path_elements.clone()),
+            ("pathIndices".to_string(), self.path_indices.clone()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i64]) -> Vec<BigInt> {
+        values.iter().map(|&v| BigInt::from(v)).collect()
+    }
+
+    fn dec(value: &str) -> BigInt {
+        BigInt::parse_bytes(value.as_bytes(), 10).unwrap()
+    }
+
+    #[test]
+    fn test_merkle_vectors() {
+        // Reference values from circomlibjs `poseidon`
+        let tree = PoseidonMerkleTree::new(1, &ints(&[1, 2])).unwrap();
+        assert_eq!(
+            *tree.root(),
+            dec("7853200120776062878684798364095072458815029376092732009249414926327459813530")
+        );
+
+        // Empty subtrees hash to the usual Poseidon zero values
+        let empty = PoseidonMerkleTree::new(1, &[]).unwrap();
+        assert_eq!(
+            *empty.root(),
+            dec("14744269619966411208579211824598458697587494354926760081771325075741142829156")
+        );
+        let empty = PoseidonMerkleTree::new(2, &[]).unwrap();
+        assert_eq!(
+            *empty.root(),
+            dec("7423237065226347324353380772367382631490014989348495481811164164159255474657")
+        );
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        let leaves = ints(&[1, 2, 3]);
+        let tree = PoseidonMerkleTree::new(2, &leaves).unwrap();
+        assert_eq!(tree.leaves(), leaves.as_slice());
+
+        let proof = tree.proof(2).unwrap();
+        let h01 = poseidon_hash(&ints(&[1, 2])).unwrap();
+        assert_eq!(proof.leaf, BigInt::from(3));
+        assert_eq!(proof.path_elements, signal_array(&[BigInt::from(0), h01]));
+        assert_eq!(proof.path_indices, signal_array(&[0, 1]));
+        assert!(proof.verify().unwrap());
+
+        for index in 0..4 {
+            assert!(tree.proof(index).unwrap().verify().unwrap());
+        }
+        assert_eq!(tree.proof(3).unwrap().leaf, BigInt::from(0));
+
+        // Deep trees only hash the populated path
+        let deep = PoseidonMerkleTree::new(MERKLE_MAX_DEPTH, &leaves).unwrap();
+        assert!(deep.proof(1).unwrap().verify().unwrap());
+
+        let signals = proof.to_signals();
+        assert_eq!(signals["root"], SignalValue::single(tree.root()));
+        assert_eq!(signals["pathIndices"], proof.path_indices);
+
+        // A proof for the wrong leaf
+        let mut forged = proof.clone();
+        forged.leaf = BigInt::from(4);
+        assert!(!forged.verify().unwrap());
+    }
+
+    #[test]
+    fn test_merkle_bounds() {
+        assert!(PoseidonMerkleTree::new(0, &[]).is_err());
+        assert!(PoseidonMerkleTree::new(MERKLE_MAX_DEPTH + 1, &[]).is_err());
+        assert!(PoseidonMerkleTree::new(1, &ints(&[1, 2, 3])).is_err());
+
+        let tree = PoseidonMerkleTree::new(1, &ints(&[1])).unwrap();
+        assert!(tree.proof(1).is_ok());
+        assert!(tree.proof(2).is_err());
+    }
+}
@@ -1,7 +1,39 @@
 //! Utility functions for Circomkit
 
+mod babyjubjub;
+mod bits;
+mod circomlib;
+#[cfg(feature = "native-verify")]
+mod groth16;
+mod merkle;
+mod poseidon;
 mod ptau;
+mod r1cs;
 mod signals;
+mod sym;
 
-pub use ptau::{PtauInfo, download_ptau, get_recommended_ptau};
-pub use signals::{signal_array, signals};
+pub use babyjubjub::{
+    BABYJUBJUB_SUBORDER, Point, base_point, point_add, point_to_strings, scalar_mul,
+};
+pub use bits::{bits_to_num, num_to_bits};
+pub use circomlib::{CircomlibGit, default_cache_dir, fetch_circomlib, find_circomlib};
+#[cfg(feature = "native-verify")]
+pub use groth16::{Groth16Verifier, verify_groth16};
+pub use merkle::{MERKLE_MAX_DEPTH, MerkleProof, PoseidonMerkleTree};
+pub use poseidon::{POSEIDON_MAX_INPUTS, poseidon_hash, poseidon_hash_to_field};
+pub use ptau::{
+    PTAU_DIR_ENV, PTAU_DOWNLOAD_ATTEMPTS, PtauHeader, PtauInfo, download_ptau,
+    download_ptau_with_attempts, fetch_ptau, generate_ptau, get_recommended_ptau, list_ptau_files,
+    ptau_dir, read_ptau_header, verify_ptau,
+};
+#[cfg(test)]
+pub(crate) use r1cs::r1cs_bytes;
+pub use r1cs::{
+    Constraint, LinearCombination, MAX_LISTED_CONSTRAINTS, R1csHeader, read_r1cs_constraints,
+    read_r1cs_header,
+};
+pub use signals::{
+    SignalBuilder, bytes_to_field, field_to_bytes, hash_to_field, normalize_signals, parse_inputs,
+    parse_signals, serialize_signals, signal_array, signals, signals_from_json,
+};
+pub use sym::{Symbol, SymbolTable, read_symbol_table};
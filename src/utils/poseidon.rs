@@ -0,0 +1,109 @@
+//! Poseidon hashing utilities
+//!
+//! Uses circomlib's Poseidon parameters over the BN128 scalar field, so hashes
+//! computed here match those computed by `Poseidon(n)` inside a circuit.
+
+use crate::error::{CircomkitError, Result};
+use ark_bn254::Fr;
+use light_poseidon::{Poseidon, PoseidonHasher};
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// Maximum number of inputs supported by a single Poseidon invocation
+pub const POSEIDON_MAX_INPUTS: usize = 12;
+
+/// Convert a big integer to a field element, reducing modulo the field order
+fn to_field(value: &BigInt) -> Fr {
+    let (sign, magnitude) = value.to_bytes_be();
+    let element = Fr::from(BigUint::from_bytes_be(&magnitude));
+    if sign == Sign::Minus {
+        -element
+    } else {
+        element
+    }
+}
+
+/// Hash field elements with circomlib-compatible Poseidon
+///
+/// Inputs are reduced modulo the BN128 scalar field. Between 1 and
+/// [`POSEIDON_MAX_INPUTS`] inputs are supported.
+///
+/// # Example
+///
+/// ```
+/// use circomkit::utils::poseidon_hash;
+/// use num_bigint::BigInt;
+///
+/// let hash = poseidon_hash(&[BigInt::from(1), BigInt::from(2)]).unwrap();
+/// ```
+pub fn poseidon_hash(inputs: &[BigInt]) -> Result<BigInt> {
+    if inputs.is_empty() || inputs.len() > POSEIDON_MAX_INPUTS {
+        return Err(CircomkitError::InvalidSignals(format!(
+            "Poseidon supports 1 to {} inputs, got {}",
+            POSEIDON_MAX_INPUTS,
+            inputs.len()
+        )));
+    }
+
+    let mut hasher = Poseidon::<Fr>::new_circom(inputs.len())
+        .map_err(|e| CircomkitError::Other(e.to_string()))?;
+
+    let elements: Vec<Fr> = inputs.iter().map(to_field).collect();
+    let hash = hasher
+        .hash(&elements)
+        .map_err(|e| CircomkitError::Other(e.to_string()))?;
+
+    Ok(BigInt::from(BigUint::from(hash)))
+}
+
+/// Hash field elements with Poseidon and return the decimal string
+///
+/// The result can be used directly as a signal value.
+pub fn poseidon_hash_to_field(inputs: &[BigInt]) -> Result<String> {
+    poseidon_hash(inputs).map(|hash| hash.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i64]) -> Vec<BigInt> {
+        values.iter().map(|&v| BigInt::from(v)).collect()
+    }
+
+    #[test]
+    fn test_poseidon_vectors() {
+        // Reference values from circomlibjs `poseidon`
+        assert_eq!(
+            poseidon_hash_to_field(&ints(&[1])).unwrap(),
+            "18586133768512220936620570745912940619677854269274689475585506675881198879027"
+        );
+        assert_eq!(
+            poseidon_hash_to_field(&ints(&[1, 2])).unwrap(),
+            "7853200120776062878684798364095072458815029376092732009249414926327459813530"
+        );
+        assert_eq!(
+            poseidon_hash_to_field(&ints(&[1, 2, 3])).unwrap(),
+            "6542985608222806190361240322586112750744169038454362455181422643027100751666"
+        );
+    }
+
+    #[test]
+    fn test_poseidon_reduces_inputs() {
+        let modulus = BigInt::parse_bytes(
+            b"21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(
+            poseidon_hash(&[modulus + 1]).unwrap(),
+            poseidon_hash(&ints(&[1])).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_poseidon_input_count() {
+        assert!(poseidon_hash(&[]).is_err());
+        assert!(poseidon_hash(&ints(&[0; POSEIDON_MAX_INPUTS + 1])).is_err());
+    }
+}
@@ -1,8 +1,16 @@
 //! PTAU (Powers of Tau) file utilities
 
+use crate::core::CircomkitConfig;
 use crate::error::{CircomkitError, Result};
+use crate::types::Prime;
+use crate::utils::R1csHeader;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
 /// Information about a PTAU file
 #[derive(Debug, Clone)]
@@ -11,48 +19,302 @@ pub struct PtauInfo {
     pub power: u8,
     /// File name
     pub filename: String,
-    /// URL to download from
+    /// URL to download from, empty if the file is generated locally
     pub url: String,
     /// Expected file size in bytes
     pub size: u64,
+    /// Curve the file is for
+    pub prime: Prime,
 }
 
+impl PtauInfo {
+    /// Whether the file is generated locally rather than downloaded
+    ///
+    /// There is no public snarkjs-format ceremony for BLS12-381, so those
+    /// files are generated with a single local contribution. They are fine
+    /// for testing but must not be used for production keys.
+    pub fn is_generated(&self) -> bool {
+        self.url.is_empty()
+    }
+}
+
+/// Header of a snarkjs `.ptau` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtauHeader {
+    /// File format version
+    pub version: u32,
+    /// Curve the ceremony ran over
+    pub curve: Prime,
+    /// Power of 2 of the largest circuit domain the file supports
+    pub power: u32,
+    /// Power of the ceremony the file was taken from
+    pub ceremony_power: u32,
+}
+
+impl PtauHeader {
+    /// Check that the file can set up a circuit over `prime`
+    ///
+    /// Catches a PTAU for the wrong curve, or one too small for the
+    /// circuit's constraints, before snarkjs fails with a vaguer message.
+    pub fn check_circuit(&self, r1cs: &R1csHeader, prime: Prime) -> Result<()> {
+        if self.curve != prime {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "PTAU is for {} but the circuit uses {}",
+                self.curve, prime
+            )));
+        }
+
+        let needed = r1cs.ptau_power();
+        if needed > self.power {
+            return Err(CircomkitError::InvalidConfig(format!(
+                "PTAU of power {} is too small: the circuit's {} constraints need power {} or more",
+                self.power, r1cs.n_constraints, needed
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Section id of the PTAU header
+const HEADER_SECTION: u32 = 1;
+
+/// Base field moduli identifying the curve of a PTAU file
+const CURVE_MODULI: [(Prime, &str); 2] = [
+    (
+        Prime::Bn128,
+        "21888242871839275222246405745257275088696311157297823662689037894645226208583",
+    ),
+    (
+        Prime::Bls12381,
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559787",
+    ),
+];
+
 /// Hermez ceremony PTAU files
 const HERMEZ_PTAU_BASE: &str = "https://storage.googleapis.com/zkevm/ptau";
 
-/// Get information about the recommended PTAU for a given number of constraints
-pub fn get_recommended_ptau(num_constraints: usize) -> PtauInfo {
+/// Environment variable overriding the PTAU directory
+pub const PTAU_DIR_ENV: &str = "CIRCOMKIT_PTAU_DIR";
+
+/// Directory PTAU files are stored in
+///
+/// `CIRCOMKIT_PTAU_DIR` takes precedence over `configured`.
+pub fn ptau_dir(configured: &Path) -> PathBuf {
+    dir_override(PTAU_DIR_ENV, configured)
+}
+
+/// The directory in `env_var` if set and non-empty, otherwise `configured`
+fn dir_override(env_var: &str, configured: &Path) -> PathBuf {
+    std::env::var_os(env_var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| configured.to_path_buf())
+}
+
+/// Get information about the recommended PTAU for a given number of
+/// constraints over `prime`
+///
+/// BN128 files come from the Hermez ceremony; BLS12-381 files are generated
+/// locally (see [`PtauInfo::is_generated`]). Goldilocks circuits aren't
+/// proven with a powers-of-tau setup and return an error.
+pub fn get_recommended_ptau(num_constraints: usize, prime: Prime) -> Result<PtauInfo> {
     // Calculate minimum power needed
     let power = (num_constraints as f64).log2().ceil() as u8;
-    let power = power.max(8).min(28);
-
-    let filename = format!("powersOfTau28_hez_final_{:02}.ptau", power);
-    let url = format!("{}/{}", HERMEZ_PTAU_BASE, filename);
-
-    // Approximate sizes (actual sizes vary)
-    let size = match power {
-        8 => 8_388_608,
-        9 => 16_777_216,
-        10 => 33_554_432,
-        11 => 67_108_864,
-        12 => 134_217_728,
-        13 => 268_435_456,
-        14 => 536_870_912,
-        15 => 1_073_741_824,
-        16 => 2_147_483_648,
-        _ => 0, // Unknown size for larger powers
-    };
+    let power = power.clamp(8, 28);
+
+    match prime {
+        Prime::Bn128 => {
+            let filename = format!("powersOfTau28_hez_final_{:02}.ptau", power);
+            let url = format!("{}/{}", HERMEZ_PTAU_BASE, filename);
 
-    PtauInfo {
-        power,
-        filename,
-        url,
-        size,
+            // Approximate sizes (actual sizes vary)
+            let size = match power {
+                8 => 8_388_608,
+                9 => 16_777_216,
+                10 => 33_554_432,
+                11 => 67_108_864,
+                12 => 134_217_728,
+                13 => 268_435_456,
+                14 => 536_870_912,
+                15 => 1_073_741_824,
+                16 => 2_147_483_648,
+                _ => 0, // Unknown size for larger powers
+            };
+
+            Ok(PtauInfo {
+                power,
+                filename,
+                url,
+                size,
+                prime,
+            })
+        }
+        Prime::Bls12381 => Ok(PtauInfo {
+            power,
+            filename: format!("powersOfTau_bls12381_local_{:02}.ptau", power),
+            url: String::new(),
+            size: 0,
+            prime,
+        }),
+        Prime::Goldilocks => Err(CircomkitError::InvalidConfig(
+            "Goldilocks has no pairing-friendly curve and needs no PTAU file".to_string(),
+        )),
+    }
+}
+
+/// Download or generate the PTAU file described by `info` into `output_dir`
+///
+/// Files that can't be downloaded are generated with `config`'s snarkjs;
+/// downloads make up to `attempts` attempts (see
+/// [`download_ptau_with_attempts`]).
+pub async fn fetch_ptau(
+    info: &PtauInfo,
+    output_dir: &Path,
+    config: &CircomkitConfig,
+    attempts: u32,
+) -> Result<PathBuf> {
+    if info.is_generated() {
+        generate_ptau(info, output_dir, config).await
+    } else {
+        download_ptau_with_attempts(info, output_dir, attempts).await
     }
 }
 
-/// Download a PTAU file
+/// Generate a PTAU file locally with a single random contribution
+///
+/// Only suitable for testing; see [`PtauInfo::is_generated`]. snarkjs is run
+/// as `config` runs it, with its `snarkjs_flags` and `node_options`. The
+/// file is written under a temporary name and only moved into place once
+/// complete, so an interrupted run never leaves a truncated PTAU behind.
+pub async fn generate_ptau(
+    info: &PtauInfo,
+    output_dir: &Path,
+    config: &CircomkitConfig,
+) -> Result<PathBuf> {
+    let output_path = output_dir.join(&info.filename);
+
+    if output_path.exists() {
+        log::info!("PTAU file already exists: {:?}", output_path);
+        return Ok(output_path);
+    }
+
+    fs::create_dir_all(output_dir).await?;
+    log::warn!(
+        "Generating a local {} PTAU; do not use it for production keys",
+        info.prime
+    );
+
+    let stage = |n: u8| output_dir.join(format!("{}.{}", info.filename, n));
+    let partial_path = output_dir.join(format!("{}.part", info.filename));
+    let entropy = hex::encode(Sha256::digest(format!(
+        "{:?}{}",
+        std::time::SystemTime::now(),
+        std::process::id()
+    )));
+
+    let steps: [Vec<String>; 3] = [
+        vec![
+            "powersoftau".into(),
+            "new".into(),
+            info.prime.to_string(),
+            info.power.to_string(),
+            stage(0).to_string_lossy().into(),
+        ],
+        vec![
+            "powersoftau".into(),
+            "contribute".into(),
+            stage(0).to_string_lossy().into(),
+            stage(1).to_string_lossy().into(),
+            "--name=circomkit".into(),
+            format!("-e={}", entropy),
+        ],
+        vec![
+            "powersoftau".into(),
+            "prepare".into(),
+            "phase2".into(),
+            stage(1).to_string_lossy().into(),
+            partial_path.to_string_lossy().into(),
+        ],
+    ];
+
+    let result = steps
+        .into_iter()
+        .try_for_each(|args| run_snarkjs(config, args));
+
+    // Intermediate files are only needed to build the final one
+    for n in 0..2 {
+        let _ = fs::remove_file(stage(n)).await;
+    }
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial_path).await;
+        return Err(e);
+    }
+    fs::rename(&partial_path, &output_path).await?;
+
+    log::info!("Generated PTAU at: {:?}", output_path);
+    Ok(output_path)
+}
+
+/// Run a snarkjs command as `config` does, failing on a non-zero exit
+fn run_snarkjs(config: &CircomkitConfig, args: Vec<String>) -> Result<()> {
+    let (snarkjs, args) = config.snarkjs_args(args);
+    let output = config
+        .node_process(&snarkjs)
+        .args(args)
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                CircomkitError::tool_not_found(&snarkjs)
+            } else {
+                CircomkitError::Io(e)
+            }
+        })?;
+
+    if !output.status.success() {
+        return Err(CircomkitError::CommandFailed {
+            command: snarkjs,
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Attempts [`download_ptau`] makes before giving up
+pub const PTAU_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry of a download, doubled for each later one
+const PTAU_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Longest wait between download attempts
+const PTAU_MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Wait before download attempt `attempt`, counting from 2
+fn retry_delay(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(2));
+    PTAU_RETRY_DELAY
+        .saturating_mul(factor)
+        .min(PTAU_MAX_RETRY_DELAY)
+}
+
+/// Download a PTAU file, making up to [`PTAU_DOWNLOAD_ATTEMPTS`] attempts
 pub async fn download_ptau(info: &PtauInfo, output_dir: &Path) -> Result<PathBuf> {
+    download_ptau_with_attempts(info, output_dir, PTAU_DOWNLOAD_ATTEMPTS).await
+}
+
+/// Download a PTAU file, retrying failed attempts with exponential backoff
+///
+/// The file is downloaded to `<filename>.part`, resuming where an earlier
+/// attempt stopped, and only moved into place once its header matches
+/// `info`'s curve and power and its length matches the sections the header
+/// lists. A partial file that fails the check is deleted so the next attempt
+/// starts clean. Retries wait at most a minute. After `attempts` failures,
+/// the last one is returned as `CommandFailed`.
+pub async fn download_ptau_with_attempts(
+    info: &PtauInfo,
+    output_dir: &Path,
+    attempts: u32,
+) -> Result<PathBuf> {
     let output_path = output_dir.join(&info.filename);
 
     // Check if already exists
@@ -63,51 +325,147 @@ pub async fn download_ptau(info: &PtauInfo, output_dir: &Path) -> Result<PathBuf
 
     // Create output directory if needed
     fs::create_dir_all(output_dir).await?;
+    let partial_path = output_dir.join(format!("{}.part", info.filename));
 
     log::info!("Downloading PTAU from: {}", info.url);
     log::info!("This may take a while for larger files...");
 
-    // Use curl or wget to download
-    let output = std::process::Command::new("curl")
-        .arg("-L")
-        .arg("-o")
-        .arg(&output_path)
-        .arg("--progress-bar")
-        .arg(&info.url)
-        .output()
-        .map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                // Try wget instead
-                return CircomkitError::tool_not_found("curl (or wget)");
+    let attempts = attempts.max(1);
+    let mut last_error = None;
+    for attempt in 1..=attempts {
+        if let Some(error) = &last_error {
+            let delay = retry_delay(attempt);
+            log::warn!(
+                "PTAU download attempt {} of {} failed, retrying in {:?}: {}",
+                attempt - 1,
+                attempts,
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+
+        let downloaded = match download_file(&info.url, &partial_path) {
+            Ok(()) => check_download(info, &partial_path).await,
+            // Retrying won't install the tool
+            Err(e @ CircomkitError::ToolNotFound { .. }) => return Err(e),
+            Err(e) => Err(e),
+        };
+        match downloaded {
+            Ok(()) => {
+                fs::rename(&partial_path, &output_path).await?;
+                log::info!("Downloaded PTAU to: {:?}", output_path);
+                return Ok(output_path);
             }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(match last_error {
+        Some(e @ CircomkitError::CommandFailed { .. }) => e,
+        e => CircomkitError::CommandFailed {
+            command: "curl".to_string(),
+            exit_code: -1,
+            stderr: e.map(|e| e.to_string()).unwrap_or_default(),
+        },
+    })
+}
+
+/// Download `url` to `path` with curl, or wget if curl is missing or fails,
+/// resuming a partial file
+///
+/// When curl fails and wget isn't installed, curl's error is returned.
+fn download_file(url: &str, path: &Path) -> Result<()> {
+    let mut curl = std::process::Command::new("curl");
+    curl.args(["-L", "-f", "-C", "-", "--progress-bar", "-o"])
+        .arg(path)
+        .arg(url);
+    let curl_error = match run_download("curl", &mut curl) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    let mut wget = std::process::Command::new("wget");
+    wget.args(["-c", "--show-progress", "-O"])
+        .arg(path)
+        .arg(url);
+    match (run_download("wget", &mut wget), curl_error) {
+        (Err(CircomkitError::ToolNotFound { .. }), CircomkitError::ToolNotFound { .. }) => {
+            Err(CircomkitError::tool_not_found("curl (or wget)"))
+        }
+        (Err(CircomkitError::ToolNotFound { .. }), curl_error) => Err(curl_error),
+        (result, _) => result,
+    }
+}
+
+/// Run a download command, failing on a non-zero exit
+fn run_download(program: &str, cmd: &mut std::process::Command) -> Result<()> {
+    let output = cmd.output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            CircomkitError::tool_not_found(program)
+        } else {
             CircomkitError::Io(e)
-        })?;
+        }
+    })?;
 
     if !output.status.success() {
-        // Try wget as fallback
-        let output = std::process::Command::new("wget")
-            .arg("-O")
-            .arg(&output_path)
-            .arg("--show-progress")
-            .arg(&info.url)
-            .output()
-            .map_err(CircomkitError::Io)?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CircomkitError::CommandFailed {
-                command: "wget".to_string(),
-                exit_code: output.status.code().unwrap_or(-1),
-                stderr: stderr.to_string(),
-            });
-        }
+        return Err(CircomkitError::CommandFailed {
+            command: program.to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
     }
+    Ok(())
+}
 
-    log::info!("Downloaded PTAU to: {:?}", output_path);
+/// Check that a downloaded file is a complete PTAU for `info`'s curve and
+/// power, deleting it if not
+async fn check_download(info: &PtauInfo, path: &Path) -> Result<()> {
+    let checked = async {
+        let header = read_ptau_header(path).await?;
+        if header.curve != info.prime || header.power < u32::from(info.power) {
+            return Err(CircomkitError::Other(format!(
+                "Downloaded PTAU is a power {} {} file, expected power {} {}",
+                header.power, header.curve, info.power, info.prime
+            )));
+        }
 
-    Ok(output_path)
+        let expected = ptau_sections_len(path).await?;
+        let actual = fs::metadata(path).await?.len();
+        if actual != expected {
+            return Err(CircomkitError::Other(format!(
+                "Downloaded PTAU is {} bytes, but its sections add up to {}",
+                actual, expected
+            )));
+        }
+        Ok(())
+    }
+    .await;
+    if checked.is_err() {
+        let _ = fs::remove_file(path).await;
+    }
+    checked
 }
 
+/// Length of a `.ptau` file according to its section headers
+///
+/// Walks the section table without reading the sections, so a truncated
+/// file gives a length past its end rather than an error.
+async fn ptau_sections_len(path: &Path) -> Result<u64> {
+    let mut file = fs::File::open(path).await?;
+
+    // Magic, version, and section count
+    let mut len = 12u64;
+    file.seek(SeekFrom::Start(8)).await?;
+    let n_sections = file.read_u32_le().await?;
+
+    for _ in 0..n_sections {
+        file.seek(SeekFrom::Start(len + 4)).await?;
+        let section_size = file.read_u64_le().await?;
+        len = len.saturating_add(12).saturating_add(section_size);
+    }
+    Ok(len)
+}
 /// Verify a PTAU file integrity
 pub async fn verify_ptau(path: &Path) -> Result<bool> {
     if !path.exists() {
@@ -131,6 +489,56 @@ pub async fn verify_ptau(path: &Path) -> Result<bool> {
     Ok(true)
 }
 
+/// Read the header of a `.ptau` file without loading the powers
+pub async fn read_ptau_header(path: &Path) -> Result<PtauHeader> {
+    if !path.exists() {
+        return Err(CircomkitError::PtauNotFound(path.to_path_buf()));
+    }
+
+    let invalid =
+        |msg: &str| CircomkitError::Other(format!("Invalid ptau file {:?}: {}", path, msg));
+
+    let mut file = fs::File::open(path).await?;
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).await?;
+    if &magic != b"ptau" {
+        return Err(invalid("bad magic"));
+    }
+    let version = file.read_u32_le().await?;
+    let n_sections = file.read_u32_le().await?;
+
+    for _ in 0..n_sections {
+        let section_type = file.read_u32_le().await?;
+        let section_size = file.read_u64_le().await?;
+
+        if section_type != HEADER_SECTION {
+            file.seek(SeekFrom::Current(section_size as i64)).await?;
+            continue;
+        }
+
+        let field_size = file.read_u32_le().await?;
+        let mut modulus = vec![0u8; field_size as usize];
+        file.read_exact(&mut modulus).await?;
+        let modulus = BigUint::from_bytes_le(&modulus);
+
+        let curve = CURVE_MODULI
+            .iter()
+            .find(|(_, q)| BigUint::parse_bytes(q.as_bytes(), 10).as_ref() == Some(&modulus))
+            .map(|(prime, _)| *prime)
+            .ok_or_else(|| invalid("unknown curve"))?;
+
+        return Ok(PtauHeader {
+            version,
+            curve,
+            power: file.read_u32_le().await?,
+            ceremony_power: file.read_u32_le().await?,
+        });
+    }
+
+    Err(invalid("missing header section"))
+}
+
 /// Get all PTAU files in a directory
 pub async fn list_ptau_files(dir: &Path) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
@@ -158,19 +566,259 @@ mod tests {
 
     #[test]
     fn test_get_recommended_ptau() {
-        let info = get_recommended_ptau(100);
+        let info = get_recommended_ptau(100, Prime::Bn128).unwrap();
         assert_eq!(info.power, 8); // 2^7 = 128 > 100
 
-        let info = get_recommended_ptau(1000);
+        let info = get_recommended_ptau(1000, Prime::Bn128).unwrap();
         assert_eq!(info.power, 10); // 2^10 = 1024 > 1000
 
-        let info = get_recommended_ptau(1_000_000);
+        let info = get_recommended_ptau(1_000_000, Prime::Bn128).unwrap();
         assert_eq!(info.power, 20); // 2^20 = 1048576 > 1000000
     }
 
+    #[test]
+    fn test_get_recommended_ptau_per_curve() {
+        let info = get_recommended_ptau(1000, Prime::Bls12381).unwrap();
+        assert_eq!(info.power, 10);
+        assert_eq!(info.prime, Prime::Bls12381);
+        assert!(info.is_generated());
+        assert!(info.filename.contains("bls12381"));
+
+        assert!(
+            !get_recommended_ptau(1000, Prime::Bn128)
+                .unwrap()
+                .is_generated()
+        );
+        assert!(matches!(
+            get_recommended_ptau(1000, Prime::Goldilocks),
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_ptau_missing_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = get_recommended_ptau(100, Prime::Bls12381).unwrap();
+
+        let config = CircomkitConfig::default().with_snarkjs_path("/nonexistent/snarkjs");
+        let result = fetch_ptau(&info, dir.path(), &config, 1).await;
+        assert!(matches!(result, Err(CircomkitError::ToolNotFound { .. })));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_generate_ptau_uses_config() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // snarkjs logging its flags and NODE_OPTIONS and writing every ptau
+        // it's given, failing `prepare` if `$FAIL` exists
+        let dir = tempfile::tempdir().unwrap();
+        let snarkjs = dir.path().join("snarkjs");
+        let log = dir.path().join("log");
+        std::fs::write(
+            &snarkjs,
+            format!(
+                "#!/bin/sh\n\
+                 echo \"$NODE_OPTIONS $*\" >> {log}\n\
+                 [ \"$2\" = prepare ] && [ -e {fail} ] && exit 1\n\
+                 for a in \"$@\"; do case \"$a\" in *.ptau*) echo x >> \"$a\";; esac; done\n",
+                log = log.display(),
+                fail = dir.path().join("fail").display(),
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&snarkjs, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let config = CircomkitConfig::default()
+            .with_snarkjs_path(&snarkjs)
+            .with_snarkjs_flag("-v")
+            .with_node_options("--max-old-space-size=64");
+        let info = get_recommended_ptau(100, Prime::Bls12381).unwrap();
+        let out = dir.path().join("out");
+
+        // A failed run leaves nothing behind
+        std::fs::write(dir.path().join("fail"), "").unwrap();
+        assert!(generate_ptau(&info, &out, &config).await.is_err());
+        assert_eq!(std::fs::read_dir(&out).unwrap().count(), 0);
+
+        std::fs::remove_file(dir.path().join("fail")).unwrap();
+        let path = generate_ptau(&info, &out, &config).await.unwrap();
+        assert_eq!(path, out.join(&info.filename));
+        assert_eq!(std::fs::read_dir(&out).unwrap().count(), 1);
+
+        let log = std::fs::read_to_string(&log).unwrap();
+        assert!(log.lines().count() > 0);
+        for line in log.lines() {
+            assert!(line.starts_with("--max-old-space-size=64 powersoftau"));
+            assert!(line.ends_with(" -v"));
+        }
+    }
+
+    #[test]
+    fn test_ptau_dir_env_override() {
+        // A variable no other test reads, so setting it can't race them
+        const VAR: &str = "CIRCOMKIT_TEST_PTAU_DIR";
+
+        unsafe { std::env::remove_var(VAR) };
+        assert_eq!(dir_override(VAR, Path::new("ptau")), PathBuf::from("ptau"));
+
+        unsafe { std::env::set_var(VAR, "/ci/ptau") };
+        assert_eq!(
+            dir_override(VAR, Path::new("ptau")),
+            PathBuf::from("/ci/ptau")
+        );
+
+        unsafe { std::env::set_var(VAR, "") };
+        assert_eq!(dir_override(VAR, Path::new("ptau")), PathBuf::from("ptau"));
+
+        unsafe { std::env::remove_var(VAR) };
+    }
+
+    /// Build a minimal ptau file with a header section after an empty one
+    fn ptau_bytes(curve: Prime, power: u32) -> Vec<u8> {
+        let (_, modulus) = CURVE_MODULI.iter().find(|(p, _)| *p == curve).unwrap();
+        let modulus = BigUint::parse_bytes(modulus.as_bytes(), 10)
+            .unwrap()
+            .to_bytes_le();
+        let field_size = modulus.len().div_ceil(8) * 8;
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(field_size as u32).to_le_bytes());
+        header.extend_from_slice(&modulus);
+        header.resize(4 + field_size, 0);
+        header.extend_from_slice(&power.to_le_bytes());
+        header.extend_from_slice(&28u32.to_le_bytes());
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"ptau");
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        // tau points section (type 2), 4 bytes of payload
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&4u64.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&HEADER_SECTION.to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_read_ptau_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.ptau");
+
+        std::fs::write(&path, ptau_bytes(Prime::Bn128, 12)).unwrap();
+        let header = read_ptau_header(&path).await.unwrap();
+        assert_eq!(header.curve, Prime::Bn128);
+        assert_eq!(header.power, 12);
+        assert_eq!(header.ceremony_power, 28);
+
+        std::fs::write(&path, ptau_bytes(Prime::Bls12381, 9)).unwrap();
+        let header = read_ptau_header(&path).await.unwrap();
+        assert_eq!((header.curve, header.power), (Prime::Bls12381, 9));
+
+        std::fs::write(&path, b"nope").unwrap();
+        assert!(read_ptau_header(&path).await.is_err());
+        assert!(matches!(
+            read_ptau_header(&dir.path().join("missing.ptau")).await,
+            Err(CircomkitError::PtauNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_circuit() {
+        let ptau = PtauHeader {
+            version: 1,
+            curve: Prime::Bn128,
+            power: 10,
+            ceremony_power: 28,
+        };
+        let r1cs = |n_constraints| R1csHeader {
+            field_size: 32,
+            prime: BigUint::from(0u32),
+            n_wires: 4,
+            n_pub_out: 1,
+            n_pub_in: 1,
+            n_prv_in: 1,
+            n_labels: 4,
+            n_constraints,
+        };
+
+        // 1021 constraints + 2 public + 1 fit exactly in 2^10
+        assert!(ptau.check_circuit(&r1cs(1021), Prime::Bn128).is_ok());
+        let err = ptau.check_circuit(&r1cs(1022), Prime::Bn128).unwrap_err();
+        assert!(err.to_string().contains("need power 11"));
+        assert!(matches!(
+            ptau.check_circuit(&r1cs(10), Prime::Bls12381),
+            Err(CircomkitError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_download_ptau_with_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.ptau");
+        let out = dir.path().join("out");
+        let info = |url: &Path| PtauInfo {
+            power: 10,
+            filename: "test.ptau".to_string(),
+            url: format!("file://{}", url.display()),
+            size: 0,
+            prime: Prime::Bn128,
+        };
+
+        // A file failing the header check is deleted, not installed
+        std::fs::write(&source, ptau_bytes(Prime::Bls12381, 12)).unwrap();
+        match download_ptau_with_attempts(&info(&source), &out, 1).await {
+            Err(CircomkitError::CommandFailed { stderr, .. }) => {
+                assert!(stderr.contains("expected power 10 bn128"));
+            }
+            other => panic!("Expected CommandFailed, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_dir(&out).unwrap().count(), 0);
+
+        // A missing source fails with curl's error
+        assert!(matches!(
+            download_ptau_with_attempts(&info(&dir.path().join("missing")), &out, 1).await,
+            Err(CircomkitError::CommandFailed { .. })
+        ));
+
+        // A partial download is resumed
+        let bytes = ptau_bytes(Prime::Bn128, 12);
+        std::fs::write(&source, &bytes).unwrap();
+        std::fs::write(out.join("test.ptau.part"), &bytes[..8]).unwrap();
+        let path = download_ptau_with_attempts(&info(&source), &out, 1)
+            .await
+            .unwrap();
+        assert_eq!(path, out.join("test.ptau"));
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        assert!(!out.join("test.ptau.part").exists());
+
+        // A file not matching its section table is rejected even with a
+        // valid header
+        let out = dir.path().join("mismatched");
+        std::fs::write(&source, [bytes.as_slice(), &[0]].concat()).unwrap();
+        match download_ptau_with_attempts(&info(&source), &out, 1).await {
+            Err(CircomkitError::CommandFailed { stderr, .. }) => {
+                assert!(stderr.contains("sections add up to"));
+            }
+            other => panic!("Expected CommandFailed, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_dir(&out).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+        assert_eq!(retry_delay(7), PTAU_MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), PTAU_MAX_RETRY_DELAY);
+    }
+
     #[test]
     fn test_ptau_info_url() {
-        let info = get_recommended_ptau(1000);
+        let info = get_recommended_ptau(1000, Prime::Bn128).unwrap();
         assert!(info.url.contains("powersOfTau28_hez_final_10.ptau"));
     }
 }
@@ -0,0 +1,416 @@
+//! R1CS file utilities
+
+use crate::error::{CircomkitError, Result};
+use crate::types::CircuitInfo;
+use num_bigint::{BigInt, BigUint};
+use std::collections::BTreeMap;
+use std::io::SeekFrom;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Section id of the R1CS header
+const HEADER_SECTION: u32 = 1;
+
+/// Section id of the R1CS constraints
+const CONSTRAINTS_SECTION: u32 = 2;
+
+/// Most constraints [`read_r1cs_constraints`] lists by default
+pub const MAX_LISTED_CONSTRAINTS: u32 = 10_000;
+
+/// Linear combination of wires, keyed by wire id
+///
+/// Coefficients above half the prime are stored as their negative, so
+/// `p - 1` reads as `-1`.
+pub type LinearCombination = BTreeMap<u32, BigInt>;
+
+/// An R1CS constraint `a * b - c = 0`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Constraint {
+    /// Left factor
+    pub a: LinearCombination,
+    /// Right factor
+    pub b: LinearCombination,
+    /// Product
+    pub c: LinearCombination,
+    /// Signal names of the wires used, where known
+    pub names: BTreeMap<u32, String>,
+}
+
+impl Constraint {
+    /// Render a linear combination using signal names, e.g. `2*main.a - main.b`
+    fn format_lc(&self, lc: &LinearCombination) -> String {
+        if lc.is_empty() {
+            return "0".to_string();
+        }
+
+        let mut out = String::new();
+        for (i, (wire, coefficient)) in lc.iter().enumerate() {
+            let negative = coefficient.sign() == num_bigint::Sign::Minus;
+            let magnitude = coefficient.magnitude();
+            match (i, negative) {
+                (0, true) => out.push('-'),
+                (0, false) => {}
+                (_, true) => out.push_str(" - "),
+                (_, false) => out.push_str(" + "),
+            }
+
+            // Wire 0 is the constant 1
+            let name = match (*wire, self.names.get(wire)) {
+                (0, _) => None,
+                (_, Some(name)) => Some(name.clone()),
+                (_, None) => Some(format!("w{}", wire)),
+            };
+            match name {
+                None => out.push_str(&magnitude.to_string()),
+                Some(name) if *magnitude == BigUint::from(1u8) => out.push_str(&name),
+                Some(name) => out.push_str(&format!("{}*{}", magnitude, name)),
+            }
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({}) * ({}) - ({}) = 0",
+            self.format_lc(&self.a),
+            self.format_lc(&self.b),
+            self.format_lc(&self.c)
+        )
+    }
+}
+
+/// Header of a circom `.r1cs` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct R1csHeader {
+    /// Size of a field element in bytes
+    pub field_size: u32,
+    /// Prime field modulus
+    pub prime: BigUint,
+    /// Number of wires, including the constant `1` wire
+    pub n_wires: u32,
+    /// Number of public outputs
+    pub n_pub_out: u32,
+    /// Number of public inputs
+    pub n_pub_in: u32,
+    /// Number of private inputs
+    pub n_prv_in: u32,
+    /// Number of labels (signals before optimization)
+    pub n_labels: u64,
+    /// Number of constraints
+    pub n_constraints: u32,
+}
+
+impl R1csHeader {
+    /// Wire ids of the main component's outputs
+    ///
+    /// Wire 0 is the constant `1`; outputs, public inputs, and private
+    /// inputs follow in that order.
+    pub fn output_wires(&self) -> std::ops::Range<u32> {
+        1..1 + self.n_pub_out
+    }
+
+    /// Wire ids of the main component's public inputs
+    pub fn public_input_wires(&self) -> std::ops::Range<u32> {
+        let start = 1 + self.n_pub_out;
+        start..start + self.n_pub_in
+    }
+
+    /// Smallest PTAU power snarkjs accepts for this circuit
+    ///
+    /// The setup domain holds every constraint plus one per public signal
+    /// and the constant wire. PLONK and FFLONK domains can be larger still.
+    pub fn ptau_power(&self) -> u32 {
+        let domain = self.n_constraints as u64 + self.n_pub_in as u64 + self.n_pub_out as u64 + 1;
+        domain.next_power_of_two().trailing_zeros()
+    }
+
+    /// Wire ids of the main component's inputs (public then private)
+    pub fn input_wires(&self) -> std::ops::Range<u32> {
+        let start = 1 + self.n_pub_out;
+        start..start + self.n_pub_in + self.n_prv_in
+    }
+
+    /// Sizes of the circuit, as `snarkjs r1cs info` reports them
+    pub fn circuit_info(&self) -> CircuitInfo {
+        CircuitInfo {
+            constraints: self.n_constraints as usize,
+            private_inputs: self.n_prv_in as usize,
+            public_inputs: self.n_pub_in as usize,
+            public_outputs: self.n_pub_out as usize,
+            labels: self.n_labels as usize,
+        }
+    }
+}
+
+/// Read the header of an `.r1cs` file without loading the constraints
+pub async fn read_r1cs_header(path: &Path) -> Result<R1csHeader> {
+    if !path.exists() {
+        return Err(CircomkitError::CircuitNotFound(path.to_path_buf()));
+    }
+
+    let invalid =
+        |msg: &str| CircomkitError::Other(format!("Invalid r1cs file {:?}: {}", path, msg));
+
+    let mut file = File::open(path).await?;
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).await?;
+    if &magic != b"r1cs" {
+        return Err(invalid("bad magic"));
+    }
+    let _version = file.read_u32_le().await?;
+    let n_sections = file.read_u32_le().await?;
+
+    for _ in 0..n_sections {
+        let section_type = file.read_u32_le().await?;
+        let section_size = file.read_u64_le().await?;
+
+        if section_type != HEADER_SECTION {
+            file.seek(SeekFrom::Current(section_size as i64)).await?;
+            continue;
+        }
+
+        let field_size = file.read_u32_le().await?;
+        let mut prime = vec![0u8; field_size as usize];
+        file.read_exact(&mut prime).await?;
+
+        return Ok(R1csHeader {
+            field_size,
+            prime: BigUint::from_bytes_le(&prime),
+            n_wires: file.read_u32_le().await?,
+            n_pub_out: file.read_u32_le().await?,
+            n_pub_in: file.read_u32_le().await?,
+            n_prv_in: file.read_u32_le().await?,
+            n_labels: file.read_u64_le().await?,
+            n_constraints: file.read_u32_le().await?,
+        });
+    }
+
+    Err(invalid("missing header section"))
+}
+
+/// Read the constraints of an `.r1cs` file
+///
+/// Fails without reading them if the circuit has more than `max`
+/// constraints. Names are left empty; fill them from the circuit's
+/// [`SymbolTable`](crate::utils::SymbolTable).
+pub async fn read_r1cs_constraints(path: &Path, max: u32) -> Result<Vec<Constraint>> {
+    let header = read_r1cs_header(path).await?;
+    if header.n_constraints > max {
+        return Err(CircomkitError::Other(format!(
+            "Circuit has {} constraints, more than the {} that can be listed",
+            header.n_constraints, max
+        )));
+    }
+
+    let invalid =
+        |msg: &str| CircomkitError::Other(format!("Invalid r1cs file {:?}: {}", path, msg));
+
+    let mut file = File::open(path).await?;
+    file.seek(SeekFrom::Start(8)).await?;
+    let n_sections = file.read_u32_le().await?;
+
+    for _ in 0..n_sections {
+        let section_type = file.read_u32_le().await?;
+        let section_size = file.read_u64_le().await?;
+
+        if section_type != CONSTRAINTS_SECTION {
+            file.seek(SeekFrom::Current(section_size as i64)).await?;
+            continue;
+        }
+
+        let mut section = vec![0u8; section_size as usize];
+        file.read_exact(&mut section).await?;
+        let mut reader = SectionReader {
+            bytes: &section,
+            field_size: header.field_size as usize,
+            prime: BigInt::from(header.prime.clone()),
+        };
+
+        return (0..header.n_constraints)
+            .map(|_| {
+                Some(Constraint {
+                    a: reader.linear_combination()?,
+                    b: reader.linear_combination()?,
+                    c: reader.linear_combination()?,
+                    names: BTreeMap::new(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid("truncated constraints section"));
+    }
+
+    Err(invalid("missing constraints section"))
+}
+
+/// Cursor over the bytes of a constraints section
+struct SectionReader<'a> {
+    bytes: &'a [u8],
+    field_size: usize,
+    prime: BigInt,
+}
+
+impl SectionReader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        if self.bytes.len() < n {
+            return None;
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn linear_combination(&mut self) -> Option<LinearCombination> {
+        let n_terms = self.u32()?;
+        (0..n_terms)
+            .map(|_| {
+                let wire = self.u32()?;
+                let value = BigInt::from(BigUint::from_bytes_le(self.take(self.field_size)?));
+                let coefficient = if value.clone() * 2 > self.prime {
+                    value - &self.prime
+                } else {
+                    value
+                };
+                Some((wire, coefficient))
+            })
+            .collect()
+    }
+}
+
+/// Build a minimal BN128 r1cs file for tests, with a header section after
+/// an empty constraints section
+///
+/// The circuit has one wire per signal plus the constant, each with a label.
+#[cfg(test)]
+pub(crate) fn r1cs_bytes(
+    n_pub_out: u32,
+    n_pub_in: u32,
+    n_prv_in: u32,
+    n_constraints: u32,
+) -> Vec<u8> {
+    let n_wires = 1 + n_pub_out + n_pub_in + n_prv_in;
+    let mut prime = crate::types::Prime::Bn128.modulus().to_bytes_le();
+    prime.resize(32, 0);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&32u32.to_le_bytes());
+    header.extend_from_slice(&prime);
+    for n in [n_wires, n_pub_out, n_pub_in, n_prv_in] {
+        header.extend_from_slice(&n.to_le_bytes());
+    }
+    header.extend_from_slice(&u64::from(n_wires).to_le_bytes());
+    header.extend_from_slice(&n_constraints.to_le_bytes());
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"r1cs");
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    // constraints section, 4 bytes of payload
+    bytes.extend_from_slice(&CONSTRAINTS_SECTION.to_le_bytes());
+    bytes.extend_from_slice(&4u64.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&HEADER_SECTION.to_le_bytes());
+    bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&header);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_r1cs_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.r1cs");
+        std::fs::write(&path, r1cs_bytes(1, 2, 3, 3)).unwrap();
+
+        let header = read_r1cs_header(&path).await.unwrap();
+        assert_eq!(header.field_size, 32);
+        assert_eq!(header.prime, crate::types::Prime::Bn128.modulus());
+        assert_eq!(header.n_wires, 7);
+        assert_eq!(header.n_constraints, 3);
+        assert_eq!(header.n_labels, 7);
+        assert_eq!(header.output_wires(), 1..2);
+        assert_eq!(header.input_wires(), 2..7);
+        assert_eq!(header.public_input_wires(), 2..4);
+        // 3 constraints + 3 public signals + 1
+        assert_eq!(header.ptau_power(), 3);
+
+        std::fs::write(&path, b"nope").unwrap();
+        assert!(read_r1cs_header(&path).await.is_err());
+    }
+
+    /// Build an r1cs file over the prime 97 with one constraint
+    /// `(2*w1 - w2) * (w3) - (1) = 0`
+    fn constraint_r1cs_bytes() -> Vec<u8> {
+        let term = |wire: u32, value: u8| {
+            let mut bytes = wire.to_le_bytes().to_vec();
+            let mut coefficient = [0u8; 8];
+            coefficient[0] = value;
+            bytes.extend_from_slice(&coefficient);
+            bytes
+        };
+        let mut constraints = Vec::new();
+        constraints.extend_from_slice(&2u32.to_le_bytes());
+        constraints.extend(term(1, 2));
+        constraints.extend(term(2, 96));
+        constraints.extend_from_slice(&1u32.to_le_bytes());
+        constraints.extend(term(3, 1));
+        constraints.extend_from_slice(&1u32.to_le_bytes());
+        constraints.extend(term(0, 1));
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&8u32.to_le_bytes());
+        header.extend_from_slice(&97u64.to_le_bytes());
+        for n in [4u32, 1, 0, 2] {
+            header.extend_from_slice(&n.to_le_bytes());
+        }
+        header.extend_from_slice(&4u64.to_le_bytes());
+        header.extend_from_slice(&1u32.to_le_bytes());
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"r1cs");
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&CONSTRAINTS_SECTION.to_le_bytes());
+        bytes.extend_from_slice(&(constraints.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&constraints);
+        bytes.extend_from_slice(&HEADER_SECTION.to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_read_r1cs_constraints() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.r1cs");
+        std::fs::write(&path, constraint_r1cs_bytes()).unwrap();
+
+        let mut constraints = read_r1cs_constraints(&path, 10).await.unwrap();
+        assert_eq!(constraints.len(), 1);
+        let constraint = &mut constraints[0];
+        assert_eq!(constraint.a[&1], BigInt::from(2));
+        assert_eq!(constraint.a[&2], BigInt::from(-1));
+        assert_eq!(constraint.to_string(), "(2*w1 - w2) * (w3) - (1) = 0");
+
+        constraint.names.insert(1, "main.a".to_string());
+        constraint.names.insert(2, "main.b".to_string());
+        constraint.names.insert(3, "main.c".to_string());
+        assert_eq!(
+            constraint.to_string(),
+            "(2*main.a - main.b) * (main.c) - (1) = 0"
+        );
+
+        assert!(read_r1cs_constraints(&path, 0).await.is_err());
+    }
+}
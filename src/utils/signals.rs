@@ -1,6 +1,10 @@
 //! Signal creation utilities
 
-use crate::types::{CircuitSignals, SignalValue};
+use crate::core::Circomkit;
+use crate::error::{CircomkitError, Result};
+use crate::types::{CircuitConfig, CircuitSignals, SignalValue};
+use num_bigint::{BigInt, Sign};
+use std::collections::BTreeMap;
 
 /// Create a circuit signals map from key-value pairs
 ///
@@ -37,9 +41,14 @@ pub fn signal_array<T: ToString>(values: &[T]) -> SignalValue {
 }
 
 /// Builder for creating circuit signals
+///
+/// A builder from [`SignalBuilder::for_circuit`] checks each signal against
+/// the circuit's declared inputs as it is added.
 #[derive(Debug, Default)]
 pub struct SignalBuilder {
     signals: CircuitSignals,
+    /// Array dimensions of each declared input, when checking signals
+    shapes: Option<BTreeMap<String, Vec<usize>>>,
 }
 
 impl SignalBuilder {
@@ -48,35 +57,87 @@ impl SignalBuilder {
         Self::default()
     }
 
+    /// Create a builder that checks signals against a compiled circuit
+    ///
+    /// Signal names must be inputs of the circuit, with as many elements as
+    /// declared. Fails with `CircuitNotFound` if the circuit isn't compiled.
+    pub async fn for_circuit(circomkit: &Circomkit, circuit: &CircuitConfig) -> Result<Self> {
+        Ok(Self {
+            signals: CircuitSignals::new(),
+            shapes: Some(circomkit.input_shapes(circuit).await?),
+        })
+    }
+
     /// Add a single numeric signal
-    pub fn add<T: ToString>(mut self, name: &str, value: T) -> Self {
-        self.signals
-            .insert(name.to_string(), SignalValue::Single(value.to_string()));
-        self
+    ///
+    /// # Panics
+    ///
+    /// If the builder checks signals and `name` isn't a scalar input; see
+    /// [`SignalBuilder::try_add`].
+    pub fn add<T: ToString>(self, name: &str, value: T) -> Self {
+        self.try_add(name, value)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Add an array signal
-    pub fn add_array<T: ToString>(mut self, name: &str, values: &[T]) -> Self {
-        self.signals.insert(name.to_string(), signal_array(values));
-        self
+    ///
+    /// # Panics
+    ///
+    /// If the builder checks signals and `name` isn't an input of this
+    /// width; see [`SignalBuilder::try_add_array`].
+    pub fn add_array<T: ToString>(self, name: &str, values: &[T]) -> Self {
+        self.try_add_array(name, values)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Add a 2D array signal
-    pub fn add_2d_array<T: ToString>(mut self, name: &str, values: &[Vec<T>]) -> Self {
-        let arr = SignalValue::Array(
-            values
-                .iter()
-                .map(|row| {
-                    SignalValue::Array(
-                        row.iter()
-                            .map(|v| SignalValue::Single(v.to_string()))
-                            .collect(),
-                    )
-                })
-                .collect(),
-        );
-        self.signals.insert(name.to_string(), arr);
-        self
+    ///
+    /// # Panics
+    ///
+    /// If the builder checks signals and `name` isn't an input of this
+    /// size; see [`SignalBuilder::try_add_2d_array`].
+    pub fn add_2d_array<T: ToString>(self, name: &str, values: &[Vec<T>]) -> Self {
+        self.try_add_2d_array(name, values)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Add a single numeric signal, failing if it doesn't match the circuit
+    pub fn try_add<T: ToString>(self, name: &str, value: T) -> Result<Self> {
+        self.insert(name, SignalValue::Single(value.to_string()))
+    }
+
+    /// Add an array signal, failing if it doesn't match the circuit
+    pub fn try_add_array<T: ToString>(self, name: &str, values: &[T]) -> Result<Self> {
+        self.insert(name, signal_array(values))
+    }
+
+    /// Add a 2D array signal, failing if it doesn't match the circuit
+    pub fn try_add_2d_array<T: ToString>(self, name: &str, values: &[Vec<T>]) -> Result<Self> {
+        let arr = SignalValue::Array(values.iter().map(|row| signal_array(row)).collect());
+        self.insert(name, arr)
+    }
+
+    /// Insert a signal after checking it against the declared inputs
+    ///
+    /// Like witness input validation, only the element count of arrays is
+    /// checked, so nested and flat arrays are both accepted.
+    fn insert(mut self, name: &str, value: SignalValue) -> Result<Self> {
+        if let Some(shapes) = &self.shapes {
+            let dims = shapes.get(name).ok_or_else(|| {
+                CircomkitError::InvalidSignals(format!("'{}' is not an input of the circuit", name))
+            })?;
+            let expected: usize = dims.iter().product();
+            let actual = value.num_elements();
+            if actual != expected {
+                return Err(CircomkitError::InvalidSignals(format!(
+                    "'{}' expects {} element(s), got {}",
+                    name, expected, actual
+                )));
+            }
+        }
+
+        self.signals.insert(name.to_string(), value);
+        Ok(self)
     }
 
     /// Build the circuit signals
@@ -111,33 +172,112 @@ macro_rules! signals {
 }
 
 /// Parse signals from a JSON string
-pub fn parse_signals(json: &str) -> Result<CircuitSignals, serde_json::Error> {
+pub fn parse_signals(json: &str) -> std::result::Result<CircuitSignals, serde_json::Error> {
     serde_json::from_str(json)
 }
 
+/// Convert a JSON object of signal values into circuit signals
+pub fn signals_from_json(value: serde_json::Value) -> Result<CircuitSignals> {
+    let serde_json::Value::Object(map) = value else {
+        return Err(CircomkitError::InvalidSignals(format!(
+            "inputs must be a JSON object, got {}",
+            value
+        )));
+    };
+
+    map.into_iter()
+        .map(|(name, value)| {
+            SignalValue::try_from(value)
+                .map(|v| (name.clone(), v))
+                .map_err(|e| CircomkitError::InvalidSignals(format!("'{}': {}", name, e)))
+        })
+        .collect()
+}
+
+/// Parse signals from the contents of an input file
+///
+/// The format is chosen by `extension`: `json`, `toml` (requires the `toml`
+/// feature), or `yaml`/`yml` (requires the `serde_yaml` feature).
+pub fn parse_inputs(content: &str, extension: &str) -> Result<CircuitSignals> {
+    match extension.to_ascii_lowercase().as_str() {
+        "json" => Ok(serde_json::from_str(content)?),
+        #[cfg(feature = "toml")]
+        "toml" => {
+            let value: serde_json::Value = toml::from_str(content)
+                .map_err(|e| CircomkitError::InvalidSignals(format!("Invalid TOML: {}", e)))?;
+            signals_from_json(value)
+        }
+        #[cfg(feature = "serde_yaml")]
+        "yaml" | "yml" => {
+            let value: serde_json::Value = serde_yaml::from_str(content)
+                .map_err(|e| CircomkitError::InvalidSignals(format!("Invalid YAML: {}", e)))?;
+            signals_from_json(value)
+        }
+        #[cfg(not(feature = "toml"))]
+        "toml" => Err(CircomkitError::InvalidSignals(
+            "TOML inputs require the `toml` feature".to_string(),
+        )),
+        #[cfg(not(feature = "serde_yaml"))]
+        "yaml" | "yml" => Err(CircomkitError::InvalidSignals(
+            "YAML inputs require the `serde_yaml` feature".to_string(),
+        )),
+        other => Err(CircomkitError::InvalidSignals(format!(
+            "Unsupported input file extension: {:?}",
+            other
+        ))),
+    }
+}
+
+/// Copy of `signals` with every leaf value as a decimal string
+///
+/// See [`SignalValue::normalize`]. Input files written this way have a
+/// uniform representation that snarkjs and the witness calculator accept.
+pub fn normalize_signals(signals: &CircuitSignals) -> CircuitSignals {
+    signals
+        .iter()
+        .map(|(name, value)| (name.clone(), value.normalize()))
+        .collect()
+}
+
 /// Serialize signals to a JSON string
-pub fn serialize_signals(signals: &CircuitSignals) -> Result<String, serde_json::Error> {
+pub fn serialize_signals(
+    signals: &CircuitSignals,
+) -> std::result::Result<String, serde_json::Error> {
     serde_json::to_string_pretty(signals)
 }
 
-/// Convert field element string to bytes (big-endian)
+/// Width in bytes of a serialized field element
+const FIELD_BYTES: usize = 32;
+
+/// Convert field element string to bytes (big-endian, left-padded to 32 bytes)
+///
+/// Accepts decimal strings and `0x`-prefixed hex strings. Returns an empty
+/// vector if the value cannot be parsed or is negative.
 pub fn field_to_bytes(value: &str) -> Vec<u8> {
-    // Handle hex strings
-    if value.starts_with("0x") {
-        return hex::decode(&value[2..]).unwrap_or_default();
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),
+        None => BigInt::parse_bytes(value.as_bytes(), 10),
+    };
+
+    let Some(n) = parsed else {
+        return Vec::new();
+    };
+
+    let (sign, bytes) = n.to_bytes_be();
+    if sign == Sign::Minus {
+        return Vec::new();
     }
 
-    // Handle decimal strings
-    // This is a simplified implementation
-    if let Ok(n) = value.parse::<u128>() {
-        return n.to_be_bytes().to_vec();
+    if bytes.len() >= FIELD_BYTES {
+        return bytes;
     }
 
-    // For larger numbers, we'd need a big integer library
-    Vec::new()
+    let mut padded = vec![0u8; FIELD_BYTES - bytes.len()];
+    padded.extend_from_slice(&bytes);
+    padded
 }
 
-/// Convert bytes to field element string
+/// Convert big-endian bytes to a decimal field element string
 pub fn bytes_to_field(bytes: &[u8]) -> String {
     if bytes.len() <= 16 {
         // Can fit in u128
@@ -145,8 +285,7 @@ pub fn bytes_to_field(bytes: &[u8]) -> String {
         padded[16 - bytes.len()..].copy_from_slice(bytes);
         u128::from_be_bytes(padded).to_string()
     } else {
-        // Return as hex for larger values
-        format!("0x{}", hex::encode(bytes))
+        BigInt::from_bytes_be(Sign::Plus, bytes).to_string()
     }
 }
 
@@ -173,6 +312,50 @@ mod tests {
         assert!(signals.contains_key("b"));
     }
 
+    #[test]
+    fn test_signals_from_json() {
+        let inputs = signals_from_json(serde_json::json!({"a": 3, "b": ["1", 2]})).unwrap();
+        assert_eq!(inputs["a"].as_string(), "3");
+        assert_eq!(inputs["b"].num_elements(), 2);
+
+        assert!(signals_from_json(serde_json::json!([1, 2])).is_err());
+        match signals_from_json(serde_json::json!({"a": true})) {
+            Err(CircomkitError::InvalidSignals(msg)) => assert!(msg.starts_with("'a'")),
+            other => panic!("Expected InvalidSignals, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_inputs() {
+        let inputs = parse_inputs(r#"{"a": "3", "b": ["1", "2"]}"#, "json").unwrap();
+        assert_eq!(inputs["b"].num_elements(), 2);
+        assert!(parse_inputs("a = 3", "txt").is_err());
+
+        #[cfg(feature = "toml")]
+        {
+            let inputs = parse_inputs("# fixture\na = 3\nb = [\"1\", 2]\n", "toml").unwrap();
+            assert_eq!(inputs["a"].as_string(), "3");
+            assert_eq!(inputs["b"].num_elements(), 2);
+        }
+        #[cfg(not(feature = "toml"))]
+        assert!(matches!(
+            parse_inputs("a = 3", "toml"),
+            Err(CircomkitError::InvalidSignals(_))
+        ));
+
+        #[cfg(feature = "serde_yaml")]
+        {
+            let inputs = parse_inputs("a: 3\nb: [\"1\", 2]\n", "yml").unwrap();
+            assert_eq!(inputs["a"].as_string(), "3");
+            assert_eq!(inputs["b"].num_elements(), 2);
+        }
+        #[cfg(not(feature = "serde_yaml"))]
+        assert!(matches!(
+            parse_inputs("a: 3", "yaml"),
+            Err(CircomkitError::InvalidSignals(_))
+        ));
+    }
+
     #[test]
     fn test_signal_builder() {
         let signals = SignalBuilder::new()
@@ -187,6 +370,52 @@ mod tests {
         assert!(signals.contains_key("arr"));
     }
 
+    #[test]
+    fn test_signal_builder_checks_shapes() {
+        let builder = || SignalBuilder {
+            signals: CircuitSignals::new(),
+            shapes: Some(BTreeMap::from([
+                ("a".to_string(), vec![]),
+                ("m".to_string(), vec![2, 2]),
+            ])),
+        };
+
+        let signals = builder()
+            .add("a", 1)
+            .add_2d_array("m", &[vec![1, 2], vec![3, 4]])
+            .build();
+        assert_eq!(signals.len(), 2);
+        // Flat arrays of the right size are accepted
+        assert!(builder().try_add_array("m", &[1, 2, 3, 4]).is_ok());
+
+        let err = builder().try_add("typo", 1).unwrap_err().to_string();
+        assert!(err.contains("'typo' is not an input"));
+        let err = builder().try_add_array("m", &[1, 2, 3]).unwrap_err();
+        assert!(err.to_string().contains("'m' expects 4 element(s), got 3"));
+        assert!(builder().try_add_array("a", &[1, 2]).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "'m' expects 4 element(s), got 1")]
+    fn test_signal_builder_panics_on_mismatch() {
+        let builder = SignalBuilder {
+            signals: CircuitSignals::new(),
+            shapes: Some(BTreeMap::from([("m".to_string(), vec![4])])),
+        };
+        builder.add("m", 1);
+    }
+
+    #[tokio::test]
+    async fn test_signal_builder_for_uncompiled_circuit() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::core::CircomkitConfig::default().with_build_dir(dir.path());
+        let circomkit = Circomkit::new(config).unwrap();
+        assert!(matches!(
+            SignalBuilder::for_circuit(&circomkit, &CircuitConfig::new("test")).await,
+            Err(CircomkitError::CircuitNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_signal_array() {
         let arr = signal_array(&[1, 2, 3]);
@@ -203,4 +432,32 @@ mod tests {
         let back = bytes_to_field(&bytes);
         assert_eq!(back, "12345");
     }
+
+    #[test]
+    fn test_field_conversions_near_modulus() {
+        // BN128 scalar field modulus and its neighbours
+        let values = [
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616",
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            "21888242871839275222246405745257275088548364400416034343698204186575808495618",
+            "340282366920938463463374607431768211456", // 2^128
+        ];
+
+        for value in values {
+            let bytes = field_to_bytes(value);
+            assert_eq!(bytes.len(), 32);
+            assert_eq!(bytes_to_field(&bytes), value);
+        }
+    }
+
+    #[test]
+    fn test_field_to_bytes_hex_and_invalid() {
+        let bytes = field_to_bytes("0xff");
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(bytes[31], 0xff);
+        assert_eq!(bytes_to_field(&bytes), "255");
+
+        assert!(field_to_bytes("not a number").is_empty());
+        assert!(field_to_bytes("-1").is_empty());
+    }
 }
@@ -0,0 +1,229 @@
+//! Symbol file utilities
+
+use crate::error::{CircomkitError, Result};
+use crate::utils::R1csHeader;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use tokio::fs;
+
+/// A signal entry of a circom `.sym` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Signal (label) index
+    pub index: usize,
+    /// Witness wire id, `None` if the signal was optimized away
+    pub wire: Option<u32>,
+    /// Index of the component the signal belongs to
+    pub component: usize,
+    /// Fully qualified name, e.g. `main.bits.out[3]`
+    pub name: String,
+}
+
+impl Symbol {
+    /// Name relative to the main component, e.g. `bits.out[3]`
+    ///
+    /// The main component is the first segment of the qualified name,
+    /// whatever the instance is called.
+    pub fn signal_name(&self) -> &str {
+        self.name
+            .split_once('.')
+            .map_or(self.name.as_str(), |(_, signal)| signal)
+    }
+
+    /// Whether this is a signal of the main component itself rather than
+    /// of a subcomponent
+    pub fn is_main_signal(&self) -> bool {
+        self.name
+            .split_once('.')
+            .is_some_and(|(_, signal)| !signal.contains('.'))
+    }
+
+    /// Split the signal name into its base name and array indices,
+    /// e.g. `c[1][0]` into `("c", [1, 0])`
+    pub fn base_name_and_indices(&self) -> (&str, Vec<usize>) {
+        let signal = self.signal_name();
+        match signal.split_once('[') {
+            Some((name, rest)) => {
+                let indices = rest
+                    .split('[')
+                    .filter_map(|i| i.trim_end_matches(']').parse().ok())
+                    .collect();
+                (name, indices)
+            }
+            None => (signal, Vec::new()),
+        }
+    }
+}
+
+/// Parsed `.sym` file, keyed by signal index
+///
+/// Each line of the file has the form `index,wire,component,name`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    symbols: BTreeMap<usize, Symbol>,
+}
+
+impl SymbolTable {
+    /// Parse the contents of a `.sym` file
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut symbols = BTreeMap::new();
+
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let invalid = || {
+                CircomkitError::Other(format!(
+                    "Invalid sym entry on line {}: {}",
+                    line_no + 1,
+                    line
+                ))
+            };
+
+            let mut parts = line.splitn(4, ',');
+            let mut field = || parts.next().ok_or_else(invalid);
+            let index = field()?.parse().map_err(|_| invalid())?;
+            let wire: i64 = field()?.parse().map_err(|_| invalid())?;
+            let component = field()?.parse().map_err(|_| invalid())?;
+            let name = field()?.to_string();
+
+            symbols.insert(
+                index,
+                Symbol {
+                    index,
+                    wire: u32::try_from(wire).ok(),
+                    component,
+                    name,
+                },
+            );
+        }
+
+        Ok(Self { symbols })
+    }
+
+    /// Look up a signal by its index
+    pub fn lookup(&self, index: usize) -> Option<&Symbol> {
+        self.symbols.get(&index)
+    }
+
+    /// Look up a signal by its fully qualified name
+    pub fn by_name(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.values().find(|s| s.name == name)
+    }
+
+    /// All signals in index order
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values()
+    }
+
+    /// Number of signals
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Whether the table has no signals
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Input signals of the main component (public then private)
+    ///
+    /// The `.sym` file doesn't record signal direction, so wire ranges are
+    /// taken from the circuit's r1cs header.
+    pub fn inputs(&self, header: &R1csHeader) -> Vec<&Symbol> {
+        self.main_signals_on(header.input_wires())
+    }
+
+    /// Public input signals of the main component
+    pub fn public_inputs(&self, header: &R1csHeader) -> Vec<&Symbol> {
+        self.main_signals_on(header.public_input_wires())
+    }
+
+    /// Output signals of the main component
+    pub fn outputs(&self, header: &R1csHeader) -> Vec<&Symbol> {
+        self.main_signals_on(header.output_wires())
+    }
+
+    /// Main component signals whose wire falls in `wires`
+    ///
+    /// Subcomponent signals can share a wire with a main signal and are
+    /// excluded.
+    fn main_signals_on(&self, wires: Range<u32>) -> Vec<&Symbol> {
+        self.iter()
+            .filter(|s| s.is_main_signal())
+            .filter(|s| s.wire.is_some_and(|w| wires.contains(&w)))
+            .collect()
+    }
+}
+
+/// Read and parse a `.sym` file
+pub async fn read_symbol_table(path: &Path) -> Result<SymbolTable> {
+    if !path.exists() {
+        return Err(CircomkitError::CircuitNotFound(path.to_path_buf()));
+    }
+
+    let content = fs::read_to_string(path).await?;
+    SymbolTable::parse(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    const SYM: &str = "1,1,1,main.out\n\
+                       2,2,1,main.c[0]\n\
+                       3,3,1,main.c[1]\n\
+                       4,4,1,main.s\n\
+                       5,-1,1,main.tmp\n\
+                       6,2,0,main.mux.c[0]\n";
+
+    fn header() -> R1csHeader {
+        R1csHeader {
+            field_size: 32,
+            prime: BigUint::from(0u8),
+            n_wires: 5,
+            n_pub_out: 1,
+            n_pub_in: 0,
+            n_prv_in: 3,
+            n_labels: 6,
+            n_constraints: 1,
+        }
+    }
+
+    #[test]
+    fn test_symbol_table() {
+        let table = SymbolTable::parse(SYM).unwrap();
+        assert_eq!(table.len(), 6);
+
+        let tmp = table.lookup(5).unwrap();
+        assert_eq!(tmp.wire, None);
+        assert_eq!(tmp.signal_name(), "tmp");
+
+        let sub = table.by_name("main.mux.c[0]").unwrap();
+        assert!(!sub.is_main_signal());
+        assert_eq!(
+            table.lookup(2).unwrap().base_name_and_indices(),
+            ("c", vec![0])
+        );
+
+        let names = |symbols: Vec<&Symbol>| {
+            symbols
+                .iter()
+                .map(|s| s.signal_name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(table.outputs(&header())), ["out"]);
+        assert_eq!(names(table.inputs(&header())), ["c[0]", "c[1]", "s"]);
+
+        assert!(SymbolTable::parse("1,x,1,main.out").is_err());
+
+        // Names are relative to whatever the top-level instance is called
+        let renamed = SymbolTable::parse(&SYM.replace("main.", "top.")).unwrap();
+        assert_eq!(names(renamed.outputs(&header())), ["out"]);
+        assert!(!renamed.by_name("top.mux.c[0]").unwrap().is_main_signal());
+        assert!(SymbolTable::parse("1,1").is_err());
+    }
+}